@@ -0,0 +1,38 @@
+#ifndef SOLVER_OF_SQUARES_H
+#define SOLVER_OF_SQUARES_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct SosGame SosGame;
+
+typedef struct SosSolution {
+    bool solved;
+    bool timed_out;
+    size_t move_count;
+    /* move_count NUL-terminated block colors, in order. */
+    char **moves;
+    size_t expanded;
+} SosSolution;
+
+/* Parses a puzzle from NUL-terminated YAML. Returns NULL if it is not a valid puzzle. */
+SosGame *sos_parse(const char *yaml);
+
+void sos_free_game(SosGame *game);
+
+/* A timeout of zero or less, infinite, NaN, or too large to represent means no timeout.
+   Returns NULL if game is NULL. */
+SosSolution *sos_solve(const SosGame *game, int32_t max_moves, double timeout_seconds);
+
+void sos_free_solution(SosSolution *solution);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,433 @@
+use crate::search::State;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, VecDeque};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Analysis {
+    pub states: usize,
+    // False when the depth or state limit stopped the enumeration before it ran out of states.
+    pub complete: bool,
+    pub goals: usize,
+    // States that cannot reach a goal, even through states that were not expanded.
+    pub dead_ends: usize,
+    // States that only lead to goals, if at all, through states beyond the limits.
+    pub unknown: usize,
+    // Number of states at each distance (in moves) from the nearest goal.
+    pub distance_to_goal: BTreeMap<usize, usize>,
+    // Number of states first reached at each depth from the start.
+    pub depth: BTreeMap<usize, usize>,
+}
+
+// The states reachable from a start, numbered in the order they were first reached, with the
+// moves out of each expanded one and what each move cost.
+struct Graph<C> {
+    edges: Vec<Vec<(usize, C)>>,
+    is_goal: Vec<bool>,
+    expanded: Vec<bool>,
+    depths: Vec<usize>,
+    // False when the depth or state limit stopped the enumeration before it ran out of states.
+    complete: bool,
+}
+
+// Enumerates every state reachable from `initial_state` breadth-first, up to `max_depth` moves
+// and `max_states` states, handing each state to `visit` with its number once it is done with.
+fn explore<T: State>(
+    initial_state: T,
+    max_depth: usize,
+    max_states: usize,
+    mut visit: impl FnMut(usize, T),
+) -> Graph<T::Cost> {
+    let mut index = HashMap::new();
+    let mut graph = Graph {
+        edges: vec![vec![]],
+        is_goal: vec![initial_state.is_goal()],
+        expanded: vec![false],
+        depths: vec![0],
+        complete: true,
+    };
+    let mut queue = VecDeque::new();
+
+    index.insert(initial_state.canonical_hash(), 0);
+    queue.push_back((initial_state, 0));
+
+    while let Some((state, id)) = queue.pop_front() {
+        if graph.depths[id] >= max_depth {
+            graph.complete = false;
+            visit(id, state);
+            continue;
+        }
+        graph.expanded[id] = true;
+
+        for successor in state.successors() {
+            let hash = successor.canonical_hash();
+            let step = successor.cost() - state.cost();
+            let next = match index.get(&hash) {
+                Some(&next) => next,
+                None if index.len() >= max_states => {
+                    graph.complete = false;
+                    graph.expanded[id] = false;
+                    continue;
+                }
+                None => {
+                    let next = index.len();
+                    index.insert(hash, next);
+                    graph.edges.push(vec![]);
+                    graph.is_goal.push(successor.is_goal());
+                    graph.expanded.push(false);
+                    graph.depths.push(graph.depths[id] + 1);
+                    queue.push_back((successor, next));
+                    next
+                }
+            };
+            graph.edges[id].push((next, step));
+        }
+        visit(id, state);
+    }
+
+    graph
+}
+
+// Enumerates every state reachable from `initial_state` breadth-first, up to `max_depth` moves
+// and `max_states` states, then works backwards from the goals over the moves found.
+pub fn analyze<T: State>(initial_state: T, max_depth: usize, max_states: usize) -> Analysis {
+    let Graph {
+        edges,
+        is_goal,
+        expanded,
+        depths,
+        complete,
+    } = explore(initial_state, max_depth, max_states, |_, _| {});
+
+    let mut reverse = vec![vec![]; edges.len()];
+    for (from, targets) in edges.iter().enumerate() {
+        for (to, _) in targets {
+            reverse[*to].push(from);
+        }
+    }
+    let backwards = |starts: Vec<usize>| {
+        let mut distance = vec![None; edges.len()];
+        let mut queue = VecDeque::new();
+        for start in starts {
+            distance[start] = Some(0);
+            queue.push_back(start);
+        }
+        while let Some(id) = queue.pop_front() {
+            let next = distance[id].map(|d: usize| d + 1);
+            for &from in &reverse[id] {
+                if distance[from].is_none() {
+                    distance[from] = next;
+                    queue.push_back(from);
+                }
+            }
+        }
+        distance
+    };
+
+    let to_goal = backwards((0..edges.len()).filter(|&id| is_goal[id]).collect());
+    let to_frontier = backwards((0..edges.len()).filter(|&id| !expanded[id]).collect());
+
+    let mut analysis = Analysis {
+        states: edges.len(),
+        complete,
+        goals: is_goal.iter().filter(|goal| **goal).count(),
+        ..Analysis::default()
+    };
+    for id in 0..edges.len() {
+        *analysis.depth.entry(depths[id]).or_default() += 1;
+        match (to_goal[id], to_frontier[id]) {
+            (Some(distance), _) => *analysis.distance_to_goal.entry(distance).or_default() += 1,
+            (None, Some(_)) => analysis.unknown += 1,
+            (None, None) => analysis.dead_ends += 1,
+        }
+    }
+    analysis
+}
+
+/// How a state's heuristic estimate compares with the cheapest cost from it to a goal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Overestimate<T> {
+    pub state: T,
+    pub estimate: i32,
+    pub actual: i32,
+}
+
+/// A move across which the heuristic estimate drops by more than the move costs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Inconsistency<T> {
+    pub state: T,
+    pub successor: T,
+    pub estimate: i32,
+    pub successor_estimate: i32,
+    pub step: i32,
+}
+
+/// What [`check_heuristic`] found: how often the heuristic overestimates the cost to a goal,
+/// which makes the searches that rely on it miss shorter solutions, and how often it drops by
+/// more than a move costs, which makes A* reach states again by cheaper paths.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeuristicCheck<T> {
+    pub states: usize,
+    // False when the depth or state limit stopped the enumeration before it ran out of states.
+    pub complete: bool,
+    // States whose cheapest cost to a goal is known exactly, the only ones checked for
+    // overestimates. A state is left out when a cheaper path could go through states the
+    // enumeration did not expand.
+    pub exact: usize,
+    pub overestimates: usize,
+    // The state the heuristic overestimates by the most.
+    pub worst_overestimate: Option<Overestimate<T>>,
+    pub moves: usize,
+    pub inconsistencies: usize,
+    // The move across which the estimate drops the most beyond its cost.
+    pub worst_inconsistency: Option<Inconsistency<T>>,
+}
+
+impl<T> HeuristicCheck<T> {
+    /// No checked state was overestimated.
+    pub fn admissible(&self) -> bool {
+        self.overestimates == 0
+    }
+
+    /// No move checked dropped the estimate by more than it cost.
+    pub fn consistent(&self) -> bool {
+        self.inconsistencies == 0
+    }
+
+    /// The same report with each example state replaced by `f` of it.
+    pub fn map<U>(self, f: impl Fn(T) -> U) -> HeuristicCheck<U> {
+        HeuristicCheck {
+            states: self.states,
+            complete: self.complete,
+            exact: self.exact,
+            overestimates: self.overestimates,
+            worst_overestimate: self.worst_overestimate.map(|worst| Overestimate {
+                state: f(worst.state),
+                estimate: worst.estimate,
+                actual: worst.actual,
+            }),
+            moves: self.moves,
+            inconsistencies: self.inconsistencies,
+            worst_inconsistency: self.worst_inconsistency.map(|worst| Inconsistency {
+                state: f(worst.state),
+                successor: f(worst.successor),
+                estimate: worst.estimate,
+                successor_estimate: worst.successor_estimate,
+                step: worst.step,
+            }),
+        }
+    }
+}
+
+// The cheapest cost from each state to one of `starts` over the reversed moves in `reverse`, or
+// `None` if there is no way.
+fn cheapest_to(
+    reverse: &[Vec<(usize, i32)>],
+    starts: impl Iterator<Item = usize>,
+) -> Vec<Option<i32>> {
+    let mut cost = vec![None; reverse.len()];
+    let mut queue = BinaryHeap::new();
+    for start in starts {
+        cost[start] = Some(0);
+        queue.push(Reverse((0, start)));
+    }
+    while let Some(Reverse((so_far, id))) = queue.pop() {
+        if cost[id].is_some_and(|known| known < so_far) {
+            continue;
+        }
+        for &(from, step) in &reverse[id] {
+            let through = so_far + step;
+            if cost[from].is_none_or(|known| through < known) {
+                cost[from] = Some(through);
+                queue.push(Reverse((through, from)));
+            }
+        }
+    }
+    cost
+}
+
+/// Enumerates the states reachable from `initial_state` as [`analyze`] does, works out the
+/// cheapest cost from each one to a goal, and compares it with [`State::distance_to_goal`].
+/// Flags states the heuristic overestimates and moves across which it is not consistent, so a
+/// heuristic can be trusted on small puzzles before it is used to claim a solution is optimal.
+pub fn check_heuristic<T: State<Cost = i32> + Clone>(
+    initial_state: T,
+    max_depth: usize,
+    max_states: usize,
+) -> HeuristicCheck<T> {
+    let mut states = vec![];
+    let graph = explore(initial_state, max_depth, max_states, |id, state| {
+        if states.len() <= id {
+            states.resize_with(id + 1, || None);
+        }
+        states[id] = Some(state);
+    });
+    let states: Vec<T> = states.into_iter().flatten().collect();
+    let estimates: Vec<i32> = states.iter().map(State::distance_to_goal).collect();
+
+    let mut reverse = vec![vec![]; graph.edges.len()];
+    for (from, targets) in graph.edges.iter().enumerate() {
+        for &(to, step) in targets {
+            reverse[to].push((from, step));
+        }
+    }
+    let to_goal = cheapest_to(&reverse, (0..states.len()).filter(|&id| graph.is_goal[id]));
+    let to_frontier = cheapest_to(
+        &reverse,
+        (0..states.len()).filter(|&id| !graph.expanded[id]),
+    );
+
+    let mut check = HeuristicCheck {
+        states: states.len(),
+        complete: graph.complete,
+        exact: 0,
+        overestimates: 0,
+        worst_overestimate: None,
+        moves: 0,
+        inconsistencies: 0,
+        worst_inconsistency: None,
+    };
+    let mut worst = 0;
+    for id in 0..states.len() {
+        // Any path that leaves the expanded states costs at least as much as reaching the first
+        // state it leaves them at, so a cost to a goal no higher than that is the cheapest.
+        let Some(actual) = to_goal[id] else {
+            continue;
+        };
+        if to_frontier[id].is_some_and(|frontier| frontier < actual) {
+            continue;
+        }
+        check.exact += 1;
+        if estimates[id] > actual {
+            check.overestimates += 1;
+            if estimates[id] - actual > worst {
+                worst = estimates[id] - actual;
+                check.worst_overestimate = Some(Overestimate {
+                    state: states[id].clone(),
+                    estimate: estimates[id],
+                    actual,
+                });
+            }
+        }
+    }
+
+    let mut worst = 0;
+    for (from, targets) in graph.edges.iter().enumerate() {
+        for &(to, step) in targets {
+            check.moves += 1;
+            let excess = estimates[from] - step - estimates[to];
+            if excess > 0 {
+                check.inconsistencies += 1;
+                if excess > worst {
+                    worst = excess;
+                    check.worst_inconsistency = Some(Inconsistency {
+                        state: states[from].clone(),
+                        successor: states[to].clone(),
+                        estimate: estimates[from],
+                        successor_estimate: estimates[to],
+                        step,
+                    });
+                }
+            }
+        }
+    }
+    check
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hash::Hash;
+
+    // A walk along 0..=4 where 4 is the goal and 0 is a trap with no way out.
+    #[derive(Hash)]
+    struct Walk(i32);
+
+    impl State for Walk {
+        type Cost = i32;
+
+        fn successors(&self) -> Vec<Self> {
+            match self.0 {
+                0 | 4 => vec![],
+                n => vec![Walk(n - 1), Walk(n + 1)],
+            }
+        }
+        fn is_goal(&self) -> bool {
+            self.0 == 4
+        }
+        fn distance_to_goal(&self) -> i32 {
+            4 - self.0
+        }
+        fn cost(&self) -> i32 {
+            0
+        }
+    }
+
+    #[test]
+    fn test_analyze_counts_dead_ends_and_distances() {
+        let analysis = analyze(Walk(2), 10, 100);
+        assert!(analysis.complete);
+        assert_eq!((analysis.states, analysis.goals), (5, 1));
+        assert_eq!((analysis.dead_ends, analysis.unknown), (1, 0));
+        assert_eq!(
+            analysis.distance_to_goal,
+            BTreeMap::from([(0, 1), (1, 1), (2, 1), (3, 1)])
+        );
+
+        let limited = analyze(Walk(2), 1, 100);
+        assert!(!limited.complete);
+        assert_eq!(
+            (limited.states, limited.dead_ends, limited.unknown),
+            (3, 0, 3)
+        );
+    }
+
+    // The same walk with an estimate that is too high two steps from the goal.
+    #[derive(Clone, Hash)]
+    struct Pessimist(i32, i32);
+
+    impl State for Pessimist {
+        type Cost = i32;
+
+        fn successors(&self) -> Vec<Self> {
+            match self.0 {
+                0 | 4 => vec![],
+                n => vec![Pessimist(n - 1, self.1 + 1), Pessimist(n + 1, self.1 + 1)],
+            }
+        }
+        fn is_goal(&self) -> bool {
+            self.0 == 4
+        }
+        fn distance_to_goal(&self) -> i32 {
+            if self.0 == 2 {
+                5
+            } else {
+                4 - self.0
+            }
+        }
+        fn cost(&self) -> i32 {
+            self.1
+        }
+        fn canonical_hash(&self) -> u64 {
+            crate::search::hash(&self.0)
+        }
+    }
+
+    #[test]
+    fn test_check_heuristic_flags_overestimates_and_inconsistent_moves() {
+        let check = check_heuristic(Pessimist(2, 0), 10, 100);
+        assert!(check.complete);
+        assert_eq!((check.states, check.exact), (5, 4));
+        assert_eq!(check.overestimates, 1);
+        let worst = check.worst_overestimate.as_ref().unwrap();
+        assert_eq!((worst.state.0, worst.estimate, worst.actual), (2, 5, 2));
+        assert_eq!(check.inconsistencies, 2);
+        let worst = check.worst_inconsistency.as_ref().unwrap();
+        assert_eq!((worst.state.0, worst.successor.0), (2, 3));
+        assert!(!check.admissible() && !check.consistent());
+
+        let limited = check_heuristic(Pessimist(2, 0), 1, 100);
+        assert!(!limited.complete);
+        assert_eq!(limited.exact, 0);
+        assert!(limited.admissible());
+    }
+}
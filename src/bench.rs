@@ -0,0 +1,210 @@
+use solver_of_squares::game::Game;
+use solver_of_squares::solver::{Algorithm, SolverOptions};
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// One algorithm to compare, as written on the command line: a name from
+/// [`Algorithm`], `idastar`, `greedy`, or `weighted:W` for A* with the heuristic weighted by W.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Configuration {
+    pub label: String,
+    algorithm: Algorithm,
+    weight: Option<f64>,
+}
+
+impl Configuration {
+    fn options(&self, max_moves: i32, timeout: Option<Duration>) -> SolverOptions {
+        let mut options = SolverOptions::new(max_moves).algorithm(self.algorithm);
+        options.timeout = timeout;
+        // Greedy is the same A* that `--portfolio` runs, weighted so the moves only break ties.
+        let weight = self
+            .weight
+            .unwrap_or_else(|| f64::from(max_moves.max(0)) + 1.0);
+        options.weight(weight)
+    }
+}
+
+impl FromStr for Configuration {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (algorithm, weight) = match s.split_once(':') {
+            Some(("weighted", weight)) => {
+                let weight: f64 = weight
+                    .parse()
+                    .map_err(|_| format!("invalid weight in `{}`", s))?;
+                (Algorithm::AStar, Some(weight))
+            }
+            Some(_) => return Err(format!("unknown algorithm `{}`", s)),
+            None if s == "greedy" => (Algorithm::AStar, None),
+            None if s == "idastar" => (Algorithm::IdaStar, Some(1.0)),
+            None => (s.parse()?, Some(1.0)),
+        };
+        Ok(Configuration {
+            label: s.to_string(),
+            algorithm,
+            weight,
+        })
+    }
+}
+
+/// How one configuration did on one puzzle.
+#[derive(Debug)]
+pub struct Run {
+    pub puzzle: String,
+    pub label: String,
+    pub moves: Option<usize>,
+    pub millis: f64,
+    pub expanded: usize,
+    pub generated: usize,
+    pub timed_out: bool,
+}
+
+/// Runs every configuration on every puzzle, one at a time so the timings do not compete for
+/// cores.
+pub fn run(
+    puzzles: &[(String, Game)],
+    configurations: &[Configuration],
+    max_moves: i32,
+    timeout: Option<Duration>,
+) -> Vec<Run> {
+    let mut runs = vec![];
+    for (puzzle, game) in puzzles {
+        for configuration in configurations {
+            let options = configuration.options(max_moves, timeout);
+            let start = Instant::now();
+            let (moves, stats) = game.solve_with(&options);
+            runs.push(Run {
+                puzzle: puzzle.clone(),
+                label: configuration.label.clone(),
+                moves: moves.map(|moves| moves.len()),
+                millis: start.elapsed().as_secs_f64() * 1000.0,
+                expanded: stats.expanded,
+                generated: stats.generated,
+                timed_out: stats.timed_out,
+            });
+        }
+    }
+    runs
+}
+
+/// A row per run, then the totals for each configuration over the puzzles it solved.
+pub fn table(runs: &[Run], configurations: &[Configuration]) -> String {
+    let width = runs
+        .iter()
+        .map(|run| run.puzzle.len())
+        .max()
+        .unwrap_or(0)
+        .max(6);
+    let label_width = configurations
+        .iter()
+        .map(|configuration| configuration.label.len())
+        .max()
+        .unwrap_or(0)
+        .max(9);
+
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "{:<width$}  {:<label_width$}  {:>6}  {:>10}  {:>10}",
+        "puzzle", "algorithm", "moves", "time (ms)", "nodes"
+    );
+    for run in runs {
+        let moves = match run.moves {
+            Some(moves) => moves.to_string(),
+            None if run.timed_out => "timeout".to_string(),
+            None => "-".to_string(),
+        };
+        let _ = writeln!(
+            output,
+            "{:<width$}  {:<label_width$}  {:>6}  {:>10.1}  {:>10}",
+            run.puzzle, run.label, moves, run.millis, run.expanded
+        );
+    }
+
+    let _ = writeln!(output);
+    for configuration in configurations {
+        let mine = || runs.iter().filter(|run| run.label == configuration.label);
+        let solved: Vec<&Run> = mine().filter(|run| run.moves.is_some()).collect();
+        let _ = writeln!(
+            output,
+            "{:<label_width$}  solved {}/{}, {} moves, {:.1} ms, {} nodes",
+            configuration.label,
+            solved.len(),
+            mine().count(),
+            solved.iter().filter_map(|run| run.moves).sum::<usize>(),
+            solved.iter().map(|run| run.millis).sum::<f64>(),
+            solved.iter().map(|run| run.expanded).sum::<usize>()
+        );
+    }
+    output
+}
+
+/// The runs as CSV, with an empty `moves` field when there was no solution.
+pub fn csv(runs: &[Run]) -> String {
+    let mut output = "puzzle,algorithm,moves,time_ms,expanded,generated,timed_out\n".to_string();
+    for run in runs {
+        let _ = writeln!(
+            output,
+            "{},{},{},{:.3},{},{},{}",
+            csv_field(&run.puzzle),
+            csv_field(&run.label),
+            run.moves.map(|moves| moves.to_string()).unwrap_or_default(),
+            run.millis,
+            run.expanded,
+            run.generated,
+            run.timed_out
+        );
+    }
+    output
+}
+
+/// `field` quoted for CSV if it needs to be.
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configurations_parse_and_runs_format() {
+        let weighted: Configuration = "weighted:1.5".parse().unwrap();
+        assert_eq!(weighted.options(20, None).weight, 1.5);
+        assert_eq!(
+            "idastar".parse::<Configuration>().unwrap().algorithm,
+            Algorithm::IdaStar
+        );
+        assert_eq!(
+            "greedy"
+                .parse::<Configuration>()
+                .unwrap()
+                .options(20, None)
+                .weight,
+            21.0
+        );
+        assert!("weighted:heavy".parse::<Configuration>().is_err());
+        assert!("bogus".parse::<Configuration>().is_err());
+
+        let game: Game = serde_yaml::from_str(
+            "blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n    goal: [2, 0]\n",
+        )
+        .unwrap();
+        let configurations = vec![weighted, "astar".parse().unwrap()];
+        let runs = run(&[("a, b.yaml".to_string(), game)], &configurations, 1, None);
+        assert_eq!(runs.len(), 2);
+        assert!(runs.iter().all(|run| run.moves.is_none()));
+        assert!(table(&runs, &configurations).contains("solved 0/1"));
+        assert!(csv(&runs)
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("\"a, b.yaml\",weighted:1.5,,"));
+    }
+}
@@ -0,0 +1,67 @@
+//! Positions proven hopeless by earlier searches, kept between runs.
+//!
+//! A breadth-first search expands every position at the fewest moves it can be reached in, so
+//! when it finishes it has proven a lower bound on the moves each expanded position still needs.
+//! [`Game::solve_cached`](crate::game::Game::solve_cached) records those bounds, and any later
+//! search of the same puzzle uses them to skip positions that cannot finish within its bound.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Lower bounds on the moves still needed, by puzzle and then by position. Puzzles are keyed by
+/// [`Game::cache_key`](crate::game::Game::cache_key), so a puzzle that has only been moved
+/// shares its entries.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DeadEndCache {
+    puzzles: HashMap<u64, HashMap<u64, i32>>,
+}
+
+impl DeadEndCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The bounds known for a puzzle, keyed by position hash.
+    pub fn bounds(&self, puzzle: u64) -> Option<&HashMap<u64, i32>> {
+        self.puzzles.get(&puzzle)
+    }
+
+    /// Adds bounds for a puzzle, keeping the higher bound where a position is already known.
+    pub fn record(&mut self, puzzle: u64, bounds: HashMap<u64, i32>) {
+        let known = self.puzzles.entry(puzzle).or_default();
+        for (position, needed) in bounds {
+            let entry = known.entry(position).or_insert(needed);
+            *entry = (*entry).max(needed);
+        }
+    }
+
+    /// The number of positions with a known bound, over all puzzles.
+    pub fn len(&self) -> usize {
+        self.puzzles.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_keeps_the_higher_bound() {
+        let mut cache = DeadEndCache::new();
+        cache.record(1, HashMap::from([(10, 3), (11, 5)]));
+        cache.record(1, HashMap::from([(10, 4), (11, 2)]));
+        cache.record(2, HashMap::from([(10, 1)]));
+
+        assert_eq!(cache.bounds(1), Some(&HashMap::from([(10, 4), (11, 5)])));
+        assert_eq!(cache.len(), 3);
+        assert!(cache.bounds(3).is_none());
+
+        let json = serde_json::to_string(&cache).unwrap();
+        let loaded: DeadEndCache = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.bounds(2), cache.bounds(2));
+    }
+}
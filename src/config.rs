@@ -0,0 +1,126 @@
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+use solver_of_squares::fifteen::Heuristic;
+use solver_of_squares::search::TieBreak;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How `solve` searches when neither `--optimal` nor a conflicting flag is given.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    Astar,
+    Optimal,
+}
+
+/// What `solve` prints after the moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Plain,
+    Detailed,
+    Notation,
+    Diff,
+}
+
+/// Defaults read from `config.toml`. Every key is optional, and flags given on the command line
+/// take precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub max_moves: Option<i32>,
+    #[serde(deserialize_with = "timeout")]
+    pub timeout: Option<Duration>,
+    pub algorithm: Option<Algorithm>,
+    #[serde(deserialize_with = "from_str")]
+    pub tie_break: Option<TieBreak>,
+    #[serde(deserialize_with = "from_str")]
+    pub heuristic: Option<Heuristic>,
+    pub threads: Option<usize>,
+    pub format: Option<Format>,
+}
+
+fn from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err = String>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|text| text.parse().map_err(de::Error::custom))
+        .transpose()
+}
+
+fn timeout<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<f64>::deserialize(deserializer)?
+        .map(|seconds| seconds_to_duration(seconds).map_err(de::Error::custom))
+        .transpose()
+}
+
+/// Checks a timeout given in seconds, on the command line or in the config file.
+pub fn seconds_to_duration(seconds: f64) -> Result<Duration, String> {
+    match Duration::try_from_secs_f64(seconds) {
+        Ok(duration) if !duration.is_zero() => Ok(duration),
+        _ => Err("timeout must be a positive number of seconds".to_string()),
+    }
+}
+
+/// `$XDG_CONFIG_HOME/solver-of-squares/config.toml`, falling back to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|base| !base.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("solver-of-squares").join("config.toml"))
+}
+
+/// Reads the file given with `--config`, which must exist, or else the default file if there is
+/// one.
+pub fn load(path: Option<&Path>) -> Result<Config, String> {
+    let path = match (path, default_path()) {
+        (Some(path), _) => path.to_path_buf(),
+        (None, Some(path)) if path.is_file() => path,
+        (None, _) => return Ok(Config::default()),
+    };
+
+    let text = fs::read_to_string(&path)
+        .map_err(|error| format!("could not read {}: {}", path.display(), error))?;
+    parse(&text).map_err(|error| format!("could not parse {}: {}", path.display(), error))
+}
+
+pub fn parse(text: &str) -> Result<Config, toml::de::Error> {
+    toml::from_str(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reads_every_key_and_rejects_unknown_ones() {
+        let config = parse(
+            "max_moves = 80\ntimeout = 2.5\nalgorithm = \"optimal\"\ntie_break = \"higher-cost\"\n\
+             heuristic = \"misplaced\"\nthreads = 4\nformat = \"notation\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.max_moves, Some(80));
+        assert_eq!(config.timeout, Some(Duration::from_secs_f64(2.5)));
+        assert_eq!(config.algorithm, Some(Algorithm::Optimal));
+        assert_eq!(config.tie_break, Some(TieBreak::HigherCost));
+        assert_eq!(config.heuristic, Some(Heuristic::Misplaced));
+        assert_eq!(config.threads, Some(4));
+        assert_eq!(config.format, Some(Format::Notation));
+
+        assert_eq!(parse("").unwrap().max_moves, None);
+        assert!(parse("tie_break = \"newest\"").is_err());
+        assert!(parse("max_move = 3").is_err());
+        assert!(parse("timeout = -1").is_err());
+        assert!(parse("timeout = 1e300").is_err());
+    }
+}
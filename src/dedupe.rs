@@ -0,0 +1,147 @@
+use rayon::prelude::*;
+use solver_of_squares::game::{Equivalence, Game};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A puzzle and the other files that hold the same puzzle.
+#[derive(Debug)]
+pub struct Group {
+    pub kept: PathBuf,
+    pub duplicates: Vec<Duplicate>,
+}
+
+#[derive(Debug)]
+pub struct Duplicate {
+    pub path: PathBuf,
+    /// How to turn the kept puzzle into this one, or `None` when it is the same puzzle, at most
+    /// moved on the board.
+    pub equivalence: Option<Equivalence>,
+}
+
+impl Duplicate {
+    pub fn describe(&self) -> String {
+        let Some(equivalence) = &self.equivalence else {
+            return String::from("the same puzzle");
+        };
+        let mut description = equivalence.transform.clone();
+        let renamed: Vec<String> = equivalence
+            .colors
+            .iter()
+            .filter(|(from, to)| from != to)
+            .map(|(from, to)| format!("{} -> {}", from, to))
+            .collect();
+        if !renamed.is_empty() {
+            description.push_str(&format!(", colors {}", renamed.join(", ")));
+        }
+        description
+    }
+}
+
+/// The puzzle files directly in `dir`, sorted, so that the first of each group is kept.
+pub fn puzzle_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path.extension().is_some_and(|extension| {
+                    extension == "yaml" || extension == "yml" || extension == "json"
+                })
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Groups the puzzles that are the same up to position, rotation, reflection, and colors, in the
+/// order of `puzzles`. Puzzles with no duplicate are left out.
+pub fn find(puzzles: &[(PathBuf, Game)]) -> Vec<Group> {
+    let forms: Vec<(String, String)> = puzzles
+        .par_iter()
+        .map(|(_, game)| (game.canonical_form(), game.normalized().to_yaml()))
+        .collect();
+
+    let mut groups: Vec<Vec<usize>> = vec![];
+    let mut group_of: HashMap<&str, usize> = HashMap::new();
+    for (index, (canonical, _)) in forms.iter().enumerate() {
+        match group_of.get(canonical.as_str()) {
+            Some(&group) => groups[group].push(index),
+            None => {
+                group_of.insert(canonical, groups.len());
+                groups.push(vec![index]);
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|members| members.len() > 1)
+        .map(|members| {
+            let kept = members[0];
+            let duplicates = members[1..]
+                .iter()
+                .map(|&index| Duplicate {
+                    path: puzzles[index].0.clone(),
+                    equivalence: (forms[index].1 != forms[kept].1)
+                        .then(|| puzzles[kept].1.equivalence(&puzzles[index].1))
+                        .flatten(),
+                })
+                .collect();
+            Group {
+                kept: puzzles[kept].0.clone(),
+                duplicates,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solver_of_squares::game::Direction;
+
+    #[test]
+    fn test_duplicates_group_under_the_first_file() {
+        let mut original = Game::new();
+        original.add_block("red".into(), Direction::Right, [0, 0], Some([2, 0]));
+        original.add_block("blue".into(), Direction::Up, [1, 1], None);
+        original.add_arrow(Direction::Down, [1, 0]);
+
+        let mut moved = Game::new();
+        moved.add_block("red".into(), Direction::Right, [5, 5], Some([7, 5]));
+        moved.add_block("blue".into(), Direction::Up, [6, 6], None);
+        moved.add_arrow(Direction::Down, [6, 5]);
+
+        let mut mirrored = Game::new();
+        mirrored.add_block("green".into(), Direction::Left, [2, 0], Some([0, 0]));
+        mirrored.add_block("blue".into(), Direction::Up, [1, 1], None);
+        mirrored.add_arrow(Direction::Down, [1, 0]);
+
+        let mut other = original.clone();
+        other.add_arrow(Direction::Up, [3, 3]);
+
+        let puzzles: Vec<(PathBuf, Game)> = [
+            ("a.yaml", original),
+            ("b.yaml", other),
+            ("c.yaml", moved),
+            ("d.yaml", mirrored),
+        ]
+        .into_iter()
+        .map(|(path, game)| (PathBuf::from(path), game))
+        .collect();
+
+        let groups = find(&puzzles);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].kept, PathBuf::from("a.yaml"));
+        let duplicates = &groups[0].duplicates;
+        assert_eq!(duplicates.len(), 2);
+        assert_eq!(duplicates[0].path, PathBuf::from("c.yaml"));
+        assert_eq!(duplicates[0].describe(), "the same puzzle");
+        assert_eq!(duplicates[1].path, PathBuf::from("d.yaml"));
+        assert_eq!(
+            duplicates[1].describe(),
+            "mirrored left to right, colors red -> green"
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! Problems with a puzzle file, each placed on the line it is about, sent as the Language Server
+//! Protocol's `textDocument/publishDiagnostics` notification so an editor can underline them.
+//!
+//! ```
+//! use solver_of_squares::diagnostics::{diagnose, Severity};
+//! use solver_of_squares::solver::SolverOptions;
+//!
+//! let text = "blocks:\n  - color: red\n    direction: up\n    position: [0, 0]\n    goal: [1, 0]\n";
+//! let diagnostics = diagnose(text, &SolverOptions::new(10));
+//! assert_eq!(diagnostics[0].severity, Severity::Error);
+//! assert_eq!(diagnostics[0].line, 1);
+//! ```
+
+use crate::game::{Game, Position2D};
+use crate::solver::{AbortReason, SolveOutcome, SolverOptions};
+use serde_json::json;
+use serde_yaml::Value;
+use std::collections::HashSet;
+
+/// How serious a problem is, numbered as the protocol numbers them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error = 1,
+    Warning = 2,
+    Information = 3,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Where the problem is, counting from 1 as [`ParseError`](crate::parse::ParseError) does.
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Everything wrong with the puzzle in `text`: why it does not parse, or else blocks and arrows
+/// listed twice or on the same cell, arrows no block can reach, and whether `options` finds a
+/// solution.
+pub fn diagnose(text: &str, options: &SolverOptions) -> Vec<Diagnostic> {
+    let game: Game = match text.parse() {
+        Ok(game) => game,
+        Err(error) => {
+            return vec![Diagnostic {
+                line: error.line.unwrap_or(1),
+                column: error.column.unwrap_or(1),
+                severity: Severity::Error,
+                message: error.to_string(),
+            }]
+        }
+    };
+
+    let mut diagnostics = duplicates(text);
+    for (position, _) in game.arrows() {
+        if !reachable(&game, &position) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                message: String::from(
+                    "no block can reach this arrow: nothing else is in its row or column",
+                ),
+                ..locate(text, &position_needles(&position), 0, "arrows:")
+            });
+        }
+    }
+
+    let (moves, stats) = game.solve_with(options);
+    let unsolved = match game.outcome(moves, stats) {
+        SolveOutcome::Solved(_) => None,
+        SolveOutcome::ProvenUnsolvable => Some((
+            Severity::Error,
+            String::from("the puzzle cannot be solved: every reachable position was searched"),
+        )),
+        SolveOutcome::NoSolutionWithinBound => Some((
+            Severity::Error,
+            format!("no solution within {} moves", options.move_bound()),
+        )),
+        SolveOutcome::Aborted(reason) => Some((
+            Severity::Information,
+            match reason {
+                AbortReason::TimedOut => {
+                    String::from("the solver timed out before finding a solution")
+                }
+                AbortReason::Cancelled | AbortReason::LimitReached => {
+                    String::from("the solver stopped before finding a solution")
+                }
+            },
+        )),
+    };
+    if let Some((severity, message)) = unsolved {
+        diagnostics.push(Diagnostic {
+            severity,
+            message,
+            ..locate(text, &[String::from("blocks:")], 0, "blocks:")
+        });
+    }
+    diagnostics
+}
+
+/// The notification that replaces an editor's diagnostics for the document at `uri`, framed
+/// with the `Content-Length` header the protocol sends before every message.
+pub fn publish_diagnostics(uri: &str, diagnostics: &[Diagnostic]) -> String {
+    let diagnostics: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            // The protocol counts from 0. The range runs to the start of the next line.
+            let line = diagnostic.line.saturating_sub(1);
+            json!({
+                "range": {
+                    "start": { "line": line, "character": diagnostic.column.saturating_sub(1) },
+                    "end": { "line": line + 1, "character": 0 },
+                },
+                "severity": diagnostic.severity as u8,
+                "source": "solver-of-squares",
+                "message": diagnostic.message,
+            })
+        })
+        .collect();
+    let body = json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+    .to_string();
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+}
+
+// Blocks and arrows move in straight lines and only turn on arrows, so an arrow is reachable
+// only from a block or another arrow in its row or column.
+fn reachable(game: &Game, position: &Position2D) -> bool {
+    let in_line = |other: &Position2D| other[0] == position[0] || other[1] == position[1];
+    game.initial_state()
+        .blocks()
+        .iter()
+        .any(|(_, block)| in_line(&block.position))
+        || game
+            .arrows()
+            .iter()
+            .any(|(other, _)| other != position && in_line(other))
+}
+
+// Read from the YAML itself, since a parsed puzzle keeps only the last of two blocks of the same
+// color or two arrows on the same cell.
+fn duplicates(text: &str) -> Vec<Diagnostic> {
+    let Ok(document) = serde_yaml::from_str::<Value>(text) else {
+        return vec![];
+    };
+    let list = |key: &str| -> Vec<Value> {
+        match document.get(key) {
+            Some(Value::Sequence(items)) => items.clone(),
+            _ => vec![],
+        }
+    };
+    let position = |item: &Value| -> Option<Position2D> {
+        serde_yaml::from_value(item.get("position")?.clone()).ok()
+    };
+
+    let mut diagnostics = vec![];
+    let (mut colors, mut cells) = (HashSet::new(), HashSet::new());
+    for block in list("blocks") {
+        let Some(color) = block.get("color").and_then(Value::as_str) else {
+            continue;
+        };
+        let needle = format!("color: {}", color);
+        let occurrence = usize::from(colors.contains(color));
+        if !colors.insert(color.to_string()) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                message: format!("there is already a {} block; this one replaces it", color),
+                ..locate(text, std::slice::from_ref(&needle), occurrence, "blocks:")
+            });
+        }
+        if let Some(position) = position(&block) {
+            if !cells.insert(position) {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    message: format!(
+                        "another block already starts at ({}, {})",
+                        position[0], position[1]
+                    ),
+                    ..locate(text, &[needle], occurrence, "blocks:")
+                });
+            }
+        }
+    }
+
+    let mut arrows = HashSet::new();
+    for arrow in list("arrows") {
+        let Some(position) = position(&arrow) else {
+            continue;
+        };
+        if !arrows.insert(position) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                message: format!(
+                    "there is already an arrow at ({}, {}); this one replaces it",
+                    position[0], position[1]
+                ),
+                ..locate(text, &position_needles(&position), 1, "arrows:")
+            });
+        }
+    }
+    diagnostics
+}
+
+fn position_needles(position: &Position2D) -> Vec<String> {
+    vec![
+        format!("[{}, {}]", position[0], position[1]),
+        format!("[{},{}]", position[0], position[1]),
+    ]
+}
+
+// The `occurrence`th line (from 0) that contains one of `needles`, or failing that the line
+// with `fallback`, or the first line.
+fn locate(text: &str, needles: &[String], occurrence: usize, fallback: &str) -> Diagnostic {
+    let find = |matches: &dyn Fn(&str) -> Option<usize>, skip: usize| {
+        text.lines()
+            .enumerate()
+            .filter_map(|(i, line)| matches(line).map(|column| (i + 1, column + 1)))
+            .nth(skip)
+    };
+    let (line, column) = find(
+        &|line: &str| needles.iter().find_map(|needle| line.find(needle.as_str())),
+        occurrence,
+    )
+    .or_else(|| find(&|line: &str| line.find(fallback), 0))
+    .unwrap_or((1, 1));
+    Diagnostic {
+        line,
+        column,
+        severity: Severity::Information,
+        message: String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnose_finds_duplicates_unreachable_arrows_and_unsolvable_puzzles() {
+        let text = "blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n    \
+                    goal: [2, 0]\n  - color: blue\n    direction: up\n    position: [0, 0]\n  \
+                    - color: red\n    direction: right\n    position: [0, 1]\narrows:\n  \
+                    - direction: up\n    position: [5, 5]\n  - direction: down\n    \
+                    position: [5, 5]\n";
+        let diagnostics = diagnose(text, &SolverOptions::new(5));
+        let found: Vec<(usize, Severity, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.severity, &d.message[..20]))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (6, Severity::Error, "another block alread"),
+                (9, Severity::Error, "there is already a r"),
+                (16, Severity::Error, "there is already an "),
+                (14, Severity::Warning, "no block can reach t"),
+            ]
+        );
+
+        let bounded = "blocks:\n  - color: red\n    direction: up\n    position: [0, 0]\n    \
+                       goal: [1, 0]\nrules:\n  bounds: [[0, 0], [1, 1]]\n";
+        let diagnostics = diagnose(bounded, &SolverOptions::new(5));
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0]
+            .message
+            .starts_with("the puzzle cannot be solved"));
+
+        let diagnostics = diagnose(
+            "blocks:\n  - color: red\n    direction: upp\n",
+            &SolverOptions::new(5),
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (3, 16));
+
+        let message = publish_diagnostics("file:///level.yaml", &diagnostics);
+        let (header, body) = message.split_once("\r\n\r\n").unwrap();
+        assert_eq!(header, format!("Content-Length: {}", body.len()));
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["method"], "textDocument/publishDiagnostics");
+        assert_eq!(
+            body["params"]["diagnostics"][0]["range"]["start"]["line"],
+            2
+        );
+        assert_eq!(body["params"]["diagnostics"][0]["severity"], 1);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::game::ExpansionRecord;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+const HIGHLIGHT: &str = "red";
+
+pub fn search_tree(
+    expanded: &[ExpansionRecord],
+    solution: &[ExpansionRecord],
+    max_depth: Option<i32>,
+) -> String {
+    let on_path: HashSet<u64> = solution.iter().map(|record| record.hash).collect();
+    let expanded_hashes: HashSet<u64> = expanded.iter().map(|record| record.hash).collect();
+    let within_depth = |record: &&ExpansionRecord| max_depth.is_none_or(|depth| record.g <= depth);
+
+    let mut dot = String::from("digraph search {\n    node [shape=box];\n");
+    let records = expanded.iter().chain(
+        solution
+            .iter()
+            .filter(|record| !expanded_hashes.contains(&record.hash)),
+    );
+
+    for record in records.filter(within_depth) {
+        let style = if on_path.contains(&record.hash) {
+            format!(", color={HIGHLIGHT}, penwidth=2")
+        } else {
+            String::new()
+        };
+        let _ = writeln!(
+            dot,
+            "    n{} [label=\"g={} h={}\"{}];",
+            record.hash, record.g, record.h, style
+        );
+
+        let Some(parent) = record.parent else {
+            continue;
+        };
+        let label = record.r#move.as_deref().unwrap_or_default();
+        let style = if on_path.contains(&record.hash) && on_path.contains(&parent) {
+            format!(", color={HIGHLIGHT}, penwidth=2")
+        } else {
+            String::new()
+        };
+        let _ = writeln!(
+            dot,
+            "    n{} -> n{} [label=\"{}\"{}];",
+            parent, record.hash, label, style
+        );
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(hash: u64, parent: Option<u64>, g: i32, r#move: Option<&str>) -> ExpansionRecord {
+        ExpansionRecord {
+            hash,
+            parent,
+            g,
+            h: 0,
+            r#move: r#move.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_search_tree_highlights_solution_and_limits_depth() {
+        let expanded = vec![
+            record(1, None, 0, None),
+            record(2, Some(1), 1, Some("red")),
+            record(3, Some(1), 1, Some("blue")),
+            record(4, Some(3), 2, Some("red")),
+        ];
+        let solution = vec![
+            record(1, None, 0, None),
+            record(2, Some(1), 1, Some("red")),
+            record(5, Some(2), 2, Some("blue")),
+        ];
+
+        let dot = search_tree(&expanded, &solution, Some(1));
+        assert!(dot.contains("n1 -> n2 [label=\"red\", color=red, penwidth=2];"));
+        assert!(dot.contains("n1 -> n3 [label=\"blue\"];"));
+        assert!(!dot.contains("n4"));
+        assert!(!dot.contains("n5"));
+
+        let dot = search_tree(&expanded, &solution, None);
+        assert!(dot.contains("n2 -> n5 [label=\"blue\", color=red, penwidth=2];"));
+    }
+}
@@ -0,0 +1,351 @@
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use solver_of_squares::game::{Color, Direction, Game, Position2D};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+const PALETTE: [&str; 8] = [
+    "red", "blue", "green", "yellow", "orange", "purple", "cyan", "white",
+];
+
+const HELP: &str = "arrow keys move the cursor; b add or select a block, d turn it, \
+                    g put the selected block's goal here, a cycle the arrow here, \
+                    x delete, s save, q quit";
+
+type Request = (usize, Game);
+type Response = (usize, Option<usize>);
+
+enum Analysis {
+    Pending,
+    Solved(usize),
+    Unsolvable,
+}
+
+struct Editor {
+    game: Game,
+    path: PathBuf,
+    max_moves: i32,
+    cursor: Position2D,
+    selected: Option<Color>,
+    dirty: bool,
+    message: String,
+    generation: usize,
+    analysis: Analysis,
+    requests: Sender<Request>,
+    results: Receiver<Response>,
+}
+
+pub fn run(game: Game, path: &Path, max_moves: i32) -> io::Result<()> {
+    let (requests, results) = spawn_solver(max_moves);
+    let mut editor = Editor {
+        game,
+        path: path.to_path_buf(),
+        max_moves,
+        cursor: [0, 0],
+        selected: None,
+        dirty: false,
+        message: String::new(),
+        generation: 0,
+        analysis: Analysis::Pending,
+        requests,
+        results,
+    };
+    editor.analyze();
+
+    let mut terminal = ratatui::init();
+    let result = editor.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+// Solves on a background thread so the editor stays responsive; only the latest request matters.
+fn spawn_solver(max_moves: i32) -> (Sender<Request>, Receiver<Response>) {
+    let (request_sender, request_receiver) = mpsc::channel::<Request>();
+    let (result_sender, result_receiver) = mpsc::channel();
+    thread::spawn(move || {
+        while let Ok(mut request) = request_receiver.recv() {
+            while let Ok(newer) = request_receiver.try_recv() {
+                request = newer;
+            }
+            let (generation, game) = request;
+            let length = game.solve_optimal(max_moves).map(|moves| moves.len());
+            if result_sender.send((generation, length)).is_err() {
+                return;
+            }
+        }
+    });
+
+    (request_sender, result_receiver)
+}
+
+impl Editor {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            while let Ok((generation, length)) = self.results.try_recv() {
+                if generation == self.generation {
+                    self.analysis = match length {
+                        Some(length) => Analysis::Solved(length),
+                        None => Analysis::Unsolvable,
+                    };
+                }
+            }
+
+            terminal.draw(|frame| self.draw(frame))?;
+            if !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.on_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn analyze(&mut self) {
+        self.generation += 1;
+        self.analysis = Analysis::Pending;
+        let _ = self.requests.send((self.generation, self.game.clone()));
+    }
+
+    fn edited(&mut self) {
+        self.dirty = true;
+        self.message.clear();
+        self.analyze();
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> bool {
+        let [x, y] = self.cursor;
+        match key.code {
+            KeyCode::Up => self.cursor = [x, y + 1],
+            KeyCode::Down => self.cursor = [x, y - 1],
+            KeyCode::Left => self.cursor = [x - 1, y],
+            KeyCode::Right => self.cursor = [x + 1, y],
+            KeyCode::Char('b') => self.add_or_select_block(),
+            KeyCode::Char('d') => self.turn_block(),
+            KeyCode::Char('g') => self.place_goal(),
+            KeyCode::Char('a') => self.cycle_arrow(),
+            KeyCode::Char('x') => self.delete(),
+            KeyCode::Char('s') => self.save(),
+            KeyCode::Char('q') | KeyCode::Esc => {
+                if !self.dirty || self.message.starts_with("Unsaved") {
+                    return false;
+                }
+                self.message = String::from("Unsaved changes; press q again to quit anyway");
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn add_or_select_block(&mut self) {
+        if let Some((color, _)) = self.game.block_at(&self.cursor) {
+            self.selected = Some(color.clone());
+            return;
+        }
+
+        let colors = self.game.colors();
+        let Some(color) = PALETTE
+            .iter()
+            .find(|color| !colors.iter().any(|used| used == *color))
+        else {
+            self.message = format!("At most {} blocks are supported", PALETTE.len());
+            return;
+        };
+        self.game
+            .add_block(color.to_string(), Direction::Up, self.cursor, None);
+        self.selected = Some(color.to_string());
+        self.edited();
+    }
+
+    fn turn_block(&mut self) {
+        if let Some((color, direction)) = self.game.block_at(&self.cursor) {
+            let (color, direction) = (color.clone(), direction.clockwise());
+            self.game.set_direction(&color, direction);
+            self.selected = Some(color);
+            self.edited();
+        }
+    }
+
+    fn place_goal(&mut self) {
+        let Some(color) = self.selected.clone() else {
+            self.message = String::from("Select a block with b first");
+            return;
+        };
+        let goal = (self.game.goal_at(&self.cursor) != Some(&color)).then_some(self.cursor);
+        self.game.set_goal(&color, goal);
+        self.edited();
+    }
+
+    fn cycle_arrow(&mut self) {
+        match self.game.arrow_at(&self.cursor) {
+            Some(Direction::Left) => {
+                self.game.remove_arrow(&self.cursor);
+            }
+            Some(direction) => {
+                let direction = direction.clockwise();
+                self.game.add_arrow(direction, self.cursor);
+            }
+            None => self.game.add_arrow(Direction::Up, self.cursor),
+        }
+        self.edited();
+    }
+
+    fn delete(&mut self) {
+        if let Some((color, _)) = self.game.block_at(&self.cursor) {
+            let color = color.clone();
+            self.game.remove_block(&color);
+            if self.selected.as_ref() == Some(&color) {
+                self.selected = None;
+            }
+        } else if let Some(color) = self.game.goal_at(&self.cursor) {
+            let color = color.clone();
+            self.game.set_goal(&color, None);
+        } else if self.game.remove_arrow(&self.cursor).is_none() {
+            return;
+        }
+        self.edited();
+    }
+
+    fn save(&mut self) {
+        self.message = match fs::write(&self.path, self.game.to_yaml()) {
+            Ok(()) => {
+                self.dirty = false;
+                format!("Saved {}", self.path.display())
+            }
+            Err(error) => format!("Could not save {}: {}", self.path.display(), error),
+        };
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [board_area, side_area] =
+            Layout::horizontal([Constraint::Min(10), Constraint::Length(40)]).areas(frame.area());
+
+        let (mut min, mut max) = self.game.bounds_after(&[]);
+        for i in 0..2 {
+            min[i] = min[i].min(self.cursor[i]) - 1;
+            max[i] = max[i].max(self.cursor[i]) + 1;
+        }
+
+        let mut rows = vec![];
+        for y in (min[1]..=max[1]).rev() {
+            let cells: Vec<Span> = (min[0]..=max[0])
+                .map(|x| {
+                    let cell = Span::raw(self.cell([x, y]).to_string());
+                    if [x, y] == self.cursor {
+                        cell.style(Style::new().add_modifier(Modifier::REVERSED))
+                    } else {
+                        cell
+                    }
+                })
+                .collect();
+            rows.push(Line::from(cells));
+        }
+        let title = format!(
+            "{}{}",
+            self.path.display(),
+            if self.dirty { " *" } else { "" }
+        );
+        frame.render_widget(
+            Paragraph::new(rows).block(Block::bordered().title(title)),
+            board_area,
+        );
+
+        let analysis = match self.analysis {
+            Analysis::Pending => String::from("Solving..."),
+            Analysis::Solved(length) => format!("Optimal solution: {} moves", length),
+            Analysis::Unsolvable => format!("No solution within {} moves", self.max_moves),
+        };
+        let here = match self.game.block_at(&self.cursor) {
+            Some((color, direction)) => format!(" {} facing {}", color, direction),
+            None => String::new(),
+        };
+        let side = vec![
+            Line::from(analysis),
+            Line::from(""),
+            Line::from(format!(
+                "Cursor: ({}, {}){}",
+                self.cursor[0], self.cursor[1], here
+            )),
+            Line::from(format!(
+                "Selected: {}",
+                self.selected.as_deref().unwrap_or("none")
+            )),
+            Line::from(""),
+            Line::from(self.message.as_str()),
+            Line::from(""),
+            Line::from(HELP),
+        ];
+        frame.render_widget(
+            Paragraph::new(side)
+                .wrap(Wrap { trim: true })
+                .block(Block::bordered().title("Edit")),
+            side_area,
+        );
+    }
+
+    fn cell(&self, position: Position2D) -> char {
+        let initial = |color: &Color| color.chars().next().unwrap_or('?');
+        if let Some((color, _)) = self.game.block_at(&position) {
+            initial(color).to_ascii_uppercase()
+        } else if let Some(color) = self.game.goal_at(&position) {
+            initial(color).to_ascii_lowercase()
+        } else if let Some(direction) = self.game.arrow_at(&position) {
+            direction.symbol()
+        } else {
+            '.'
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builds_a_puzzle_from_keys() {
+        let (requests, results) = spawn_solver(10);
+        let mut editor = Editor {
+            game: Game::new(),
+            path: PathBuf::from("new.yaml"),
+            max_moves: 10,
+            cursor: [0, 0],
+            selected: None,
+            dirty: false,
+            message: String::new(),
+            generation: 0,
+            analysis: Analysis::Pending,
+            requests,
+            results,
+        };
+
+        for code in [
+            KeyCode::Char('b'),
+            KeyCode::Char('d'),
+            KeyCode::Right,
+            KeyCode::Right,
+            KeyCode::Char('g'),
+        ] {
+            editor.on_key(KeyEvent::from(code));
+        }
+
+        assert!(editor.dirty);
+        assert_eq!(
+            editor.game.to_yaml(),
+            "blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n    goal: [2, 0]\n"
+        );
+        let latest = editor
+            .results
+            .iter()
+            .find(|(generation, _)| *generation == editor.generation);
+        assert_eq!(latest, Some((editor.generation, Some(2))));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::game::Game;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+use std::time::Duration;
+
+pub struct SosGame(Game);
+
+#[repr(C)]
+pub struct SosSolution {
+    pub solved: bool,
+    pub timed_out: bool,
+    pub move_count: usize,
+    pub moves: *mut *mut c_char,
+    pub expanded: usize,
+}
+
+/// Parses a puzzle from NUL-terminated YAML. Returns null if the text is not a valid puzzle.
+///
+/// # Safety
+///
+/// `yaml` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sos_parse(yaml: *const c_char) -> *mut SosGame {
+    if yaml.is_null() {
+        return ptr::null_mut();
+    }
+    let Ok(yaml) = CStr::from_ptr(yaml).to_str() else {
+        return ptr::null_mut();
+    };
+    match serde_yaml::from_str(yaml) {
+        Ok(game) => Box::into_raw(Box::new(SosGame(game))),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `game` must be null or come from `sos_parse`, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn sos_free_game(game: *mut SosGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Solves a parsed puzzle. A timeout of zero or less, or one too large or not finite to represent,
+/// means no timeout. Returns null if `game` is null.
+///
+/// # Safety
+///
+/// `game` must be null or a live pointer from `sos_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn sos_solve(
+    game: *const SosGame,
+    max_moves: i32,
+    timeout_seconds: f64,
+) -> *mut SosSolution {
+    let Some(SosGame(game)) = game.as_ref() else {
+        return ptr::null_mut();
+    };
+
+    let timeout = Duration::try_from_secs_f64(timeout_seconds)
+        .ok()
+        .filter(|timeout| !timeout.is_zero());
+    let (moves, stats) = game.solve_with_timeout(max_moves, timeout);
+    let solved = moves.is_some();
+    let moves: Box<[*mut c_char]> = moves
+        .unwrap_or_default()
+        .into_iter()
+        .map(|color| CString::new(color).unwrap_or_default().into_raw())
+        .collect();
+    let move_count = moves.len();
+
+    Box::into_raw(Box::new(SosSolution {
+        solved,
+        timed_out: stats.timed_out,
+        move_count,
+        moves: Box::into_raw(moves) as *mut *mut c_char,
+        expanded: stats.expanded,
+    }))
+}
+
+/// # Safety
+///
+/// `solution` must be null or come from `sos_solve`, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn sos_free_solution(solution: *mut SosSolution) {
+    if solution.is_null() {
+        return;
+    }
+    let solution = Box::from_raw(solution);
+    let moves = Box::from_raw(ptr::slice_from_raw_parts_mut(
+        solution.moves,
+        solution.move_count,
+    ));
+    for color in moves.iter() {
+        drop(CString::from_raw(*color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_solve_and_free() {
+        let yaml = c"blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n    goal: [2, 0]\n";
+        unsafe {
+            assert!(sos_parse(c"blocks: 3".as_ptr()).is_null());
+
+            let game = sos_parse(yaml.as_ptr());
+            assert!(!game.is_null());
+            let solution = sos_solve(game, 10, 0.0);
+            let moves = std::slice::from_raw_parts((*solution).moves, (*solution).move_count);
+            assert!((*solution).solved);
+            assert_eq!(moves.len(), 2);
+            assert_eq!(CStr::from_ptr(moves[0]).to_str(), Ok("red"));
+
+            sos_free_solution(solution);
+
+            for timeout in [-1.0, f64::INFINITY, f64::NAN, 1e300] {
+                let solution = sos_solve(game, 10, timeout);
+                assert!((*solution).solved);
+                sos_free_solution(solution);
+            }
+            sos_free_game(game);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use crate::game::Direction;
+use crate::heuristics::manhattan_distance;
+use crate::puzzle::Puzzle;
+use std::str::FromStr;
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Heuristic {
+    Misplaced,
+    #[default]
+    Manhattan,
+}
+
+impl FromStr for Heuristic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "misplaced" => Ok(Heuristic::Misplaced),
+            "manhattan" => Ok(Heuristic::Manhattan),
+            _ => Err(format!(
+                "unknown heuristic `{}`; expected misplaced or manhattan",
+                s
+            )),
+        }
+    }
+}
+
+// The N×N sliding puzzle. Tiles are listed row by row with 0 for the blank, and the goal is
+// 1, 2, ..., N²-1 followed by the blank. A move names the direction the blank moves in.
+#[derive(Clone, Debug)]
+pub struct Fifteen {
+    size: usize,
+    tiles: Vec<u8>,
+    pub heuristic: Heuristic,
+}
+
+impl Fifteen {
+    pub fn new(tiles: Vec<u8>) -> Result<Self, String> {
+        let size = (tiles.len() as f64).sqrt() as usize;
+        if size < 2 || size * size != tiles.len() {
+            return Err(format!("{} tiles do not make a square board", tiles.len()));
+        }
+        let mut sorted = tiles.clone();
+        sorted.sort();
+        if sorted
+            .iter()
+            .enumerate()
+            .any(|(i, tile)| *tile as usize != i)
+        {
+            return Err(format!(
+                "the tiles must be 0 to {} with each number once",
+                tiles.len() - 1
+            ));
+        }
+
+        Ok(Fifteen {
+            size,
+            tiles,
+            heuristic: Heuristic::default(),
+        })
+    }
+
+    // Whitespace-separated numbers, row by row; `_` may stand for the blank.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let tiles = text
+            .split_whitespace()
+            .map(|word| match word {
+                "_" => Ok(0),
+                _ => word
+                    .parse()
+                    .map_err(|_| format!("`{}` is not a tile number", word)),
+            })
+            .collect::<Result<Vec<u8>, String>>()?;
+        Self::new(tiles)
+    }
+
+    // Half of all arrangements cannot reach the goal; this is the usual inversion-count test.
+    pub fn is_solvable(&self) -> bool {
+        let numbers: Vec<u8> = self
+            .tiles
+            .iter()
+            .copied()
+            .filter(|tile| *tile != 0)
+            .collect();
+        let inversions = (0..numbers.len())
+            .flat_map(|i| (i + 1..numbers.len()).map(move |j| (i, j)))
+            .filter(|&(i, j)| numbers[i] > numbers[j])
+            .count();
+        if self.size % 2 == 1 {
+            return inversions % 2 == 0;
+        }
+        let blank_row = self.tiles.iter().position(|tile| *tile == 0).unwrap_or(0) / self.size;
+        let blank_row_from_bottom = self.size - blank_row;
+        (inversions + blank_row_from_bottom) % 2 == 1
+    }
+
+    pub fn render(&self, tiles: &[u8]) -> String {
+        let width = (self.tiles.len() - 1).to_string().len();
+        let mut text = String::new();
+        for row in tiles.chunks(self.size) {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|tile| match tile {
+                    0 => format!("{:>width$}", "_"),
+                    _ => format!("{:>width$}", tile),
+                })
+                .collect();
+            text.push_str(&cells.join(" "));
+            text.push('\n');
+        }
+        text
+    }
+
+    fn coordinates(&self, index: usize) -> [i32; 2] {
+        [(index % self.size) as i32, (index / self.size) as i32]
+    }
+}
+
+impl Puzzle for Fifteen {
+    type State = Vec<u8>;
+    type Move = Direction;
+
+    fn initial_state(&self) -> Vec<u8> {
+        self.tiles.clone()
+    }
+
+    fn moves(&self, tiles: &Vec<u8>) -> Vec<(Direction, Vec<u8>)> {
+        let blank = tiles.iter().position(|tile| *tile == 0).unwrap_or(0);
+        let [x, y] = self.coordinates(blank);
+        DIRECTIONS
+            .into_iter()
+            .filter_map(|direction| {
+                let [dx, dy] = direction.offset();
+                let (x, y) = (x + dx, y - dy);
+                if x < 0 || y < 0 || x >= self.size as i32 || y >= self.size as i32 {
+                    return None;
+                }
+                let mut next = tiles.clone();
+                next.swap(blank, y as usize * self.size + x as usize);
+                Some((direction, next))
+            })
+            .collect()
+    }
+
+    fn is_solved(&self, tiles: &Vec<u8>) -> bool {
+        let last = tiles.len() - 1;
+        tiles[last] == 0
+            && tiles[..last]
+                .iter()
+                .enumerate()
+                .all(|(i, tile)| *tile as usize == i + 1)
+    }
+
+    fn heuristic(&self, tiles: &Vec<u8>) -> i32 {
+        tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| **tile != 0)
+            .map(|(index, tile)| {
+                let home = *tile as usize - 1;
+                match self.heuristic {
+                    Heuristic::Misplaced => (index != home) as i32,
+                    Heuristic::Manhattan => {
+                        manhattan_distance(&self.coordinates(index), &self.coordinates(home))
+                    }
+                }
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::solve;
+
+    #[test]
+    fn test_solves_a_scrambled_board() {
+        let mut puzzle = Fifteen::parse("1 2 3 4\n5 6 _ 8\n9 10 7 11\n13 14 15 12").unwrap();
+        assert!(puzzle.is_solvable());
+
+        let (moves, manhattan) = solve(&puzzle, 20, None);
+        assert_eq!(
+            moves,
+            Some(vec![Direction::Down, Direction::Right, Direction::Down])
+        );
+
+        puzzle.heuristic = Heuristic::Misplaced;
+        let (moves, misplaced) = solve(&puzzle, 20, None);
+        assert_eq!(moves.map(|moves| moves.len()), Some(3));
+        assert!(manhattan.expanded <= misplaced.expanded);
+
+        assert!(!Fifteen::parse("1 2 3\n4 5 6\n8 7 _").unwrap().is_solvable());
+        assert!(Fifteen::parse("1 2 3").is_err());
+        assert!(Fifteen::parse("1 1 2 _").is_err());
+    }
+}
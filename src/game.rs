@@ -1,12 +1,31 @@
-use crate::heuristics::manhattan_distance;
-use crate::search::{astar, State};
-use serde::de::{MapAccess, Visitor};
-use serde::Deserialize;
-use std::collections::HashMap;
+use crate::analysis::{self, Analysis, HeuristicCheck};
+use crate::cache::DeadEndCache;
+use crate::parse::Limits;
+use crate::puzzle::Puzzle;
+use crate::rules::{ArrowPush, Ruleset, Violation};
+use crate::search::{
+    all_optimal, anytime, astar_from, astar_raising, count_solutions, hash, hash_version, ida_star,
+    shortest_path, shortest_path_observed, sma_star, stable_hash, CancellationToken, Frontier,
+    OpenList, SearchStats, State, TieBreak,
+};
+use crate::solver::{self, Algorithm, Heuristic, Solution, SolveOutcome, SolverOptions};
+use crate::symmetry::{find_symmetries, Symmetry};
+use crate::tiles::{Arrow, Entering, Entry, TileEffect, TileSpec};
+use serde::de::{DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use std::hash::Hash;
+use std::marker::PhantomData;
+#[cfg(feature = "yaml")]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, debug_span, info, info_span};
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Direction {
     Up,
@@ -26,21 +45,395 @@ impl Display for Direction {
     }
 }
 
+impl Direction {
+    pub fn offset(&self) -> Position2D {
+        match self {
+            Direction::Up => [0, 1],
+            Direction::Down => [0, -1],
+            Direction::Left => [-1, 0],
+            Direction::Right => [1, 0],
+        }
+    }
+
+    pub fn from_offset(offset: Position2D) -> Option<Self> {
+        match offset {
+            [0, 1] => Some(Direction::Up),
+            [0, -1] => Some(Direction::Down),
+            [-1, 0] => Some(Direction::Left),
+            [1, 0] => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    pub fn clockwise(&self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    pub fn symbol(&self) -> char {
+        match self {
+            Direction::Up => '^',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+            Direction::Right => '>',
+        }
+    }
+}
+
 pub type Position2D = [i32; 2];
 
 pub type Color = String;
 
-#[derive(Clone, Debug, Deserialize)]
-struct Block {
-    position: Position2D,
-    direction: Direction,
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Block {
+    pub position: Position2D,
+    pub direction: Direction,
+}
+
+/// A block must be on one of its goals after move `by` at the latest, or from the start if `by`
+/// is 0. With `stay`, it may not leave its goal again once it has met the deadline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Deadline {
+    pub by: usize,
+    pub stay: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PuzzleState {
+    blocks: HashMap<Color, Block>,
+}
+
+impl PuzzleState {
+    pub fn block(&self, color: &str) -> Option<&Block> {
+        self.blocks.get(color)
+    }
+
+    pub fn blocks(&self) -> Vec<(&Color, &Block)> {
+        let mut blocks: Vec<(&Color, &Block)> = self.blocks.iter().collect();
+        blocks.sort_by_key(|(color, _)| *color);
+        blocks
+    }
+}
+
+impl Hash for PuzzleState {
+    fn hash<H: std::hash::Hasher>(&self, hasher: &mut H) {
+        self.blocks().hash(hasher);
+    }
+}
+
+impl Puzzle for Game {
+    type State = PuzzleState;
+    type Move = Color;
+
+    fn initial_state(&self) -> PuzzleState {
+        Game::initial_state(self)
+    }
+
+    fn moves(&self, state: &PuzzleState) -> Vec<(Color, PuzzleState)> {
+        let Some(state) = self.board_state(state) else {
+            return vec![];
+        };
+        state
+            .successors()
+            .into_iter()
+            .filter_map(|next| {
+                let color = next.move_history.last()?.clone();
+                let blocks = next.squares.to_map();
+                Some((color, PuzzleState { blocks }))
+            })
+            .collect()
+    }
+
+    fn is_solved(&self, state: &PuzzleState) -> bool {
+        Game::is_solved(self, state)
+    }
+
+    fn heuristic(&self, state: &PuzzleState) -> i32 {
+        self.board_state(state)
+            .map_or(0, |state| state.distance_to_goal())
+    }
+}
+
+// How one puzzle maps onto an equivalent one: the rotation or reflection and the color renaming.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Equivalence {
+    pub transform: String,
+    pub colors: Vec<(Color, Color)>,
+}
+
+/// A block of a puzzle by its index in [`Game::colors`]. [`Game::color_id`] and
+/// [`Game::color_name`] convert between the two.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct ColorId(pub usize);
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Move {
+    pub block: ColorId,
+    pub direction: Direction,
+    pub from: Position2D,
+    pub to: Position2D,
+    /// The blocks pushed along, in order: the moved block pushes the first, which pushes the
+    /// second, and so on.
+    pub pushed: Vec<ColorId>,
+    pub redirected_to: Option<Direction>,
+}
+
+impl Move {
+    /// Each push as the pushing block and the pushed one, e.g. red and blue, then blue and green.
+    pub fn push_chain(&self) -> Vec<(ColorId, ColorId)> {
+        std::iter::once(self.block)
+            .chain(self.pushed.iter().copied())
+            .zip(self.pushed.iter().copied())
+            .collect()
+    }
+}
+
+/// A move with its blocks named by their colors, from [`Game::display_move`].
+pub struct MoveDisplay<'a> {
+    game: &'a Game,
+    step: &'a Move,
+}
+
+impl Display for MoveDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let name = |id| self.game.color_name(id).map_or("?", String::as_str);
+        let step = self.step;
+        write!(
+            f,
+            "{} {} ({}, {}) -> ({}, {})",
+            name(step.block),
+            step.direction,
+            step.from[0],
+            step.from[1],
+            step.to[0],
+            step.to[1]
+        )?;
+        if !step.pushed.is_empty() {
+            let pushes: Vec<String> = step
+                .push_chain()
+                .into_iter()
+                .map(|(pusher, pushed)| format!("{} pushes {}", name(pusher), name(pushed)))
+                .collect();
+            write!(f, "; {}", pushes.join(", "))?;
+        }
+        if let Some(direction) = &step.redirected_to {
+            write!(f, "; turned {} by an arrow", direction)?;
+        }
+        Ok(())
+    }
+}
+
+/// What a move in a solution was mainly for, from [`Game::explain_moves`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Purpose {
+    /// Moving the block itself, toward its goal or out of another block's way.
+    Positioning,
+    /// Sending the block onto an arrow that turned it.
+    Redirecting,
+    /// Pushing other blocks along, whether or not an arrow also turned the block.
+    Pushing,
+}
+
+impl Display for Purpose {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Purpose::Positioning => "positioning",
+            Purpose::Redirecting => "redirecting",
+            Purpose::Pushing => "pushing",
+        })
+    }
+}
+
+/// A move of a solution with what it did toward the goal, from [`Game::explain_moves`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Explanation {
+    pub step: Move,
+    pub purpose: Purpose,
+    /// The heuristic's estimate of the moves left, before and after the move.
+    pub estimate_before: i32,
+    pub estimate_after: i32,
+    /// Blocks with goals that ended the move nearer their nearest goal, or farther from it.
+    pub closer: Vec<Color>,
+    pub farther: Vec<Color>,
+}
+
+/// An explained move with its blocks named by their colors, from [`Game::display_explanation`].
+pub struct ExplanationDisplay<'a> {
+    game: &'a Game,
+    explanation: &'a Explanation,
+}
+
+impl Display for ExplanationDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let explanation = self.explanation;
+        write!(
+            f,
+            "{}: {}; estimate {} -> {}",
+            explanation.purpose,
+            self.game.display_move(&explanation.step),
+            explanation.estimate_before,
+            explanation.estimate_after
+        )?;
+        if !explanation.closer.is_empty() {
+            write!(f, "; closer: {}", explanation.closer.join(", "))?;
+        }
+        if !explanation.farther.is_empty() {
+            write!(f, "; farther: {}", explanation.farther.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// How one block changed over a move, from [`Game::diff_moves`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Change {
+    pub color: Color,
+    /// Whether this is the block that was moved, rather than one it pushed.
+    pub moved: bool,
+    pub before: Block,
+    pub after: Block,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} ({}, {}) -> ({}, {})",
+            self.color,
+            if self.moved { "moved" } else { "pushed" },
+            self.before.position[0],
+            self.before.position[1],
+            self.after.position[0],
+            self.after.position[1]
+        )?;
+        if self.after.direction != self.before.direction {
+            write!(
+                f,
+                ", turned {} -> {} by an arrow",
+                self.before.direction, self.after.direction
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// A solution together with the board before the first move and after every move. `verify`
+// reads the `moves` key and ignores the rest.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SolutionRecord {
+    pub moves: Vec<Color>,
+    pub solved: bool,
+    pub states: Vec<BTreeMap<Color, Block>>,
+    pub final_positions: BTreeMap<Color, Position2D>,
+}
+
+/// A solution with the position before the first move and after every move, from
+/// [`Game::solve_with_states`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolutionPath {
+    pub moves: Vec<Color>,
+    pub states: Vec<PuzzleState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    puzzle: u64,
+    max_moves: i32,
+    expanded: usize,
+    generated: usize,
+    seen: Vec<u64>,
+    open: Vec<Vec<Color>>,
+}
+
+/// What is wrong with a move, as [`Game::verify_solution`] finds it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    UnknownColor,
+    Forbidden(Violation),
+    /// The block whose deadline the move would break.
+    MissedDeadline(Color),
+    /// The push went round a loop of arrows back into itself, so nothing moved.
+    NoEffect,
+}
+
+/// The first move of a sequence that cannot be played: its index from 0, the block it names,
+/// and why.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidMove {
+    pub index: usize,
+    pub color: Color,
+    pub error: MoveError,
+}
+
+impl Display for InvalidMove {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "Move {} ({}) ", self.index + 1, self.color)?;
+        match &self.error {
+            MoveError::UnknownColor => write!(f, "does not name a block in the puzzle"),
+            MoveError::Forbidden(violation) => write!(f, "{}", violation),
+            MoveError::MissedDeadline(color) => write!(f, "makes {} miss its deadline", color),
+            MoveError::NoEffect => write!(f, "moves nothing"),
+        }
+    }
 }
 
 #[derive(Debug)]
+pub struct Verification {
+    pub solved: bool,
+    pub moves_applied: usize,
+    pub first_solved_after: Option<usize>,
+    pub unknown_color: Option<Color>,
+    /// The block whose move was refused because the rules do not allow it, and why.
+    pub forbidden_move: Option<Color>,
+    pub violation: Option<Violation>,
+    /// The block whose deadline the next move would have broken.
+    pub missed_deadline: Option<Color>,
+    pub board: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExpansionRecord {
+    pub hash: u64,
+    pub parent: Option<u64>,
+    pub g: i32,
+    pub h: i32,
+    pub r#move: Option<Color>,
+}
+
+impl ExpansionRecord {
+    fn new(state: &BoardState, hash: u64, parent: Option<u64>) -> Self {
+        ExpansionRecord {
+            hash,
+            parent,
+            g: state.cost(),
+            h: state.distance_to_goal(),
+            r#move: state.move_history.last().cloned(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Game {
-    goals: HashMap<Color, Position2D>,
+    // Each color's goals, any one of which will do. A color with a goal has at least one.
+    goals: HashMap<Color, Vec<Position2D>>,
     arrows: HashMap<Position2D, Direction>,
+    // Tiles other than arrows, on cells without one.
+    tiles: HashMap<Position2D, Arc<dyn TileEffect>>,
+    // Cells no block may enter, and cells particular blocks may not.
+    avoided: HashSet<Position2D>,
+    avoided_by: HashMap<Color, HashSet<Position2D>>,
+    // Sorted, so each board state can record which have been met in the same order.
+    deadlines: BTreeMap<Color, Deadline>,
+    rules: Ruleset,
     initial_state: HashMap<Color, Block>,
+    symmetries: OnceLock<Vec<Symmetry>>,
+    // What a search reads but never changes, built once for every search until the game changes.
+    board: OnceLock<Arc<StaticBoard>>,
 }
 
 impl Game {
@@ -48,7 +441,14 @@ impl Game {
         Game {
             goals: HashMap::new(),
             arrows: HashMap::new(),
+            tiles: HashMap::new(),
+            avoided: HashSet::new(),
+            avoided_by: HashMap::new(),
+            deadlines: BTreeMap::new(),
+            rules: Ruleset::default(),
             initial_state: HashMap::new(),
+            symmetries: OnceLock::new(),
+            board: OnceLock::new(),
         }
     }
 
@@ -66,193 +466,4406 @@ impl Game {
                 direction,
             },
         );
-        if let Some(goal_position) = goal_position {
-            self.goals.insert(color, goal_position);
+        match goal_position {
+            Some(goal_position) => {
+                self.goals.insert(color, vec![goal_position]);
+            }
+            None => {
+                self.goals.remove(&color);
+            }
+        }
+        self.symmetries = OnceLock::new();
+        self.board = OnceLock::new();
+    }
+
+    /// Starts the block of `color` at `position` facing `direction` instead, keeping its goals.
+    /// Returns false, changing nothing, if the puzzle has no such block.
+    pub fn place_block(&mut self, color: &str, direction: Direction, position: Position2D) -> bool {
+        let Some(block) = self.initial_state.get_mut(color) else {
+            return false;
+        };
+        *block = Block {
+            position,
+            direction,
+        };
+        self.symmetries = OnceLock::new();
+        self.board = OnceLock::new();
+        true
+    }
+
+    /// Lets the block of `color` finish at `goal` as well as at any goal it already has.
+    pub fn add_goal(&mut self, color: &str, goal: Position2D) {
+        if !self.initial_state.contains_key(color) {
+            return;
         }
+        let goals = self.goals.entry(color.to_string()).or_default();
+        if !goals.contains(&goal) {
+            goals.push(goal);
+        }
+        self.symmetries = OnceLock::new();
+        self.board = OnceLock::new();
     }
 
+    /// Puts an arrow on `position`, in place of any other tile there.
     pub fn add_arrow(&mut self, direction: Direction, position: Position2D) {
+        self.tiles.remove(&position);
         self.arrows.insert(position, direction);
+        self.symmetries = OnceLock::new();
+        self.board = OnceLock::new();
     }
 
-    pub fn solve(&self, max_moves: i32) -> Option<Vec<Color>> {
-        let board_state = BoardState {
-            game: self,
-            cost: 0,
-            squares: self.initial_state.clone(),
-            move_history: vec![],
-        };
-
-        Some(astar(board_state, max_moves)?.move_history)
+    pub fn remove_arrow(&mut self, position: &Position2D) -> Option<Direction> {
+        self.symmetries = OnceLock::new();
+        self.board = OnceLock::new();
+        self.arrows.remove(position)
     }
-}
 
-impl<'de> Deserialize<'de> for Game {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        struct GameVisitor;
+    /// Puts `tile` on `position`, in place of any arrow or other tile there. See the
+    /// [`tiles`](crate::tiles) module for the kinds built in.
+    pub fn add_tile(&mut self, position: Position2D, tile: Arc<dyn TileEffect>) {
+        self.arrows.remove(&position);
+        self.tiles.insert(position, tile);
+        self.symmetries = OnceLock::new();
+        self.board = OnceLock::new();
+    }
 
-        #[derive(Deserialize)]
-        struct SerializedBlock {
-            color: Color,
-            direction: Direction,
-            position: Position2D,
-            goal: Option<Position2D>,
-        }
+    pub fn remove_tile(&mut self, position: &Position2D) -> Option<Arc<dyn TileEffect>> {
+        self.symmetries = OnceLock::new();
+        self.board = OnceLock::new();
+        self.tiles.remove(position)
+    }
 
-        #[derive(Deserialize)]
-        struct SerializedArrow {
-            direction: Direction,
-            position: Position2D,
-        }
+    /// The tiles other than arrows, sorted by position.
+    pub fn tiles(&self) -> Vec<(Position2D, &Arc<dyn TileEffect>)> {
+        let mut tiles: Vec<(Position2D, &Arc<dyn TileEffect>)> = self
+            .tiles
+            .iter()
+            .map(|(position, tile)| (*position, tile))
+            .collect();
+        tiles.sort_by_key(|(position, _)| *position);
+        tiles
+    }
 
-        impl<'de> Visitor<'de> for GameVisitor {
-            type Value = Game;
+    /// Makes moving or pushing any block onto `position` illegal.
+    pub fn avoid(&mut self, position: Position2D) {
+        self.avoided.insert(position);
+        self.symmetries = OnceLock::new();
+        self.board = OnceLock::new();
+    }
 
-            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("a game with values for blocks and (optionally) arrows")
-            }
+    /// Makes moving or pushing the block of `color` onto `position` illegal.
+    pub fn avoid_for(&mut self, color: &str, position: Position2D) {
+        if !self.initial_state.contains_key(color) {
+            return;
+        }
+        self.avoided_by
+            .entry(color.to_string())
+            .or_default()
+            .insert(position);
+        self.symmetries = OnceLock::new();
+        self.board = OnceLock::new();
+    }
 
-            fn visit_map<V>(self, mut map: V) -> Result<Game, V::Error>
-            where
-                V: MapAccess<'de>,
-            {
-                let mut game = Game::new();
+    pub fn set_rules(&mut self, rules: Ruleset) {
+        self.rules = rules;
+        self.symmetries = OnceLock::new();
+        self.board = OnceLock::new();
+    }
 
-                while let Some(key) = map.next_key::<String>()? {
-                    match key.as_str() {
-                        "blocks" => {
-                            let blocks: Vec<SerializedBlock> = map.next_value()?;
-                            for block in blocks {
-                                game.add_block(
-                                    block.color,
-                                    block.direction,
-                                    block.position,
-                                    block.goal,
-                                );
-                            }
-                        }
-                        "arrows" => {
-                            let arrows: Vec<SerializedArrow> = map.next_value()?;
-                            for arrow in arrows {
-                                game.add_arrow(arrow.direction, arrow.position);
-                            }
-                        }
-                        _ => {
-                            return Err(serde::de::Error::unknown_field(
-                                &key,
-                                &["blocks", "arrows"],
-                            ));
-                        }
-                    }
-                }
+    pub fn rules(&self) -> &Ruleset {
+        &self.rules
+    }
 
-                Ok(game)
+    /// Gives the block of `color` a deadline for reaching one of its goals, or removes it.
+    pub fn set_deadline(&mut self, color: &str, deadline: Option<Deadline>) {
+        if !self.initial_state.contains_key(color) {
+            return;
+        }
+        match deadline {
+            Some(deadline) => {
+                self.deadlines.insert(color.to_string(), deadline);
+            }
+            None => {
+                self.deadlines.remove(color);
             }
         }
+        self.board = OnceLock::new();
+    }
 
-        deserializer.deserialize_map(GameVisitor)
+    pub fn deadline_of(&self, color: &str) -> Option<Deadline> {
+        self.deadlines.get(color).copied()
     }
-}
 
-#[derive(Clone, Debug)]
-struct BoardState<'a> {
-    game: &'a Game,
-    cost: i32,
-    squares: HashMap<Color, Block>,
-    move_history: Vec<Color>,
-}
+    pub fn may_enter(&self, color: &str, position: &Position2D) -> bool {
+        self.board().may_enter(color, position)
+    }
 
-impl<'a> BoardState<'a> {
-    fn move_square(&self, color: &Color) -> Self {
-        let mut new_state = self.clone();
-        new_state.cost += 1;
-        new_state.move_history.push(color.clone());
-        new_state.push_square(color, &self.squares.get(color).unwrap().direction);
+    /// The cells no block may enter, sorted.
+    pub fn avoided(&self) -> Vec<Position2D> {
+        let mut cells: Vec<Position2D> = self.avoided.iter().copied().collect();
+        cells.sort();
+        cells
+    }
 
-        new_state
+    /// The cells the block of `color` may not enter besides [`Game::avoided`], sorted.
+    pub fn avoided_by(&self, color: &str) -> Vec<Position2D> {
+        let mut cells: Vec<Position2D> = self
+            .avoided_by
+            .get(color)
+            .into_iter()
+            .flatten()
+            .copied()
+            .collect();
+        cells.sort();
+        cells
+    }
+
+    pub fn remove_block(&mut self, color: &str) {
+        self.initial_state.remove(color);
+        self.goals.remove(color);
+        self.avoided_by.remove(color);
+        self.deadlines.remove(color);
+        self.symmetries = OnceLock::new();
+        self.board = OnceLock::new();
     }
 
-    fn find_collision_with(&self, color: Color) -> Option<Color> {
-        let block = self.squares.get(&color).unwrap();
+    pub fn set_direction(&mut self, color: &str, direction: Direction) {
+        if let Some(block) = self.initial_state.get_mut(color) {
+            block.direction = direction;
+        }
+    }
 
-        for (other_color, other_block) in self.squares.iter() {
-            if other_color != &color && other_block.position == block.position {
-                return Some(other_color.clone());
+    /// Replaces every goal of `color` with `goal`.
+    pub fn set_goal(&mut self, color: &str, goal: Option<Position2D>) {
+        match goal {
+            Some(goal) if self.initial_state.contains_key(color) => {
+                self.goals.insert(color.to_string(), vec![goal]);
+            }
+            _ => {
+                self.goals.remove(color);
             }
         }
+        self.symmetries = OnceLock::new();
+        self.board = OnceLock::new();
+    }
 
-        None
+    pub fn block_at(&self, position: &Position2D) -> Option<(&Color, &Direction)> {
+        self.initial_state
+            .iter()
+            .find(|(_, block)| block.position == *position)
+            .map(|(color, block)| (color, &block.direction))
     }
 
-    fn push_square(&mut self, color: &Color, direction: &Direction) {
-        let block = self.squares.get_mut(color).unwrap();
+    /// The cells the block of `color` may finish on, empty if it may finish anywhere.
+    pub fn goals_of(&self, color: &str) -> &[Position2D] {
+        self.goals.get(color).map(Vec::as_slice).unwrap_or_default()
+    }
 
-        block.position = match direction {
-            Direction::Up => [block.position[0], block.position[1] + 1],
-            Direction::Down => [block.position[0], block.position[1] - 1],
-            Direction::Left => [block.position[0] - 1, block.position[1]],
-            Direction::Right => [block.position[0] + 1, block.position[1]],
-        };
+    pub fn goal_at(&self, position: &Position2D) -> Option<&Color> {
+        self.goals
+            .iter()
+            .filter(|(_, goals)| goals.contains(position))
+            .map(|(color, _)| color)
+            .min()
+    }
 
-        if let Some(new_direction) = self.game.arrows.get(&block.position) {
-            block.direction = new_direction.clone();
-        }
+    /// The goal each block with goals is on in `state`, by color. Blocks that are not on one of
+    /// their goals are left out.
+    pub fn goals_reached(&self, state: &PuzzleState) -> Vec<(Color, Position2D)> {
+        let mut reached: Vec<(Color, Position2D)> = self
+            .goals
+            .iter()
+            .filter_map(|(color, goals)| {
+                let position = state.block(color)?.position;
+                goals.contains(&position).then(|| (color.clone(), position))
+            })
+            .collect();
+        reached.sort();
+        reached
+    }
 
-        if let Some(collided_block) = self.find_collision_with(color.clone()) {
-            self.push_square(&collided_block, direction);
-        }
+    pub fn arrow_at(&self, position: &Position2D) -> Option<&Direction> {
+        self.board().tiles.arrow_at(position)
     }
 
-    fn fingerprint(&self) -> String {
-        let mut keys: Vec<&Color> = self.squares.keys().collect();
-        keys.sort();
+    pub fn arrows(&self) -> Vec<(Position2D, Direction)> {
+        let mut arrows: Vec<(Position2D, Direction)> = self
+            .arrows
+            .iter()
+            .map(|(position, direction)| (*position, direction.clone()))
+            .collect();
+        arrows.sort_by_key(|(position, _)| *position);
+        arrows
+    }
 
-        let mut fingerprint = String::new();
+    pub fn colors(&self) -> Vec<Color> {
+        let mut colors: Vec<Color> = self.initial_state.keys().cloned().collect();
+        colors.sort();
+        colors
+    }
 
-        for key in keys {
-            let block = self.squares.get(key).unwrap();
-            fingerprint.push_str(&format!(
-                "{},{},{},{}\t",
-                key, block.position[0], block.position[1], block.direction
-            ));
+    /// The block of color `color`, or `None` if the puzzle has none.
+    pub fn color_id(&self, color: &str) -> Option<ColorId> {
+        self.board().index_of(color).map(ColorId)
+    }
+
+    /// The color of block `id`, or `None` if it is from a puzzle with fewer blocks.
+    pub fn color_name(&self, id: ColorId) -> Option<&Color> {
+        self.board().colors.get(id.0)
+    }
+
+    fn board(&self) -> &Arc<StaticBoard> {
+        self.board.get_or_init(|| Arc::new(StaticBoard::new(self)))
+    }
+
+    fn symmetries(&self) -> &[Symmetry] {
+        self.symmetries.get_or_init(|| {
+            let _span = debug_span!("precompute_symmetries").entered();
+            // Other tiles can act differently after a rotation or reflection, such as a teleporter
+            // whose destination would have to be another teleporter's.
+            if !self.tiles.is_empty() {
+                return vec![Symmetry::identity()];
+            }
+            // A block with several goals may finish on any of them, so they may trade places,
+            // and likewise for the cells a block must avoid. The corners of a bounded board must
+            // stay corners.
+            let mut cell_sets: Vec<Vec<Position2D>> = self.goals.values().cloned().collect();
+            cell_sets.extend(
+                self.avoided_by
+                    .values()
+                    .chain([&self.avoided])
+                    .map(|cells| cells.iter().copied().collect()),
+            );
+            if let Some([min, max]) = self.rules.bounds {
+                cell_sets.push(vec![min, [min[0], max[1]], max, [max[0], min[1]]]);
+            }
+            let symmetries = find_symmetries(&cell_sets, &self.arrows);
+            debug!(count = symmetries.len(), "found board symmetries");
+            symmetries
+        })
+    }
+
+    pub fn normalized(&self) -> Game {
+        let positions = self
+            .initial_state
+            .values()
+            .map(|block| &block.position)
+            .chain(self.goals.values().flatten())
+            .chain(self.arrows.keys())
+            .chain(self.tiles.keys())
+            .chain(&self.avoided)
+            .chain(self.avoided_by.values().flatten())
+            .chain(self.rules.bounds.iter().flatten());
+        let mut min = [i32::MAX; 2];
+        for position in positions {
+            min = [min[0].min(position[0]), min[1].min(position[1])];
         }
+        let translate = |position: &Position2D| [position[0] - min[0], position[1] - min[1]];
 
-        fingerprint
+        let mut game = Game::new();
+        for (color, block) in &self.initial_state {
+            game.add_block(
+                color.clone(),
+                block.direction.clone(),
+                translate(&block.position),
+                None,
+            );
+            for goal in self.goals_of(color) {
+                game.add_goal(color, translate(goal));
+            }
+            for cell in self.avoided_by(color) {
+                game.avoid_for(color, translate(&cell));
+            }
+        }
+        game.deadlines = self.deadlines.clone();
+        game.rules = self.rules.transformed(translate);
+        for (position, direction) in &self.arrows {
+            game.add_arrow(direction.clone(), translate(position));
+        }
+        for (position, tile) in &self.tiles {
+            game.add_tile(
+                translate(position),
+                tile.mapped(&translate, &|direction| direction.clone()),
+            );
+        }
+        for cell in &self.avoided {
+            game.avoid(translate(cell));
+        }
+
+        game
     }
-}
 
-impl<'a> Hash for BoardState<'a> {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.fingerprint().hash(state);
+    fn transformed(&self, symmetry: &Symmetry) -> Game {
+        let mut game = Game::new();
+        for (color, block) in &self.initial_state {
+            game.add_block(
+                color.clone(),
+                symmetry.apply_direction(&block.direction),
+                symmetry.apply(&block.position),
+                None,
+            );
+            for goal in self.goals_of(color) {
+                game.add_goal(color, symmetry.apply(goal));
+            }
+            for cell in self.avoided_by(color) {
+                game.avoid_for(color, symmetry.apply(&cell));
+            }
+        }
+        game.deadlines = self.deadlines.clone();
+        game.rules = self.rules.transformed(|position| symmetry.apply(position));
+        for cell in &self.avoided {
+            game.avoid(symmetry.apply(cell));
+        }
+        for (position, direction) in &self.arrows {
+            game.add_arrow(
+                symmetry.apply_direction(direction),
+                symmetry.apply(position),
+            );
+        }
+        for (position, tile) in &self.tiles {
+            game.add_tile(
+                symmetry.apply(position),
+                tile.mapped(&|position| symmetry.apply(position), &|direction| {
+                    symmetry.apply_direction(direction)
+                }),
+            );
+        }
+        game
     }
-}
 
-impl<'a> State for BoardState<'a> {
-    type Cost = i32;
+    // Renames the blocks in order of their starting positions, so that only the layout matters.
+    fn with_canonical_colors(&self) -> (Game, HashMap<Color, Color>) {
+        let mut blocks: Vec<(&Color, &Block)> = self.initial_state.iter().collect();
+        blocks.sort_by_key(|(_, block)| block.position);
+        let renames: HashMap<Color, Color> = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, (color, _))| ((*color).clone(), format!("block{:03}", i)))
+            .collect();
 
-    fn successors(&self) -> Vec<Self> {
-        self.squares.keys().map(|k| self.move_square(k)).collect()
+        let mut game = Game::new();
+        for (color, block) in &self.initial_state {
+            game.add_block(
+                renames[color].clone(),
+                block.direction.clone(),
+                block.position,
+                None,
+            );
+            for goal in self.goals_of(color) {
+                game.add_goal(&renames[color], *goal);
+            }
+            for cell in self.avoided_by(color) {
+                game.avoid_for(&renames[color], cell);
+            }
+            game.set_deadline(&renames[color], self.deadline_of(color));
+        }
+        game.arrows = self.arrows.clone();
+        game.tiles = self.tiles.clone();
+        game.avoided = self.avoided.clone();
+        game.rules = self.rules.clone();
+        (game, renames)
     }
 
-    fn is_goal(&self) -> bool {
-        self.distance_to_goal() == 0
+    /// The puzzle moved, rotated, reflected, and with its colors renamed into a standard form,
+    /// written as YAML. Two puzzles have the same form exactly when [`Game::equivalence`] finds
+    /// one to be the other, so the form can key a set of distinct puzzles.
+    pub fn canonical_form(&self) -> String {
+        Symmetry::rotations_and_reflections()
+            .map(|(_, symmetry)| {
+                self.transformed(&symmetry)
+                    .normalized()
+                    .with_canonical_colors()
+                    .0
+                    .to_yaml()
+            })
+            .min()
+            .unwrap_or_default()
     }
 
-    fn distance_to_goal(&self) -> Self::Cost {
-        self.game
-            .goals
+    // Whether `other` is this puzzle moved, rotated, reflected, and with its colors renamed.
+    pub fn equivalence(&self, other: &Game) -> Option<Equivalence> {
+        let (target, other_renames) = other.normalized().with_canonical_colors();
+        let target = target.to_yaml();
+        let other_colors: HashMap<&Color, &Color> = other_renames
             .iter()
-            .map(|(color, position)| {
-                let block = self.squares.get(color).unwrap();
-                manhattan_distance(&block.position, position)
+            .map(|(color, canonical)| (canonical, color))
+            .collect();
+
+        Symmetry::rotations_and_reflections().find_map(|(transform, symmetry)| {
+            let (candidate, renames) = self
+                .transformed(&symmetry)
+                .normalized()
+                .with_canonical_colors();
+            if candidate.to_yaml() != target {
+                return None;
+            }
+            let mut colors: Vec<(Color, Color)> = renames
+                .iter()
+                .map(|(color, canonical)| (color.clone(), other_colors[canonical].clone()))
+                .collect();
+            colors.sort();
+            Some(Equivalence {
+                transform: transform.to_string(),
+                colors,
             })
-            .sum()
+        })
     }
 
-    fn cost(&self) -> Self::Cost {
-        self.cost
+    pub fn to_yaml(&self) -> String {
+        let mut colors: Vec<&Color> = self.initial_state.keys().collect();
+        colors.sort();
+        let mut positions: Vec<&Position2D> = self.arrows.keys().collect();
+        positions.sort();
+
+        let format_position = |position: &Position2D| format!("[{}, {}]", position[0], position[1]);
+        let mut blocks = vec![];
+        for color in colors {
+            let block = &self.initial_state[color];
+            let mut entry = format!(
+                "  - color: {}\n    direction: {}\n    position: {}\n",
+                yaml_string(color),
+                block.direction,
+                format_position(&block.position)
+            );
+            // Sorted, so that puzzles with the same goals in another order read the same.
+            let mut goals = self.goals_of(color).to_vec();
+            goals.sort();
+            match goals.as_slice() {
+                [] => {}
+                [goal] => entry.push_str(&format!("    goal: {}\n", format_position(goal))),
+                goals => {
+                    let goals: Vec<String> = goals.iter().map(format_position).collect();
+                    entry.push_str(&format!("    goal: [{}]\n", goals.join(", ")));
+                }
+            }
+            let avoided: Vec<String> = self.avoided_by(color).iter().map(format_position).collect();
+            if !avoided.is_empty() {
+                entry.push_str(&format!("    avoid: [{}]\n", avoided.join(", ")));
+            }
+            match self.deadline_of(color) {
+                Some(Deadline { by, stay: false }) => {
+                    entry.push_str(&format!("    deadline: {}\n", by))
+                }
+                Some(Deadline { by, stay: true }) => {
+                    entry.push_str(&format!("    deadline: {{by: {}, stay: true}}\n", by))
+                }
+                None => {}
+            }
+            blocks.push(entry);
+        }
+        let arrows: Vec<String> = positions
+            .into_iter()
+            .map(|position| {
+                format!(
+                    "  - direction: {}\n    position: {}\n",
+                    self.arrows[position],
+                    format_position(position)
+                )
+            })
+            .collect();
+
+        let tiles: Vec<String> = self
+            .tiles()
+            .into_iter()
+            .map(|(position, tile)| {
+                let mut entry = match tile.spec() {
+                    TileSpec::Arrow { direction } => {
+                        format!("  - kind: arrow\n    direction: {}\n", direction)
+                    }
+                    TileSpec::Wall => String::from("  - kind: wall\n"),
+                    TileSpec::Pit => String::from("  - kind: pit\n"),
+                    TileSpec::Teleporter { to } => {
+                        format!("  - kind: teleporter\n    to: {}\n", format_position(&to))
+                    }
+                };
+                entry.push_str(&format!("    position: {}\n", format_position(&position)));
+                entry
+            })
+            .collect();
+
+        let mut yaml = format!("blocks:\n{}", blocks.join("\n"));
+        if !arrows.is_empty() {
+            yaml.push_str(&format!("\narrows:\n{}", arrows.join("\n")));
+        }
+        if !tiles.is_empty() {
+            yaml.push_str(&format!("\ntiles:\n{}", tiles.join("\n")));
+        }
+        let avoided: Vec<String> = self.avoided().iter().map(format_position).collect();
+        if !avoided.is_empty() {
+            yaml.push_str(&format!("\navoid: [{}]\n", avoided.join(", ")));
+        }
+        if !self.rules.is_default() {
+            yaml.push_str(&format!("\n{}", self.rules.to_yaml()));
+        }
+
+        yaml
+    }
+
+    /// Whether A* finds a solution within `max_moves`, and if not, why not.
+    pub fn solve(&self, max_moves: i32) -> SolveOutcome {
+        let (moves, stats) = self.solve_with_stats(max_moves);
+        self.outcome(moves, stats)
+    }
+
+    /// What one of the `solve_*` methods returned, with the moves described as a [`Solution`].
+    pub fn outcome(&self, moves: Option<Vec<Color>>, stats: SearchStats) -> SolveOutcome {
+        let solution = moves
+            .and_then(|moves| self.describe_moves(&moves))
+            .map(|moves| Solution {
+                length: moves.len(),
+                moves,
+                block_colors: self.colors(),
+                stats: stats.clone(),
+            });
+        SolveOutcome::from_search(solution, &stats)
+    }
+
+    pub fn solve_with_stats(&self, max_moves: i32) -> (Option<Vec<Color>>, SearchStats) {
+        self.solve_with_timeout(max_moves, None)
+    }
+
+    pub fn solve_with_timeout(
+        &self,
+        max_moves: i32,
+        timeout: Option<Duration>,
+    ) -> (Option<Vec<Color>>, SearchStats) {
+        self.solve_observed(max_moves, timeout, |_| {})
+    }
+
+    pub fn solve_observed(
+        &self,
+        max_moves: i32,
+        timeout: Option<Duration>,
+        on_expand: impl FnMut(ExpansionRecord),
+    ) -> (Option<Vec<Color>>, SearchStats) {
+        let mut options = SolverOptions::new(max_moves);
+        options.timeout = timeout;
+        self.solve_astar(&options, on_expand)
+    }
+
+    pub fn solve_with_tie_break(
+        &self,
+        max_moves: i32,
+        timeout: Option<Duration>,
+        tie_break: TieBreak,
+    ) -> (Option<Vec<Color>>, SearchStats) {
+        let mut options = SolverOptions::new(max_moves).tie_break(tie_break);
+        options.timeout = timeout;
+        self.solve_with(&options)
+    }
+
+    // Stops early, with `stats.cancelled` set, once `token` is cancelled from another thread.
+    pub fn solve_with_cancel(
+        &self,
+        max_moves: i32,
+        token: CancellationToken,
+    ) -> (Option<Vec<Color>>, SearchStats) {
+        self.solve_with(&SolverOptions::new(max_moves).cancel(token))
+    }
+
+    /// Runs the search described by `options`. The other `solve_*` methods are shorthands for
+    /// common options.
+    /// `stats` says what the search used: the states it expanded and generated, its wall time,
+    /// and for A* its estimated peak memory. Set limits on them with [`SolverOptions::timeout`],
+    /// [`SolverOptions::max_expanded`], and [`SolverOptions::max_memory`].
+    pub fn solve_with(&self, options: &SolverOptions) -> (Option<Vec<Color>>, SearchStats) {
+        let start = Instant::now();
+        let (moves, mut stats) = match options.algorithm {
+            Algorithm::AStar => self.solve_astar(options, |_| {}),
+            Algorithm::BreadthFirst => self.solve_breadth_first(options),
+            Algorithm::SmaStar => self.solve_memory_bounded(options),
+            Algorithm::Anytime => self.solve_anytime(options, |_, _| {}),
+            Algorithm::IdaStar => self.solve_iterative_deepening(options),
+        };
+        stats.elapsed = start.elapsed();
+        (moves, stats)
+    }
+
+    /// Like [`Game::solve_with`], but also returns the position before the first move and after
+    /// every move, so `states[i + 1]` is `moves[i]` applied to `states[i]`.
+    pub fn solve_with_states(
+        &self,
+        options: &SolverOptions,
+    ) -> (Option<SolutionPath>, SearchStats) {
+        let (moves, stats) = self.solve_with(options);
+        let solution = moves.and_then(|moves| {
+            let states = self.states_along(&moves)?;
+            Some(SolutionPath { moves, states })
+        });
+        (solution, stats)
+    }
+
+    /// Runs the configurations in `entries` on their own threads and returns the first solution,
+    /// cancelling the searches still running, along with the index of the configuration that
+    /// found it. At most as many searches run at once as the smallest `threads` of the entries;
+    /// the rest start in order as earlier ones finish. Searches that finish without a solution
+    /// do not stop the others. The entries' own cancellation tokens are replaced by one the race
+    /// shares. [`solver::portfolio`] builds a useful set of entries.
+    ///
+    /// [`solver::portfolio`]: crate::solver::portfolio
+    pub fn solve_portfolio(
+        &self,
+        entries: &[SolverOptions],
+    ) -> (Option<Vec<Color>>, SearchStats, Option<usize>) {
+        let token = CancellationToken::new();
+        let threads = entries.iter().map(|options| options.threads).min();
+        let next = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| {
+            for _ in 0..threads.unwrap_or(0).clamp(1, entries.len().max(1)) {
+                let sender = sender.clone();
+                let (next, token) = (&next, &token);
+                scope.spawn(move || {
+                    while !token.is_cancelled() {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(options) = entries.get(index) else {
+                            break;
+                        };
+                        let options = options.clone().cancel(token.clone());
+                        let (moves, stats) = self.solve_with(&options);
+                        let _ = sender.send((index, moves, stats));
+                    }
+                });
+            }
+            drop(sender);
+
+            let mut last = SearchStats::default();
+            for (index, moves, stats) in receiver {
+                debug!(index, solved = moves.is_some(), "portfolio entry finished");
+                if moves.is_some() {
+                    token.cancel();
+                    return (moves, stats, Some(index));
+                }
+                last = stats;
+            }
+            (None, last, None)
+        })
+    }
+
+    /// Identifies the puzzle in a [`DeadEndCache`]: the same for puzzles that differ only by
+    /// where they are. It also changes with [`hash_version`], since the cache keys positions by
+    /// their hash.
+    pub fn cache_key(&self) -> u64 {
+        stable_hash(&(self.normalized().to_yaml(), hash_version()))
+    }
+
+    /// Like [`Game::solve_with`], but skips positions that `cache` proves cannot be finished
+    /// within the bound. A breadth-first search adds the bounds it proves to `cache`; the other
+    /// algorithms only read it. The moves are the same as for the puzzle solved directly.
+    pub fn solve_cached(
+        &self,
+        options: &SolverOptions,
+        cache: &mut DeadEndCache,
+    ) -> (Option<Vec<Color>>, SearchStats) {
+        let game = self.normalized();
+        let key = stable_hash(&(game.to_yaml(), hash_version()));
+        let mut options = options.clone();
+        options.known = cache.bounds(key).cloned().map(Arc::new);
+        if options.algorithm != Algorithm::BreadthFirst {
+            let (moves, mut stats) = game.solve_with(&options);
+            // Positions the cache ruled out were skipped, so running out proves nothing.
+            stats.exhausted &= options.known.is_none();
+            return (moves, stats);
+        }
+
+        let bound = options.move_bound();
+        let _span = info_span!("search", algorithm = "breadth_first", max_moves = bound).entered();
+        let mut reached = HashMap::new();
+        let (state, mut stats) = shortest_path_observed(
+            game.start_state(&options),
+            bound,
+            options.search_limits(),
+            |s| s.is_goal(),
+            |s: &BoardState| {
+                reached.entry(s.canonical_hash()).or_insert(s.cost);
+            },
+        );
+        // Each position was expanded at the fewest moves it can be reached in, so it needs at
+        // least as many more as the search had left when it failed, or as the optimal solution
+        // has left when it succeeded.
+        let needed = |cost: i32| match &state {
+            Some(goal) => goal.cost - cost,
+            None => bound - cost + 1,
+        };
+        let bounds: HashMap<u64, i32> = reached
+            .into_iter()
+            .map(|(position, cost)| (position, needed(cost)))
+            .filter(|(_, needed)| *needed > 0)
+            .collect();
+        info!(
+            expanded = stats.expanded,
+            generated = stats.generated,
+            recorded = bounds.len(),
+            solved = state.is_some(),
+            "search finished"
+        );
+        cache.record(key, bounds);
+        stats.exhausted &= options.known.is_none();
+        (state.map(|state| state.move_history.into_vec()), stats)
+    }
+
+    /// Runs an anytime search with the options' weight and limits, calling `on_solution` with
+    /// each solution that is shorter than the ones before it. Returns the shortest.
+    pub fn solve_anytime(
+        &self,
+        options: &SolverOptions,
+        mut on_solution: impl FnMut(&[Color], &SearchStats),
+    ) -> (Option<Vec<Color>>, SearchStats) {
+        let SolverOptions {
+            heuristic, weight, ..
+        } = *options;
+        let max_moves = options.move_bound();
+        let _span = info_span!(
+            "search",
+            algorithm = "anytime",
+            max_moves,
+            ?heuristic,
+            weight
+        )
+        .entered();
+        let deadline = options.deadline();
+        let start = self.start_state(options);
+        let (state, stats) = anytime(
+            start,
+            max_moves,
+            deadline,
+            options.cancel.as_ref(),
+            |state: &BoardState, stats| on_solution(&state.move_history.to_vec(), stats),
+        );
+        info!(
+            expanded = stats.expanded,
+            generated = stats.generated,
+            timed_out = stats.timed_out,
+            cancelled = stats.cancelled,
+            solved = state.is_some(),
+            "search finished"
+        );
+        (state.map(|state| state.move_history.into_vec()), stats)
+    }
+
+    fn solve_memory_bounded(&self, options: &SolverOptions) -> (Option<Vec<Color>>, SearchStats) {
+        let SolverOptions {
+            heuristic,
+            weight,
+            max_nodes,
+            ..
+        } = *options;
+        let max_moves = options.move_bound();
+        let _span = info_span!(
+            "search",
+            algorithm = "sma_star",
+            max_moves,
+            max_nodes,
+            ?heuristic,
+            weight
+        )
+        .entered();
+        let deadline = options.deadline();
+        let start = self.start_state(options);
+        let (state, stats) = sma_star(
+            start,
+            max_moves,
+            max_nodes,
+            deadline,
+            options.cancel.as_ref(),
+        );
+        info!(
+            expanded = stats.expanded,
+            generated = stats.generated,
+            timed_out = stats.timed_out,
+            cancelled = stats.cancelled,
+            limit_reached = stats.limit_reached,
+            solved = state.is_some(),
+            "search finished"
+        );
+        (state.map(|state| state.move_history.into_vec()), stats)
+    }
+
+    fn solve_iterative_deepening(
+        &self,
+        options: &SolverOptions,
+    ) -> (Option<Vec<Color>>, SearchStats) {
+        let SolverOptions {
+            heuristic,
+            weight,
+            table_size,
+            ..
+        } = *options;
+        let max_moves = options.move_bound();
+        let _span = info_span!(
+            "search",
+            algorithm = "ida_star",
+            max_moves,
+            table_size,
+            ?heuristic,
+            weight
+        )
+        .entered();
+        let deadline = options.deadline();
+        let start = self.start_state(options);
+        let (state, stats) = ida_star(
+            start,
+            max_moves,
+            table_size,
+            deadline,
+            options.cancel.as_ref(),
+        );
+        info!(
+            expanded = stats.expanded,
+            generated = stats.generated,
+            table = stats.seen,
+            timed_out = stats.timed_out,
+            cancelled = stats.cancelled,
+            solved = state.is_some(),
+            "search finished"
+        );
+        (state.map(|state| state.move_history.into_vec()), stats)
+    }
+
+    /// Like [`Game::solve_with`] with A*, but from the position after `moves`, with the move
+    /// bound counted from there. Returns only the moves still needed.
+    pub fn solve_after(
+        &self,
+        moves: &[Color],
+        options: &SolverOptions,
+    ) -> (Option<Vec<Color>>, SearchStats) {
+        let mut start = self.start_state(options);
+        for color in moves {
+            start = start.move_square(color);
+        }
+        let options = SolverOptions {
+            max_depth: options.max_depth.saturating_add(moves.len()),
+            max_cost: options.max_cost.saturating_add(start.cost),
+            ..options.clone()
+        };
+        let (solution, stats) = self.solve_astar_from(start, &options, |_| {});
+        (
+            solution.map(|history| history[moves.len()..].to_vec()),
+            stats,
+        )
+    }
+
+    fn solve_astar(
+        &self,
+        options: &SolverOptions,
+        on_expand: impl FnMut(ExpansionRecord),
+    ) -> (Option<Vec<Color>>, SearchStats) {
+        self.solve_astar_from(self.start_state(options), options, on_expand)
+    }
+
+    fn solve_astar_from(
+        &self,
+        start: BoardState,
+        options: &SolverOptions,
+        mut on_expand: impl FnMut(ExpansionRecord),
+    ) -> (Option<Vec<Color>>, SearchStats) {
+        let SolverOptions {
+            max_depth,
+            max_cost,
+            heuristic,
+            weight,
+            tie_break,
+            open_list,
+            ..
+        } = *options;
+        let _span = info_span!(
+            "search",
+            algorithm = "astar",
+            max_depth,
+            max_cost,
+            ?heuristic,
+            weight,
+            ?tie_break,
+            ?open_list
+        )
+        .entered();
+        let deadline = options.deadline();
+        let mut frontier = Frontier::start(start);
+        frontier.tie_break = tie_break;
+        frontier.open_list = open_list;
+        frontier.max_expanded = options.max_expanded;
+        frontier.max_memory = options.max_memory;
+        frontier.max_depth = Some(max_depth);
+        frontier.progress = options.progress.clone();
+        let (state, stats) = astar_from(
+            &mut frontier,
+            max_cost,
+            deadline,
+            options.cancel.as_ref(),
+            |state: &BoardState, hash, parent| on_expand(ExpansionRecord::new(state, hash, parent)),
+            None,
+            |_, _, _| {},
+        );
+        info!(
+            expanded = stats.expanded,
+            generated = stats.generated,
+            timed_out = stats.timed_out,
+            cancelled = stats.cancelled,
+            limit_reached = stats.limit_reached,
+            depth_cut_off = stats.depth_cut_off,
+            cost_cut_off = stats.cost_cut_off,
+            solved = state.is_some(),
+            "search finished"
+        );
+        (state.map(|state| state.move_history.into_vec()), stats)
+    }
+
+    pub fn solve_checkpointed(
+        &self,
+        max_moves: i32,
+        timeout: Option<Duration>,
+        resume: Option<Checkpoint>,
+        interval: Duration,
+        mut save: impl FnMut(&Checkpoint),
+    ) -> Result<(Option<Vec<Color>>, SearchStats), String> {
+        let puzzle = stable_hash(&(self.to_yaml(), hash_version()));
+        let mut frontier = match resume {
+            Some(checkpoint) => {
+                if checkpoint.puzzle != puzzle || checkpoint.max_moves != max_moves {
+                    return Err(String::from(
+                        "the checkpoint was made for a different puzzle or move bound, or by a \
+                         build that hashes positions differently",
+                    ));
+                }
+                let mut open = vec![];
+                for moves in &checkpoint.open {
+                    let state = self.replay_checked(moves).ok_or_else(|| {
+                        String::from("the checkpoint names a block the puzzle does not have")
+                    })?;
+                    open.push(state);
+                }
+                Frontier {
+                    open,
+                    seen: checkpoint.seen.into_iter().collect(),
+                    stats: SearchStats {
+                        expanded: checkpoint.expanded,
+                        generated: checkpoint.generated,
+                        ..SearchStats::default()
+                    },
+                    keep_cut_off: false,
+                    tie_break: TieBreak::default(),
+                    open_list: OpenList::default(),
+                    max_expanded: None,
+                    max_memory: None,
+                    max_depth: None,
+                    progress: None,
+                    progress_interval: Duration::from_secs(1),
+                }
+            }
+            None => Frontier::start(self.initial_board_state()),
+        };
+
+        let _span = info_span!("search", algorithm = "astar", max_moves).entered();
+        let deadline = solver::deadline(timeout);
+        let (state, stats) = astar_from(
+            &mut frontier,
+            max_moves,
+            deadline,
+            None,
+            |_, _, _| {},
+            Some(interval),
+            |open: Vec<&BoardState>, seen, stats| {
+                debug!(open = open.len(), seen = seen.len(), "writing checkpoint");
+                save(&Checkpoint {
+                    puzzle,
+                    max_moves,
+                    expanded: stats.expanded,
+                    generated: stats.generated,
+                    seen: seen.iter().copied().collect(),
+                    open: open
+                        .into_iter()
+                        .map(|state| state.move_history.to_vec())
+                        .collect(),
+                })
+            },
+        );
+        info!(
+            expanded = stats.expanded,
+            generated = stats.generated,
+            timed_out = stats.timed_out,
+            solved = state.is_some(),
+            "search finished"
+        );
+        Ok((state.map(|state| state.move_history.into_vec()), stats))
+    }
+
+    // Tries each bound in turn, keeping the work done under the previous ones. Also returns the
+    // last bound that was searched.
+    pub fn solve_raising(
+        &self,
+        bounds: impl IntoIterator<Item = i32>,
+        timeout: Option<Duration>,
+    ) -> (Option<Vec<Color>>, SearchStats, Option<i32>) {
+        let _span = info_span!("search", algorithm = "astar_raising").entered();
+        let deadline = solver::deadline(timeout);
+        let (state, stats, bound) = astar_raising(self.initial_board_state(), bounds, deadline);
+        info!(
+            expanded = stats.expanded,
+            generated = stats.generated,
+            timed_out = stats.timed_out,
+            solved = state.is_some(),
+            bound,
+            "search finished"
+        );
+        (
+            state.map(|state| state.move_history.into_vec()),
+            stats,
+            bound,
+        )
+    }
+
+    pub fn analyze(&self, max_depth: usize, max_states: usize) -> Analysis {
+        let _span = info_span!("analyze", max_depth, max_states).entered();
+        analysis::analyze(self.initial_board_state(), max_depth, max_states)
+    }
+
+    /// Compares the heuristic `options` choose, with their weight, against the fewest moves to a
+    /// goal from every position within `max_depth` moves, up to `max_states` positions. Each
+    /// example position is given as the moves that reach it from the start.
+    pub fn check_heuristic(
+        &self,
+        options: &SolverOptions,
+        max_depth: usize,
+        max_states: usize,
+    ) -> HeuristicCheck<Vec<Color>> {
+        let _span = info_span!("check_heuristic", max_depth, max_states).entered();
+        analysis::check_heuristic(self.start_state(options), max_depth, max_states)
+            .map(|state| state.move_history.into_vec())
+    }
+
+    pub fn solve_optimal(&self, max_moves: i32) -> Option<Vec<Color>> {
+        self.solve_optimal_with_stats(max_moves).0
+    }
+
+    pub fn solve_optimal_with_stats(&self, max_moves: i32) -> (Option<Vec<Color>>, SearchStats) {
+        self.solve_breadth_first(&SolverOptions::new(max_moves).algorithm(Algorithm::BreadthFirst))
+    }
+
+    fn solve_breadth_first(&self, options: &SolverOptions) -> (Option<Vec<Color>>, SearchStats) {
+        let max_moves = options.move_bound();
+        let _span = info_span!("search", algorithm = "breadth_first", max_moves).entered();
+        let (state, stats) = shortest_path_observed(
+            self.start_state(options),
+            max_moves,
+            options.search_limits(),
+            |s| s.is_goal(),
+            |_| {},
+        );
+        info!(
+            expanded = stats.expanded,
+            generated = stats.generated,
+            solved = state.is_some(),
+            timed_out = stats.timed_out,
+            cancelled = stats.cancelled,
+            "search finished"
+        );
+        (state.map(|state| state.move_history.into_vec()), stats)
+    }
+
+    pub fn solve_optimal_after(&self, moves: &[Color], max_moves: i32) -> Option<Vec<Color>> {
+        let mut start = self.initial_board_state();
+        for color in moves {
+            start = start.move_square(color);
+        }
+        let max_cost = start.cost + max_moves;
+
+        let (end, _) = shortest_path(start, max_cost, |s| s.is_goal());
+        end.map(|end| end.move_history.to_vec().split_off(moves.len()))
+    }
+
+    pub fn solve_all_optimal(&self, max_moves: i32) -> Vec<Vec<Color>> {
+        let _span = info_span!("search", algorithm = "all_optimal", max_moves).entered();
+        // Two moves that lead to the same position start different solutions.
+        let start = BoardState {
+            dedupe_successors: false,
+            ..self.initial_board_state()
+        };
+        all_optimal(start, max_moves)
+            .into_iter()
+            .map(|state| state.move_history.into_vec())
+            .collect()
+    }
+
+    pub fn count_solutions(&self, max_moves: usize) -> u64 {
+        let _span = info_span!("search", algorithm = "count", max_moves).entered();
+        // Moving a block and straight back is a different sequence, so it is counted too, and
+        // so is each of several moves that lead to the same position.
+        let start = BoardState {
+            prune_inverses: false,
+            dedupe_successors: false,
+            ..self.initial_board_state()
+        };
+        count_solutions(start, max_moves)
+    }
+
+    pub fn render_after(&self, moves: &[Color]) -> String {
+        let mut state = self.initial_board_state();
+        for color in moves {
+            state = state.move_square(color);
+        }
+
+        state.render()
+    }
+
+    pub fn initial_state(&self) -> PuzzleState {
+        PuzzleState {
+            blocks: self.initial_state.clone(),
+        }
+    }
+
+    /// The position after moving the block of `color`, or `None` if there is no such block, the
+    /// rules do not allow the move, or `state` does not have the same blocks as this puzzle.
+    /// Deadlines are not checked, and every arrow counts as there even if the rules consume
+    /// arrows, since those depend on the moves that came before.
+    pub fn apply_move(&self, state: &PuzzleState, color: &str) -> Option<PuzzleState> {
+        if !state.blocks.contains_key(color) {
+            return None;
+        }
+
+        let before = self.board_state(state)?;
+        let next = before.move_square(&color.to_string());
+        if next.violation(&before).is_some() {
+            return None;
+        }
+        Some(PuzzleState {
+            blocks: next.squares.to_map(),
+        })
+    }
+
+    /// Every position one move before `state`, with the block that move moved, sorted by color.
+    /// A block that is on an arrow may have been facing any way before it got there, so each
+    /// way is tried and kept only if the move really leads to `state`. Under
+    /// [`ArrowPush::Follow`], moves whose pushes turn at an arrow are not found, and neither are
+    /// moves through a teleporter or onto a pit.
+    pub fn predecessors(&self, state: &PuzzleState) -> Vec<(Color, PuzzleState)> {
+        const DIRECTIONS: [Direction; 4] = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ];
+        let step = |position: Position2D, offset: Position2D, times: i32| {
+            Some([
+                position[0].checked_add(offset[0].checked_mul(times)?)?,
+                position[1].checked_add(offset[1].checked_mul(times)?)?,
+            ])
+        };
+        // The ways a block at `position` facing `direction` could have been facing before.
+        let facings = |position: &Position2D, direction: &Direction| {
+            if self.arrows.contains_key(position) {
+                DIRECTIONS.to_vec()
+            } else {
+                vec![direction.clone()]
+            }
+        };
+        let occupant: HashMap<Position2D, &Color> = state
+            .blocks
+            .iter()
+            .map(|(color, block)| (block.position, color))
+            .collect();
+
+        let mut found = vec![];
+        for (color, block) in state.blocks() {
+            for direction in facings(&block.position, &block.direction) {
+                let offset = direction.offset();
+                let Some(from) = step(block.position, offset, -1) else {
+                    continue;
+                };
+                if occupant.contains_key(&from) {
+                    continue;
+                }
+                let mut ahead = vec![];
+                while let Some(other) = step(block.position, offset, ahead.len() as i32 + 1)
+                    .and_then(|position| occupant.get(&position))
+                {
+                    ahead.push(*other);
+                }
+
+                // The move may have pushed any number of the blocks lined up in front.
+                for pushed in 0..=ahead.len() {
+                    let mut before = state.blocks.clone();
+                    before.insert(
+                        color.clone(),
+                        Block {
+                            position: from,
+                            direction: direction.clone(),
+                        },
+                    );
+                    let mut candidates = vec![before];
+                    for (i, other) in ahead[..pushed].iter().enumerate() {
+                        let now = &state.blocks[*other];
+                        // The cell behind it, where the block before it in the line is now.
+                        let position = match i {
+                            0 => block.position,
+                            i => state.blocks[ahead[i - 1]].position,
+                        };
+                        candidates = candidates
+                            .into_iter()
+                            .flat_map(|before| {
+                                facings(&now.position, &now.direction).into_iter().map(
+                                    move |direction| {
+                                        let mut before = before.clone();
+                                        before.insert(
+                                            (*other).clone(),
+                                            Block {
+                                                position,
+                                                direction,
+                                            },
+                                        );
+                                        before
+                                    },
+                                )
+                            })
+                            .collect();
+                    }
+
+                    for blocks in candidates {
+                        let before = PuzzleState { blocks };
+                        if self.apply_move(&before, color).as_ref() == Some(state) {
+                            found.push((color.clone(), before));
+                        }
+                    }
+                }
+            }
+        }
+
+        found
+    }
+
+    pub fn apply_moves(&self, state: &PuzzleState, moves: &[Color]) -> Option<PuzzleState> {
+        let mut state = state.clone();
+        for color in moves {
+            state = self.apply_move(&state, color)?;
+        }
+
+        Some(state)
+    }
+
+    /// The initial state followed by the state after each move, or `None` if a move names a
+    /// block the puzzle does not have, is not allowed by the rules, or breaks a deadline.
+    pub fn states_along(&self, moves: &[Color]) -> Option<Vec<PuzzleState>> {
+        let mut state = self.initial_board_state();
+        let mut states = vec![PuzzleState {
+            blocks: state.squares.to_map(),
+        }];
+        for color in moves {
+            if !state.squares.contains_key(color) {
+                return None;
+            }
+            let next = state.move_square(color);
+            if next.violation(&state).is_some() || next.broken_deadline(&state).is_some() {
+                return None;
+            }
+            state = next;
+            states.push(PuzzleState {
+                blocks: state.squares.to_map(),
+            });
+        }
+
+        Some(states)
+    }
+
+    /// For each move, the blocks whose position or direction it changed, the moved block first
+    /// and the rest by color. `None` if a move names a block the puzzle does not have.
+    pub fn diff_moves(&self, moves: &[Color]) -> Option<Vec<Vec<Change>>> {
+        let states = self.states_along(moves)?;
+        let diffs = states
+            .windows(2)
+            .zip(moves)
+            .map(|(pair, color)| {
+                let mut changes: Vec<Change> = pair[0]
+                    .blocks()
+                    .into_iter()
+                    .filter_map(|(other, before)| {
+                        let after = pair[1].block(other)?;
+                        (after != before).then(|| Change {
+                            color: other.clone(),
+                            moved: other == color,
+                            before: before.clone(),
+                            after: after.clone(),
+                        })
+                    })
+                    .collect();
+                changes.sort_by_key(|change| !change.moved);
+                changes
+            })
+            .collect();
+
+        Some(diffs)
+    }
+
+    pub fn describe_moves(&self, moves: &[Color]) -> Option<Vec<Move>> {
+        let mut state = self.initial_board_state();
+        let mut described = vec![];
+        for color in moves {
+            let block = state.squares.get(color)?;
+            let mut pushed = vec![];
+            let next = state.move_square_recording(color, Some(&mut pushed));
+            let moved = &next.squares[color];
+            let id = |color: &Color| ColorId(state.squares.index_of(color).unwrap());
+
+            described.push(Move {
+                block: id(color),
+                direction: block.direction.clone(),
+                from: block.position,
+                to: moved.position,
+                pushed: pushed.iter().map(id).collect(),
+                redirected_to: (moved.direction != block.direction)
+                    .then(|| moved.direction.clone()),
+            });
+            state = next;
+        }
+
+        Some(described)
+    }
+
+    /// Each move with its purpose, how it changed the heuristic's estimate, and which blocks it
+    /// brought nearer their goals or took away from them. `None` if a move names a block the
+    /// puzzle does not have.
+    pub fn explain_moves(&self, moves: &[Color]) -> Option<Vec<Explanation>> {
+        let steps = self.describe_moves(moves)?;
+        let states = self.replay(moves);
+        let distance = |state: &BoardState, color: &Color| {
+            let position = state.squares.get(color)?.position;
+            self.goals_of(color)
+                .iter()
+                .map(|goal| self.rules.distance(&position, goal))
+                .min()
+        };
+
+        let explained = steps
+            .into_iter()
+            .zip(states.windows(2))
+            .map(|(step, pair)| {
+                let (mut closer, mut farther) = (vec![], vec![]);
+                for color in self.colors() {
+                    match (distance(&pair[0], &color), distance(&pair[1], &color)) {
+                        (Some(before), Some(after)) if after < before => closer.push(color),
+                        (Some(before), Some(after)) if after > before => farther.push(color),
+                        _ => {}
+                    }
+                }
+                let purpose = if !step.pushed.is_empty() {
+                    Purpose::Pushing
+                } else if step.redirected_to.is_some() {
+                    Purpose::Redirecting
+                } else {
+                    Purpose::Positioning
+                };
+                Explanation {
+                    step,
+                    purpose,
+                    estimate_before: pair[0].distance_to_goal(),
+                    estimate_after: pair[1].distance_to_goal(),
+                    closer,
+                    farther,
+                }
+            })
+            .collect();
+
+        Some(explained)
+    }
+
+    /// Whether `state` is solved, which a state from a puzzle with other blocks never is.
+    pub fn is_solved(&self, state: &PuzzleState) -> bool {
+        self.board_state(state).is_some_and(|state| state.is_goal())
+    }
+
+    /// `state` drawn on this puzzle's board, or nothing if it is from a puzzle with other blocks.
+    pub fn render_state(&self, state: &PuzzleState) -> String {
+        self.board_state(state)
+            .map(|state| state.render())
+            .unwrap_or_default()
+    }
+
+    /// `state` on this puzzle's board, for printing with `{}`.
+    pub fn display_state<'a>(&'a self, state: &'a PuzzleState) -> StateDisplay<'a> {
+        StateDisplay { game: self, state }
+    }
+
+    /// `step` with its blocks named by color, for printing with `{}`, e.g. `red right (0, 0) ->
+    /// (1, 0); red pushes blue`.
+    pub fn display_move<'a>(&'a self, step: &'a Move) -> MoveDisplay<'a> {
+        MoveDisplay { game: self, step }
+    }
+
+    /// `explanation` with its blocks named by color, for printing with `{}`.
+    pub fn display_explanation<'a>(
+        &'a self,
+        explanation: &'a Explanation,
+    ) -> ExplanationDisplay<'a> {
+        ExplanationDisplay {
+            game: self,
+            explanation,
+        }
+    }
+
+    pub fn bounds_after(&self, moves: &[Color]) -> (Position2D, Position2D) {
+        self.replay(moves).last().unwrap().bounds()
+    }
+
+    pub fn positions_after(&self, moves: &[Color]) -> HashMap<Color, Position2D> {
+        let mut state = self.initial_board_state();
+        for color in moves {
+            state = state.move_square(color);
+        }
+
+        state
+            .squares
+            .iter()
+            .map(|(color, block)| (color.clone(), block.position))
+            .collect()
+    }
+
+    pub fn solution_path(&self, moves: &[Color]) -> Vec<ExpansionRecord> {
+        let mut parent = None;
+        self.replay(moves)
+            .iter()
+            .map(|state| {
+                let hash = state.canonical_hash();
+                let record = ExpansionRecord::new(state, hash, parent);
+                parent = Some(hash);
+                record
+            })
+            .collect()
+    }
+
+    pub fn record_solution(&self, moves: &[Color]) -> Option<SolutionRecord> {
+        let mut state = self.initial_state();
+        let mut states = vec![state.blocks.clone().into_iter().collect()];
+        for color in moves {
+            state = self.apply_move(&state, color)?;
+            states.push(state.blocks.clone().into_iter().collect());
+        }
+
+        Some(SolutionRecord {
+            moves: moves.to_vec(),
+            solved: self.is_solved(&state),
+            states,
+            final_positions: state
+                .blocks
+                .into_iter()
+                .map(|(color, block)| (color, block.position))
+                .collect(),
+        })
+    }
+
+    /// The position `moves` lead to, or the first move that cannot be played, checked by the same
+    /// rules the solver moves by. Whether the position solves the puzzle is up to
+    /// [`Game::is_solved`].
+    pub fn verify_solution(&self, moves: &[Color]) -> Result<PuzzleState, InvalidMove> {
+        let mut state = self.initial_board_state();
+        for (index, color) in moves.iter().enumerate() {
+            let invalid = |error| InvalidMove {
+                index,
+                color: color.clone(),
+                error,
+            };
+            let next = state.checked_move(color).map_err(invalid)?;
+            if next.squares.iter().eq(state.squares.iter()) {
+                return Err(invalid(MoveError::NoEffect));
+            }
+            state = next;
+        }
+        Ok(PuzzleState {
+            blocks: state.squares.to_map(),
+        })
+    }
+
+    pub fn verify(&self, moves: &[Color]) -> Verification {
+        let mut state = self.initial_board_state();
+        let mut first_solved_after = state.is_goal().then_some(0);
+        let mut unknown_color = None;
+        let mut forbidden_move = None;
+        let mut violation = None;
+        let mut missed_deadline = None;
+
+        for color in moves {
+            match state.checked_move(color) {
+                Ok(next) => state = next,
+                Err(MoveError::UnknownColor) => {
+                    unknown_color = Some(color.clone());
+                    break;
+                }
+                Err(MoveError::Forbidden(reason)) => {
+                    forbidden_move = Some(color.clone());
+                    violation = Some(reason);
+                    break;
+                }
+                Err(MoveError::MissedDeadline(late)) => {
+                    missed_deadline = Some(late);
+                    break;
+                }
+                Err(MoveError::NoEffect) => {
+                    unreachable!("checked_move makes moves that do nothing")
+                }
+            }
+            if first_solved_after.is_none() && state.is_goal() {
+                first_solved_after = Some(state.move_history.len());
+            }
+        }
+
+        Verification {
+            solved: unknown_color.is_none()
+                && forbidden_move.is_none()
+                && missed_deadline.is_none()
+                && state.is_goal(),
+            moves_applied: state.move_history.len(),
+            first_solved_after,
+            unknown_color,
+            forbidden_move,
+            violation,
+            missed_deadline,
+            board: state.render(),
+        }
+    }
+
+    pub fn optimize_solution(&self, moves: &[Color], window: usize) -> Vec<Color> {
+        let mut moves = moves.to_vec();
+
+        loop {
+            let states = self.replay(&moves);
+            let shortened =
+                Self::remove_cycles(&states).or_else(|| Self::shorten_window(&states, window));
+
+            match shortened {
+                Some(shorter) => moves = shorter,
+                None => return moves,
+            }
+        }
+    }
+
+    fn replay_checked(&self, moves: &[Color]) -> Option<BoardState> {
+        let mut state = self.initial_board_state();
+        for color in moves {
+            if !state.squares.contains_key(color) {
+                return None;
+            }
+            state = state.move_square(color);
+        }
+
+        Some(state)
+    }
+
+    fn replay(&self, moves: &[Color]) -> Vec<BoardState> {
+        let mut states = vec![self.initial_board_state()];
+        for color in moves {
+            let next = states.last().unwrap().move_square(color);
+            states.push(next);
+        }
+
+        states
+    }
+
+    fn remove_cycles(states: &[BoardState]) -> Option<Vec<Color>> {
+        let mut last_seen = HashMap::new();
+        for (i, state) in states.iter().enumerate() {
+            last_seen.insert(state.fingerprint(), i);
+        }
+
+        for (i, state) in states.iter().enumerate() {
+            let last = last_seen[&state.fingerprint()];
+            if last > i {
+                let moves = states.last().unwrap().move_history.to_vec();
+                let mut shorter = moves[..i].to_vec();
+                shorter.extend_from_slice(&moves[last..]);
+                return Some(shorter);
+            }
+        }
+
+        None
+    }
+
+    fn shorten_window(states: &[BoardState], window: usize) -> Option<Vec<Color>> {
+        let moves = states.last().unwrap().move_history.to_vec();
+        let n = moves.len();
+
+        for i in 0..n {
+            if n - i <= window {
+                let start = states[i].clone();
+                let max_cost = start.cost + (n - i) as i32 - 1;
+                if let (Some(end), _) = shortest_path(start, max_cost, |s| s.is_goal()) {
+                    return Some(end.move_history.into_vec());
+                }
+            }
+
+            for j in ((i + 2)..=(i + window).min(n)).rev() {
+                let start = states[i].clone();
+                let max_cost = start.cost + (j - i) as i32 - 1;
+                let target = states[j].fingerprint();
+                if let (Some(end), _) =
+                    shortest_path(start, max_cost, |s| s.fingerprint() == target)
+                {
+                    let mut shorter = end.move_history.into_vec();
+                    shorter.extend_from_slice(&moves[j..]);
+                    return Some(shorter);
+                }
+            }
+        }
+
+        None
+    }
+
+    // `None` if `state` is from a puzzle with other blocks.
+    fn board_state(&self, state: &PuzzleState) -> Option<BoardState> {
+        self.state_with(&state.blocks)
+    }
+
+    fn state_with(&self, blocks: &HashMap<Color, Block>) -> Option<BoardState> {
+        let board = self.board();
+        let mut board = BoardState {
+            board: board.clone(),
+            cost: 0,
+            squares: Squares::new(board.colors.clone(), blocks)?,
+            move_history: Moves::default(),
+            pushes: 0,
+            undo: None,
+            deadlines_met: vec![],
+            spent_tiles: vec![],
+            stopped: None,
+            prune_inverses: true,
+            dedupe_successors: true,
+            heuristic: Heuristic::default(),
+            weight: 1.0,
+            known: None,
+            bound: i32::MAX,
+        };
+        board.update_deadlines(&vec![false; self.deadlines.len()]);
+        Some(board)
+    }
+
+    fn start_state(&self, options: &SolverOptions) -> BoardState {
+        BoardState {
+            heuristic: options.heuristic,
+            weight: options.weight,
+            known: options.known.clone(),
+            bound: options.move_bound(),
+            // IDA* remembers what it proved below a position for when it reaches the position
+            // from elsewhere, so the moves there cannot depend on how it was reached.
+            prune_inverses: options.algorithm != Algorithm::IdaStar,
+            move_history: if options.compact_paths {
+                Moves::linked(self.board().colors.clone())
+            } else {
+                Moves::default()
+            },
+            ..self.initial_board_state()
+        }
+    }
+
+    fn initial_board_state(&self) -> BoardState {
+        self.state_with(&self.initial_state)
+            .expect("the puzzle's own blocks")
+    }
+}
+
+// `text` as a YAML scalar that reads back as the same string: plain if it is a word YAML would not
+// take for anything else, double-quoted otherwise.
+fn yaml_string(text: &str) -> String {
+    let plain = text.starts_with(|c: char| c.is_ascii_alphabetic())
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        && !matches!(
+            text.to_ascii_lowercase().as_str(),
+            "true" | "false" | "null" | "yes" | "no" | "on" | "off" | "y" | "n"
+        );
+    if plain {
+        return text.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Draws the puzzle's starting position, as `render` does.
+impl Display for Game {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.initial_board_state().render())
+    }
+}
+
+/// A position drawn on its puzzle's board, from [`Game::display_state`].
+pub struct StateDisplay<'a> {
+    game: &'a Game,
+    state: &'a PuzzleState,
+}
+
+impl Display for StateDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.game.render_state(self.state))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SerializedBlock {
+    color: Color,
+    direction: Direction,
+    position: Position2D,
+    goal: Option<SerializedGoal>,
+    #[serde(default)]
+    avoid: Vec<Position2D>,
+    deadline: Option<SerializedDeadline>,
+}
+
+// A block's goal is one position or a list of positions, any of which will do.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SerializedGoal {
+    One(Position2D),
+    AnyOf(Vec<Position2D>),
+}
+
+// A deadline is the move to be on a goal by, or that move and whether to stay there.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SerializedDeadline {
+    By(usize),
+    Full {
+        by: usize,
+        #[serde(default)]
+        stay: bool,
+    },
+}
+
+impl From<SerializedDeadline> for Deadline {
+    fn from(deadline: SerializedDeadline) -> Self {
+        match deadline {
+            SerializedDeadline::By(by) => Deadline { by, stay: false },
+            SerializedDeadline::Full { by, stay } => Deadline { by, stay },
+        }
+    }
+}
+
+impl SerializedBlock {
+    fn add_to(self, game: &mut Game) {
+        game.add_block(self.color.clone(), self.direction, self.position, None);
+        let goals = match self.goal {
+            Some(SerializedGoal::One(goal)) => vec![goal],
+            Some(SerializedGoal::AnyOf(goals)) => goals,
+            None => vec![],
+        };
+        for goal in goals {
+            game.add_goal(&self.color, goal);
+        }
+        for cell in self.avoid {
+            game.avoid_for(&self.color, cell);
+        }
+        game.set_deadline(&self.color, self.deadline.map(Deadline::from));
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SerializedArrow {
+    direction: Direction,
+    position: Position2D,
+}
+
+// A tile under `tiles`, with its position alongside whatever its kind needs.
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase", deny_unknown_fields)]
+enum SerializedTile {
+    Arrow {
+        direction: Direction,
+        position: Position2D,
+    },
+    Wall {
+        position: Position2D,
+    },
+    Pit {
+        position: Position2D,
+    },
+    Teleporter {
+        to: Position2D,
+        position: Position2D,
+    },
+}
+
+impl SerializedTile {
+    fn add_to(self, game: &mut Game) {
+        let (position, spec) = match self {
+            SerializedTile::Arrow {
+                direction,
+                position,
+            } => (position, TileSpec::Arrow { direction }),
+            SerializedTile::Wall { position } => (position, TileSpec::Wall),
+            SerializedTile::Pit { position } => (position, TileSpec::Pit),
+            SerializedTile::Teleporter { to, position } => (position, TileSpec::Teleporter { to }),
+        };
+        game.add_tile(position, spec.build());
+    }
+}
+
+// A puzzle file as written. Its blocks and arrows go on top of the puzzle it `extends`, replacing
+// blocks of the same color and arrows at the same position.
+#[cfg(feature = "yaml")]
+#[derive(Default)]
+pub(crate) struct PuzzleLayer {
+    pub(crate) extends: Option<PathBuf>,
+    blocks: Vec<SerializedBlock>,
+    arrows: Vec<SerializedArrow>,
+    tiles: Vec<SerializedTile>,
+    avoid: Vec<Position2D>,
+    rules: Option<Ruleset>,
+}
+
+#[cfg(feature = "yaml")]
+impl PuzzleLayer {
+    // Reads one file's layer, failing at the first item of a list past `limits`. The puzzle the
+    // layers make together is checked once each layer is applied.
+    pub(crate) fn deserialize_with_limits<'de, D>(
+        deserializer: D,
+        limits: &Limits,
+    ) -> Result<PuzzleLayer, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct LayerVisitor<'a> {
+            limits: &'a Limits,
+        }
+
+        impl<'de> Visitor<'de> for LayerVisitor<'_> {
+            type Value = PuzzleLayer;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a puzzle with blocks, arrows, tiles, and the file it extends")
+            }
+
+            fn visit_map<V>(self, mut map: V) -> Result<PuzzleLayer, V::Error>
+            where
+                V: MapAccess<'de>,
+            {
+                let limits = self.limits;
+                let mut layer = PuzzleLayer::default();
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "extends" => layer.extends = map.next_value()?,
+                        "blocks" => {
+                            layer.blocks = map.next_value_seed(Capped::new(
+                                limits.max_blocks,
+                                limits.max_blocks,
+                                "blocks",
+                            ))?
+                        }
+                        "arrows" => {
+                            layer.arrows = map.next_value_seed(Capped::new(
+                                limits.max_arrows,
+                                limits.max_arrows,
+                                "arrows",
+                            ))?
+                        }
+                        "tiles" => {
+                            layer.tiles = map.next_value_seed(Capped::new(
+                                limits.max_arrows,
+                                limits.max_arrows,
+                                "tiles",
+                            ))?
+                        }
+                        "avoid" => {
+                            layer.avoid = map.next_value_seed(Capped::new(
+                                limits.max_cells,
+                                limits.max_cells,
+                                "avoided cells",
+                            ))?
+                        }
+                        "rules" => layer.rules = map.next_value()?,
+                        _ => {
+                            return Err(serde::de::Error::unknown_field(
+                                &key,
+                                &["extends", "blocks", "arrows", "tiles", "avoid", "rules"],
+                            ));
+                        }
+                    }
+                }
+                Ok(layer)
+            }
+        }
+
+        deserializer.deserialize_map(LayerVisitor { limits })
+    }
+
+    pub(crate) fn apply_to(self, game: &mut Game) {
+        for block in self.blocks {
+            block.add_to(game);
+        }
+        for arrow in self.arrows {
+            game.add_arrow(arrow.direction, arrow.position);
+        }
+        for tile in self.tiles {
+            tile.add_to(game);
+        }
+        for cell in self.avoid {
+            game.avoid(cell);
+        }
+        if let Some(rules) = self.rules {
+            game.set_rules(rules);
+        }
+    }
+}
+
+/// Reads a puzzle held to the default [`Limits`].
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Game::deserialize_with_limits(deserializer, &Limits::default())
+    }
+}
+
+impl Game {
+    /// Reads a puzzle from any serde format, failing as soon as it goes past one of `limits`.
+    pub fn deserialize_with_limits<'de, D>(
+        deserializer: D,
+        limits: &Limits,
+    ) -> Result<Game, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct GameVisitor<'a> {
+            limits: &'a Limits,
+        }
+
+        impl<'de> Visitor<'de> for GameVisitor<'_> {
+            type Value = Game;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a game with values for blocks and (optionally) arrows")
+            }
+
+            fn visit_map<V>(self, mut map: V) -> Result<Game, V::Error>
+            where
+                V: MapAccess<'de>,
+            {
+                let mut game = Game::new();
+                let (mut blocks_read, mut arrows_read, mut tiles_read) = (0, 0, 0);
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "blocks" => {
+                            let blocks: Vec<SerializedBlock> = map.next_value_seed(Capped::new(
+                                self.limits.max_blocks - blocks_read,
+                                self.limits.max_blocks,
+                                "blocks",
+                            ))?;
+                            blocks_read += blocks.len();
+                            for block in blocks {
+                                block.add_to(&mut game);
+                            }
+                        }
+                        "arrows" => {
+                            let arrows: Vec<SerializedArrow> = map.next_value_seed(Capped::new(
+                                self.limits.max_arrows - arrows_read,
+                                self.limits.max_arrows,
+                                "arrows",
+                            ))?;
+                            arrows_read += arrows.len();
+                            for arrow in arrows {
+                                game.add_arrow(arrow.direction, arrow.position);
+                            }
+                        }
+                        "tiles" => {
+                            let tiles: Vec<SerializedTile> = map.next_value_seed(Capped::new(
+                                self.limits.max_arrows - tiles_read,
+                                self.limits.max_arrows,
+                                "tiles",
+                            ))?;
+                            tiles_read += tiles.len();
+                            for tile in tiles {
+                                tile.add_to(&mut game);
+                            }
+                        }
+                        "avoid" => {
+                            let cells: Vec<Position2D> = map.next_value_seed(Capped::new(
+                                self.limits.max_cells,
+                                self.limits.max_cells,
+                                "avoided cells",
+                            ))?;
+                            for cell in cells {
+                                game.avoid(cell);
+                            }
+                        }
+                        "rules" => game.set_rules(map.next_value()?),
+                        "extends" => {
+                            return Err(serde::de::Error::custom(
+                                "`extends` can only be used in a puzzle read from a file",
+                            ));
+                        }
+                        _ => {
+                            return Err(serde::de::Error::unknown_field(
+                                &key,
+                                &["blocks", "arrows", "tiles", "avoid", "rules"],
+                            ));
+                        }
+                    }
+                }
+
+                game.check_limits(self.limits)
+                    .map_err(serde::de::Error::custom)?;
+                Ok(game)
+            }
+        }
+
+        deserializer.deserialize_map(GameVisitor { limits })
+    }
+
+    // Says which of `limits` the puzzle goes past, if any.
+    pub(crate) fn check_limits(&self, limits: &Limits) -> Result<(), String> {
+        if self.initial_state.len() > limits.max_blocks {
+            return Err(format!("more than {} blocks", limits.max_blocks));
+        }
+        if self.arrows.len() > limits.max_arrows {
+            return Err(format!("more than {} arrows", limits.max_arrows));
+        }
+        if self.tiles.len() > limits.max_arrows {
+            return Err(format!("more than {} tiles", limits.max_arrows));
+        }
+        let cells = self.goals.values().map(Vec::len).sum::<usize>()
+            + self.avoided.len()
+            + self.avoided_by.values().map(HashSet::len).sum::<usize>();
+        if cells > limits.max_cells {
+            return Err(format!(
+                "more than {} goals and avoided cells",
+                limits.max_cells
+            ));
+        }
+
+        let destinations: Vec<Position2D> = self
+            .tiles
+            .values()
+            .filter_map(|tile| match tile.spec() {
+                TileSpec::Teleporter { to } => Some(to),
+                _ => None,
+            })
+            .collect();
+        let positions = self
+            .initial_state
+            .values()
+            .map(|block| &block.position)
+            .chain(self.goals.values().flatten())
+            .chain(self.arrows.keys())
+            .chain(self.tiles.keys())
+            .chain(&destinations)
+            .chain(&self.avoided)
+            .chain(self.avoided_by.values().flatten())
+            .chain(self.rules.bounds.iter().flatten());
+        for position in positions {
+            if position
+                .iter()
+                .any(|coordinate| coordinate.unsigned_abs() > limits.max_coordinate.unsigned_abs())
+            {
+                return Err(format!(
+                    "position [{}, {}] is more than {} from 0",
+                    position[0], position[1], limits.max_coordinate
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// A list read only as far as its `remaining` items, so a list far too long fails at the first
+// item past the limit rather than after all of it is in memory.
+struct Capped<T> {
+    remaining: usize,
+    limit: usize,
+    what: &'static str,
+    items: PhantomData<T>,
+}
+
+impl<T> Capped<T> {
+    fn new(remaining: usize, limit: usize, what: &'static str) -> Self {
+        Capped {
+            remaining,
+            limit,
+            what,
+            items: PhantomData,
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for Capped<T> {
+    type Value = Vec<T>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for Capped<T> {
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "a list of {}", self.what)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Vec<T>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            if items.len() == self.remaining {
+                return Err(serde::de::Error::custom(format!(
+                    "more than {} {}",
+                    self.limit, self.what
+                )));
+            }
+            items.push(item);
+        }
+        Ok(items)
+    }
+}
+
+// The parts of a game that a search reads but never changes, shared by every state of the search.
+// States hold it by `Arc` rather than borrowing the game, so they can be sent to other threads.
+#[derive(Debug)]
+struct StaticBoard {
+    // Sorted, which is the order successors are generated in.
+    colors: Arc<[Color]>,
+    // The goals of each color in `colors`, empty for a color without goals.
+    goals: Vec<Vec<Position2D>>,
+    tiles: Tiles,
+    avoided_by: HashMap<Color, HashSet<Position2D>>,
+    // Whether any cell is avoided, by every block or by some.
+    avoids_cells: bool,
+    deadlines: BTreeMap<Color, Deadline>,
+    // Whether there are tiles other than arrows, any of which might send a block further than the
+    // cell in front of it.
+    other_tiles: bool,
+    rules: Ruleset,
+    symmetries: Vec<Symmetry>,
+    // The corners of the rectangle around the goals, tiles, avoided cells and bounds.
+    extent: Option<[Position2D; 2]>,
+}
+
+impl StaticBoard {
+    fn new(game: &Game) -> Self {
+        let colors: Arc<[Color]> = game.colors().into();
+        let goals = colors
+            .iter()
+            .map(|color| game.goals_of(color).to_vec())
+            .collect();
+        let fixed = game
+            .goals
+            .values()
+            .flatten()
+            .chain(game.arrows.keys())
+            .chain(game.tiles.keys())
+            .chain(&game.avoided)
+            .chain(game.rules.bounds.iter().flatten());
+        let mut extent: Option<[Position2D; 2]> = None;
+        for position in fixed {
+            let [min, max] = extent.get_or_insert([*position; 2]);
+            *min = [min[0].min(position[0]), min[1].min(position[1])];
+            *max = [max[0].max(position[0]), max[1].max(position[1])];
+        }
+
+        StaticBoard {
+            colors,
+            goals,
+            tiles: Tiles::new(&game.arrows, &game.tiles, &game.avoided),
+            avoided_by: game.avoided_by.clone(),
+            avoids_cells: !game.avoided.is_empty() || !game.avoided_by.is_empty(),
+            deadlines: game.deadlines.clone(),
+            other_tiles: !game.tiles.is_empty(),
+            rules: game.rules.clone(),
+            symmetries: game.symmetries().to_vec(),
+            extent,
+        }
+    }
+
+    fn index_of(&self, color: &str) -> Option<usize> {
+        self.colors
+            .binary_search_by(|other| other.as_str().cmp(color))
+            .ok()
+    }
+
+    fn goals_of(&self, color: &str) -> &[Position2D] {
+        self.index_of(color)
+            .map(|index| self.goals[index].as_slice())
+            .unwrap_or_default()
+    }
+
+    fn may_enter(&self, color: &str, position: &Position2D) -> bool {
+        !self.tiles.avoided(position)
+            && self
+                .avoided_by
+                .get(color)
+                .is_none_or(|cells| !cells.contains(position))
+    }
+}
+
+// The arrows, other tiles and avoided cells. While they fit in a small enough rectangle, they are kept as a
+// grid covering it, so that pushes look a cell up by its index rather than by hashing its position.
+#[derive(Debug)]
+enum Tiles {
+    Grid {
+        min: Position2D,
+        width: usize,
+        height: usize,
+        cells: Vec<Tile>,
+    },
+    Sparse {
+        arrows: HashMap<Position2D, Direction>,
+        // Every tile, arrows included.
+        effects: HashMap<Position2D, Arc<dyn TileEffect>>,
+        avoided: HashSet<Position2D>,
+    },
+}
+
+#[derive(Clone, Debug, Default)]
+struct Tile {
+    arrow: Option<Direction>,
+    // The arrow or other tile here.
+    effect: Option<Arc<dyn TileEffect>>,
+    avoided: bool,
+}
+
+impl Tiles {
+    // Tiles spread over more cells than this stay in hash maps.
+    const MAX_CELLS: usize = 1 << 20;
+
+    fn new(
+        arrows: &HashMap<Position2D, Direction>,
+        tiles: &HashMap<Position2D, Arc<dyn TileEffect>>,
+        avoided: &HashSet<Position2D>,
+    ) -> Self {
+        let effects: HashMap<Position2D, Arc<dyn TileEffect>> = arrows
+            .iter()
+            .map(|(position, direction)| {
+                let arrow: Arc<dyn TileEffect> = Arc::new(Arrow(direction.clone()));
+                (*position, arrow)
+            })
+            .chain(
+                tiles
+                    .iter()
+                    .map(|(position, tile)| (*position, tile.clone())),
+            )
+            .collect();
+        let mut min = [i32::MAX; 2];
+        let mut max = [i32::MIN; 2];
+        for position in effects.keys().chain(avoided) {
+            min = [min[0].min(position[0]), min[1].min(position[1])];
+            max = [max[0].max(position[0]), max[1].max(position[1])];
+        }
+        let span = |axis: usize| (i64::from(max[axis]) - i64::from(min[axis]) + 1).max(0) as usize;
+        let (width, height) = (span(0), span(1));
+        if width
+            .checked_mul(height)
+            .is_none_or(|cells| cells > Self::MAX_CELLS)
+        {
+            return Tiles::Sparse {
+                arrows: arrows.clone(),
+                effects,
+                avoided: avoided.clone(),
+            };
+        }
+
+        let mut cells = vec![Tile::default(); width * height];
+        let index = |position: &Position2D| Self::grid_index(min, width, height, position);
+        for (position, direction) in arrows {
+            if let Some(index) = index(position) {
+                cells[index].arrow = Some(direction.clone());
+            }
+        }
+        for (position, effect) in effects {
+            if let Some(index) = index(&position) {
+                cells[index].effect = Some(effect);
+            }
+        }
+        for position in avoided {
+            if let Some(index) = index(position) {
+                cells[index].avoided = true;
+            }
+        }
+        Tiles::Grid {
+            min,
+            width,
+            height,
+            cells,
+        }
+    }
+
+    fn grid_index(
+        min: Position2D,
+        width: usize,
+        height: usize,
+        position: &Position2D,
+    ) -> Option<usize> {
+        let offset = |axis: usize, size: usize| {
+            usize::try_from(i64::from(position[axis]) - i64::from(min[axis]))
+                .ok()
+                .filter(|offset| *offset < size)
+        };
+        Some(offset(1, height)? * width + offset(0, width)?)
+    }
+
+    fn arrow_at(&self, position: &Position2D) -> Option<&Direction> {
+        match self {
+            Tiles::Grid {
+                min,
+                width,
+                height,
+                cells,
+            } => Self::grid_index(*min, *width, *height, position)
+                .and_then(|index| cells[index].arrow.as_ref()),
+            Tiles::Sparse { arrows, .. } => arrows.get(position),
+        }
+    }
+
+    fn effect_at(&self, position: &Position2D) -> Option<&Arc<dyn TileEffect>> {
+        match self {
+            Tiles::Grid {
+                min,
+                width,
+                height,
+                cells,
+            } => Self::grid_index(*min, *width, *height, position)
+                .and_then(|index| cells[index].effect.as_ref()),
+            Tiles::Sparse { effects, .. } => effects.get(position),
+        }
+    }
+
+    fn avoided(&self, position: &Position2D) -> bool {
+        match self {
+            Tiles::Grid {
+                min,
+                width,
+                height,
+                cells,
+            } => Self::grid_index(*min, *width, *height, position)
+                .is_some_and(|index| cells[index].avoided),
+            Tiles::Sparse { avoided, .. } => avoided.contains(position),
+        }
+    }
+}
+
+// Where each block is, in the game's move order. The colors are shared by every state of a
+// search, so copying a state to make a move copies only the blocks and allocates once.
+#[derive(Clone, Debug)]
+struct Squares {
+    colors: Arc<[Color]>,
+    blocks: Vec<Block>,
+}
+
+impl Squares {
+    // `colors` must be sorted. `None` unless `blocks` has a block for each of `colors` and no
+    // others, as a state from another puzzle may not.
+    fn new(colors: Arc<[Color]>, blocks: &HashMap<Color, Block>) -> Option<Self> {
+        if blocks.len() != colors.len() {
+            return None;
+        }
+        Some(Squares {
+            blocks: colors
+                .iter()
+                .map(|color| blocks.get(color).cloned())
+                .collect::<Option<_>>()?,
+            colors,
+        })
+    }
+
+    fn index_of(&self, color: &str) -> Option<usize> {
+        self.colors
+            .binary_search_by(|other| other.as_str().cmp(color))
+            .ok()
+    }
+
+    fn get(&self, color: &str) -> Option<&Block> {
+        self.index_of(color).map(|i| &self.blocks[i])
+    }
+
+    fn contains_key(&self, color: &str) -> bool {
+        self.index_of(color).is_some()
+    }
+
+    fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &Color> {
+        self.colors.iter()
+    }
+
+    fn values(&self) -> impl Iterator<Item = &Block> {
+        self.blocks.iter()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&Color, &Block)> {
+        self.colors.iter().zip(&self.blocks)
+    }
+
+    fn to_map(&self) -> HashMap<Color, Block> {
+        self.iter()
+            .map(|(color, block)| (color.clone(), block.clone()))
+            .collect()
+    }
+}
+
+impl std::ops::Index<&str> for Squares {
+    type Output = Block;
+
+    fn index(&self, color: &str) -> &Block {
+        self.get(color).expect("no block of that color")
+    }
+}
+
+// The moves that led to a state. `Full` copies every move into each state. `Linked` stores only
+// the last move, as an index into the colors, and shares the moves before it with the state it
+// was made from, so each state adds one link instead of a copy of the whole list, and the list is
+// rebuilt when a search ends.
+#[derive(Clone, Debug)]
+enum Moves {
+    Full(Vec<Color>),
+    Linked(Arc<[Color]>, Option<Arc<Link>>),
+}
+
+#[derive(Debug)]
+struct Link {
+    color: usize,
+    before: Option<Arc<Link>>,
+    len: usize,
+}
+
+impl Default for Moves {
+    fn default() -> Self {
+        Moves::Full(vec![])
+    }
+}
+
+impl Moves {
+    fn linked(colors: Arc<[Color]>) -> Self {
+        Moves::Linked(colors, None)
+    }
+
+    // The moves followed by the color at `index` in `colors`.
+    fn then(&self, colors: &[Color], index: usize) -> Self {
+        match self {
+            Moves::Full(moves) => {
+                // Built rather than cloned, so the list has room for the new move without growing.
+                let mut next = Vec::with_capacity(moves.len() + 1);
+                next.extend_from_slice(moves);
+                next.push(colors[index].clone());
+                Moves::Full(next)
+            }
+            Moves::Linked(colors, last) => Moves::Linked(
+                colors.clone(),
+                Some(Arc::new(Link {
+                    color: index,
+                    len: self.len() + 1,
+                    before: last.clone(),
+                })),
+            ),
+        }
+    }
+
+    fn last(&self) -> Option<&Color> {
+        match self {
+            Moves::Full(moves) => moves.last(),
+            Moves::Linked(colors, last) => last.as_ref().map(|link| &colors[link.color]),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Moves::Full(moves) => moves.len(),
+            Moves::Linked(_, last) => last.as_ref().map_or(0, |link| link.len),
+        }
+    }
+
+    fn to_vec(&self) -> Vec<Color> {
+        match self {
+            Moves::Full(moves) => moves.clone(),
+            Moves::Linked(colors, last) => {
+                let mut moves = Vec::with_capacity(self.len());
+                let mut link = last.as_deref();
+                while let Some(step) = link {
+                    moves.push(colors[step.color].clone());
+                    link = step.before.as_deref();
+                }
+                moves.reverse();
+                moves
+            }
+        }
+    }
+
+    fn into_vec(self) -> Vec<Color> {
+        match self {
+            Moves::Full(moves) => moves,
+            linked => linked.to_vec(),
+        }
+    }
+}
+
+// Unlinks the moves one at a time, so dropping a long chain cannot exhaust the stack.
+impl Drop for Link {
+    fn drop(&mut self) {
+        let mut before = self.before.take();
+        while let Some(link) = before {
+            match Arc::try_unwrap(link) {
+                Ok(mut link) => before = link.before.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+// The most cells `BoardState::render` draws. A larger board is described by its blocks alone.
+const MAX_RENDERED_CELLS: i64 = 256 * 256;
+
+#[derive(Clone, Debug)]
+struct BoardState {
+    board: Arc<StaticBoard>,
+    cost: i32,
+    squares: Squares,
+    move_history: Moves,
+    pushes: usize,
+    // Where the last block moved was before, if the move pushed nothing.
+    undo: Option<Block>,
+    // For each of the game's deadlines in order, whether its block has been on a goal in time.
+    deadlines_met: Vec<bool>,
+    // The tiles blocks have used up, sorted, such as arrows when the rules consume them.
+    spent_tiles: Vec<Position2D>,
+    // Why the last move could not happen at all, if it could not: a block would have gone past
+    // the largest or smallest coordinate, or a tile stopped it.
+    stopped: Option<Violation>,
+    prune_inverses: bool,
+    // Whether to skip a move that leaves the position as it was or as an earlier move left it.
+    dedupe_successors: bool,
+    heuristic: Heuristic,
+    weight: f64,
+    // Moves still needed from positions an earlier search has seen, and the bound they are
+    // checked against.
+    known: Option<Arc<HashMap<u64, i32>>>,
+    bound: i32,
+}
+
+// How resolving a chain of pushes ended.
+#[derive(PartialEq)]
+enum Push {
+    Done,
+    // The chain came back round to a block it already pushed.
+    Looped,
+    // A block would have moved past the largest or smallest coordinate.
+    OutOfRange,
+    // A tile did not let a block in.
+    Blocked,
+}
+
+impl BoardState {
+    fn move_square(&self, color: &Color) -> Self {
+        self.move_square_recording(color, None)
+    }
+
+    // Like `move_square`, for a move the rules may not allow.
+    fn checked_move(&self, color: &Color) -> Result<Self, MoveError> {
+        if !self.squares.contains_key(color) {
+            return Err(MoveError::UnknownColor);
+        }
+        let next = self.move_square(color);
+        if let Some(violation) = next.violation(self) {
+            return Err(MoveError::Forbidden(violation));
+        }
+        if let Some(late) = next.broken_deadline(self) {
+            return Err(MoveError::MissedDeadline(late.clone()));
+        }
+        Ok(next)
+    }
+
+    // Like `move_square`, and adds every block the move pushed to `chain`, each one pushed by the
+    // block before it.
+    fn move_square_recording(&self, color: &Color, chain: Option<&mut Vec<Color>>) -> Self {
+        let index = self
+            .squares
+            .index_of(color)
+            .expect("no block of that color");
+        let move_history = self.move_history.then(&self.squares.colors, index);
+        let mut new_state = BoardState {
+            board: self.board.clone(),
+            cost: self.cost + 1,
+            squares: self.squares.clone(),
+            move_history,
+            pushes: self.pushes,
+            undo: None,
+            deadlines_met: vec![],
+            spent_tiles: self.spent_tiles.clone(),
+            stopped: None,
+            prune_inverses: self.prune_inverses,
+            dedupe_successors: self.dedupe_successors,
+            heuristic: self.heuristic,
+            weight: self.weight,
+            known: self.known.clone(),
+            bound: self.bound,
+        };
+        let before = self.squares.blocks[index].clone();
+        let mut chain = chain;
+        let recorded = chain.as_ref().map_or(0, |chain| chain.len());
+        let outcome = new_state.push_square(index, before.direction.clone(), chain.as_deref_mut());
+        if outcome == Push::Done {
+            new_state.undo = (new_state.pushes == self.pushes
+                && new_state.spent_tiles.len() == self.spent_tiles.len())
+            .then_some(before);
+        } else {
+            // A push that went round a loop back into itself moves nothing, and one that ran out
+            // of coordinates or into a tile that stops it is not allowed.
+            new_state.stopped = match outcome {
+                Push::OutOfRange => Some(Violation::OutOfRange),
+                Push::Blocked => Some(Violation::Blocked),
+                Push::Done | Push::Looped => None,
+            };
+            new_state.squares = self.squares.clone();
+            new_state.pushes = self.pushes;
+            new_state.spent_tiles = self.spent_tiles.clone();
+            if let Some(chain) = chain {
+                chain.truncate(recorded);
+            }
+        }
+        new_state.update_deadlines(&self.deadlines_met);
+
+        new_state
+    }
+
+    // The index of the block on the same cell as the block at `index`, if there is one.
+    fn find_collision_with(&self, index: usize) -> Option<usize> {
+        let position = self.squares.blocks[index].position;
+        self.squares
+            .blocks
+            .iter()
+            .enumerate()
+            .position(|(other, block)| other != index && block.position == position)
+    }
+
+    // Moves the block at `index`, then the block it lands on, and so on down the chain, one block
+    // at a time so that a long line of blocks cannot exhaust the stack.
+    fn push_square(
+        &mut self,
+        index: usize,
+        direction: Direction,
+        mut chain: Option<&mut Vec<Color>>,
+    ) -> Push {
+        let board = self.board.clone();
+        let rules = &board.rules;
+        let (mut index, mut direction) = (index, direction);
+
+        for depth in 0..self.squares.len() {
+            let position = self.squares.blocks[index].position;
+            let offset = direction.offset();
+            let (Some(x), Some(y)) = (
+                position[0].checked_add(offset[0]),
+                position[1].checked_add(offset[1]),
+            ) else {
+                return Push::OutOfRange;
+            };
+            let mut position = rules.wrapped([x, y]);
+            let entry = board
+                .tiles
+                .effect_at(&position)
+                .map_or_else(Entry::default, |tile| {
+                    tile.on_enter(&Entering {
+                        color: &self.squares.colors[index],
+                        direction: &direction,
+                        pushed: depth > 0,
+                        spent: self.spent_tiles.binary_search(&position).is_ok(),
+                        rules,
+                    })
+                });
+            if entry.blocked {
+                return Push::Blocked;
+            }
+            if entry.consume {
+                if let Err(spent) = self.spent_tiles.binary_search(&position) {
+                    self.spent_tiles.insert(spent, position);
+                }
+            }
+            if let Some(to) = entry.teleport {
+                position = rules.wrapped(to);
+            }
+            let block = &mut self.squares.blocks[index];
+            block.position = position;
+
+            if let Some(new_direction) = entry.turn {
+                if depth == 0 || rules.arrow_push != ArrowPush::Keep {
+                    block.direction = new_direction.clone();
+                }
+                if rules.arrow_push == ArrowPush::Follow {
+                    direction = new_direction;
+                }
+            }
+
+            let Some(collided_block) = self.find_collision_with(index) else {
+                return Push::Done;
+            };
+            self.pushes += 1;
+            if let Some(chain) = chain.as_deref_mut() {
+                chain.push(self.squares.colors[collided_block].clone());
+            }
+            index = collided_block;
+        }
+
+        // More blocks pushed than there are means the chain came back round to one it already
+        // pushed, which only a loop of arrows under `ArrowPush::Follow`, or a teleporter, can do.
+        Push::Looped
+    }
+
+    // Whether `next` moved the same block as the last move straight back to where it was and
+    // facing the same way, with neither move pushing anything, so `next` is the position before
+    // this one. That only happens between two arrows that point at each other.
+    fn reverses_last_move(&self, next: &Self) -> bool {
+        next.undo.is_some()
+            && self.move_history.last() == next.move_history.last()
+            && self.undo.as_ref().is_some_and(|before| {
+                next.move_history
+                    .last()
+                    .and_then(|color| next.squares.get(color))
+                    == Some(before)
+            })
+    }
+
+    // Why the rules do not allow the move from `before` to here, if they do not.
+    fn violation(&self, before: &Self) -> Option<Violation> {
+        if let Some(violation) = self.stopped {
+            return Some(violation);
+        }
+        let rules = &self.board.rules;
+        if rules
+            .push_strength
+            .is_some_and(|strength| self.pushes - before.pushes > strength)
+        {
+            return Some(Violation::TooManyPushes);
+        }
+        if rules.stay_on_goal
+            && self
+                .squares
+                .iter()
+                .zip(before.squares.values())
+                .any(|((color, block), was)| {
+                    block.position != was.position && before.on_goal(color) && !self.on_goal(color)
+                })
+        {
+            return Some(Violation::LeftGoal);
+        }
+        if !self.board.avoids_cells && rules.bounds.is_none() {
+            return None;
+        }
+        self.squares
+            .iter()
+            .zip(before.squares.values())
+            .filter(|((_, block), was)| block.position != was.position)
+            .find_map(|((color, block), _)| {
+                if !rules.contains(&block.position) {
+                    Some(Violation::OffBoard)
+                } else if !self.board.may_enter(color, &block.position) {
+                    Some(Violation::AvoidedCell)
+                } else {
+                    None
+                }
+            })
+    }
+
+    fn on_goal(&self, color: &str) -> bool {
+        self.squares
+            .get(color)
+            .is_some_and(|block| self.board.goals_of(color).contains(&block.position))
+    }
+
+    fn update_deadlines(&mut self, met_before: &[bool]) {
+        self.deadlines_met = self
+            .board
+            .deadlines
+            .iter()
+            .zip(met_before)
+            .map(|((color, deadline), met)| {
+                *met || (self.cost as usize <= deadline.by && self.on_goal(color))
+            })
+            .collect();
+    }
+
+    // The block whose deadline the move from `before` to here broke, by reaching the deadline's
+    // move without having been on a goal, or by leaving a goal it had to stay on.
+    fn broken_deadline(&self, before: &Self) -> Option<&Color> {
+        self.board
+            .deadlines
+            .iter()
+            .zip(before.deadlines_met.iter().zip(&self.deadlines_met))
+            .find(|((color, deadline), (was_met, met))| {
+                (deadline.stay && **was_met && !self.on_goal(color))
+                    || (!**met && self.cost as usize >= deadline.by)
+            })
+            .map(|((color, _), _)| color)
+    }
+
+    // Whether some block whose deadline is unmet is too far from its goals to make it in time,
+    // given that a move brings a block at most one cell closer. A teleporter breaks that, so with
+    // tiles other than arrows nothing is out of reach.
+    fn deadline_out_of_reach(&self) -> bool {
+        if self.board.other_tiles {
+            return false;
+        }
+        self.board
+            .deadlines
+            .iter()
+            .zip(&self.deadlines_met)
+            .any(|((color, deadline), met)| {
+                !*met
+                    && self
+                        .distance_to_nearest_goal(color)
+                        .is_none_or(|distance| self.cost as usize + distance as usize > deadline.by)
+            })
+    }
+
+    // Whether an earlier search proved that this position needs more moves than the bound leaves.
+    fn known_hopeless(&self) -> bool {
+        self.known.as_ref().is_some_and(|known| {
+            known
+                .get(&self.canonical_hash())
+                .is_some_and(|needed| self.cost + needed > self.bound)
+        })
+    }
+
+    // The sum of every block's Manhattan distance to its nearest goal, which is 0 exactly when
+    // solved.
+    fn goal_distance(&self) -> i64 {
+        (0..self.squares.len())
+            .filter_map(|index| self.distance_at(index))
+            .sum()
+    }
+
+    fn distance_to_nearest_goal(&self, color: &str) -> Option<i64> {
+        self.distance_at(self.squares.index_of(color)?)
+    }
+
+    // How far the block at `index` is from the nearest of its goals, if it has any.
+    fn distance_at(&self, index: usize) -> Option<i64> {
+        let position = &self.squares.blocks[index].position;
+        self.board.goals[index]
+            .iter()
+            .map(|goal| self.board.rules.distance(position, goal))
+            .min()
+    }
+
+    fn fingerprint(&self) -> String {
+        self.fingerprint_under(&Symmetry::identity())
+    }
+
+    fn fingerprint_under(&self, symmetry: &Symmetry) -> String {
+        let mut keys: Vec<&Color> = self.squares.keys().collect();
+        keys.sort();
+
+        let mut fingerprint = String::new();
+
+        for key in keys {
+            let block = self.squares.get(key).unwrap();
+            let position = symmetry.apply(&block.position);
+            fingerprint.push_str(&format!(
+                "{},{},{},{}\t",
+                key,
+                position[0],
+                position[1],
+                symmetry.apply_direction(&block.direction)
+            ));
+        }
+        for position in self.spent_tiles_under(symmetry) {
+            fingerprint.push_str(&format!("x{},{}\t", position[0], position[1]));
+        }
+        // While a deadline is unmet, how many moves it took to get here matters as well.
+        if !self.deadlines_met.is_empty() {
+            for met in &self.deadlines_met {
+                fingerprint.push(if *met { '+' } else { '-' });
+            }
+            if self.deadlines_met.contains(&false) {
+                fingerprint.push_str(&format!("\t{}", self.cost));
+            }
+        }
+
+        fingerprint
+    }
+
+    // Whether the two states have the same fingerprint, without building it.
+    fn same_position(&self, other: &BoardState) -> bool {
+        self.squares.blocks == other.squares.blocks
+            && self.spent_tiles == other.spent_tiles
+            && self.deadlines_met == other.deadlines_met
+            && (!self.deadlines_met.contains(&false) || self.cost == other.cost)
+    }
+
+    fn spent_tiles_under(&self, symmetry: &Symmetry) -> Vec<Position2D> {
+        let mut spent: Vec<Position2D> = self
+            .spent_tiles
+            .iter()
+            .map(|position| symmetry.apply(position))
+            .collect();
+        spent.sort();
+        spent
+    }
+
+    fn bounds(&self) -> (Position2D, Position2D) {
+        let positions = self
+            .squares
+            .values()
+            .map(|block| &block.position)
+            .chain(self.board.extent.iter().flatten());
+        let (mut min, mut max) = ([i32::MAX; 2], [i32::MIN; 2]);
+        for position in positions {
+            for i in 0..2 {
+                min[i] = min[i].min(position[i]);
+                max[i] = max[i].max(position[i]);
+            }
+        }
+
+        (min, max)
+    }
+
+    fn render(&self) -> String {
+        let (min, max) = self.bounds();
+        let mut colors: Vec<&Color> = self.squares.keys().collect();
+        colors.sort();
+
+        let mut output = String::new();
+        let width = i64::from(max[0]) - i64::from(min[0]) + 1;
+        let height = i64::from(max[1]) - i64::from(min[1]) + 1;
+        if width * height > MAX_RENDERED_CELLS {
+            output.push_str(&format!(
+                "The board is {} by {} cells, too large to draw\n",
+                width, height
+            ));
+        } else {
+            self.render_grid(&colors, min, max, &mut output);
+        }
+
+        for color in colors {
+            let block = &self.squares[color];
+            output.push_str(&format!(
+                "{} at ({}, {}) facing {}\n",
+                color, block.position[0], block.position[1], block.direction
+            ));
+        }
+
+        output
+    }
+
+    // Draws the cells from `min` to `max`, the top row first.
+    fn render_grid(
+        &self,
+        colors: &[&Color],
+        min: Position2D,
+        max: Position2D,
+        output: &mut String,
+    ) {
+        let initial = |color: &Color| color.chars().next().unwrap_or('?');
+        // Blocks are drawn over goals, and where goals of several blocks meet, the first color's.
+        let mut letters: HashMap<Position2D, char> = HashMap::new();
+        for color in colors {
+            letters.insert(
+                self.squares[*color].position,
+                initial(color).to_ascii_uppercase(),
+            );
+        }
+        for color in colors {
+            for goal in self.board.goals_of(color) {
+                letters
+                    .entry(*goal)
+                    .or_insert_with(|| initial(color).to_ascii_lowercase());
+            }
+        }
+
+        for y in (min[1]..=max[1]).rev() {
+            for x in min[0]..=max[0] {
+                let position = [x, y];
+                let cell = if let Some(&letter) = letters.get(&position) {
+                    letter
+                } else if let Some(tile) = self.board.tiles.effect_at(&position) {
+                    tile.symbol(self.spent_tiles.binary_search(&position).is_ok())
+                } else if self.board.tiles.avoided(&position) {
+                    '#'
+                } else {
+                    '.'
+                };
+                output.push(cell);
+            }
+            output.push('\n');
+        }
+    }
+}
+
+impl Hash for BoardState {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.fingerprint().hash(state);
+    }
+}
+
+impl State for BoardState {
+    type Cost = i32;
+
+    // The moves skipped are those the rules or a tile do not allow, those that undo the last one,
+    // and those that lead back to this position or to the same position as an earlier move, which
+    // chains and arrows make possible.
+    fn successors(&self) -> Vec<Self> {
+        let mut successors = Vec::with_capacity(self.squares.len());
+        self.successors_into(&mut successors);
+        successors
+    }
+
+    fn successors_into(&self, successors: &mut Vec<Self>) {
+        let start = successors.len();
+        for color in self.board.colors.iter() {
+            if !self.squares.contains_key(color) {
+                continue;
+            }
+            let next = self.move_square(color);
+            if next.violation(self).is_some() {
+                continue;
+            }
+            if next.broken_deadline(self).is_some() || next.deadline_out_of_reach() {
+                continue;
+            }
+            if self.prune_inverses && self.reverses_last_move(&next) {
+                continue;
+            }
+            if self.dedupe_successors
+                && (next.same_position(self)
+                    || successors[start..]
+                        .iter()
+                        .any(|other| next.same_position(other)))
+            {
+                continue;
+            }
+            if !next.known_hopeless() {
+                successors.push(next);
+            }
+        }
+    }
+
+    fn is_goal(&self) -> bool {
+        self.goal_distance() == 0 && !self.deadlines_met.contains(&false)
+    }
+
+    fn canonical_hash(&self) -> u64 {
+        let symmetries = &self.board.symmetries;
+        if symmetries.len() == 1 {
+            return hash(self);
+        }
+
+        let canonical = symmetries
+            .iter()
+            .map(|symmetry| self.fingerprint_under(symmetry))
+            .min()
+            .unwrap_or_else(|| self.fingerprint());
+        hash(&canonical)
+    }
+
+    fn distance_to_goal(&self) -> Self::Cost {
+        match self.heuristic {
+            Heuristic::Manhattan if self.weight == 1.0 => self.goal_distance(),
+            Heuristic::Manhattan => (self.weight * self.goal_distance() as f64).round() as i64,
+            Heuristic::Zero => 0,
+        }
+        // Far-apart coordinates can make the distance larger than the cost type holds, and the
+        // searches add the cost so far to it.
+        .min(i64::from(i32::MAX - self.cost)) as i32
+    }
+
+    fn cost(&self) -> Self::Cost {
+        self.cost
+    }
+
+    // Blocks moved by another block rather than on their own, summed over all moves.
+    fn secondary_cost(&self) -> usize {
+        self.pushes
+    }
+
+    // The position of the last moved block among the colors in sorted order.
+    fn tie_break_index(&self) -> usize {
+        self.move_history.last().map_or(0, |last| {
+            self.squares.keys().filter(|color| *color < last).count()
+        })
+    }
+
+    fn depth(&self) -> usize {
+        self.move_history.len()
+    }
+
+    fn allocated_bytes(&self) -> usize {
+        let moves = match &self.move_history {
+            Moves::Full(moves) => {
+                moves.capacity() * size_of::<Color>()
+                    + moves.iter().map(String::capacity).sum::<usize>()
+            }
+            // The rest of the chain is shared with the positions before, so each adds one link
+            // and its reference counts.
+            Moves::Linked(..) => size_of::<Link>() + 2 * size_of::<usize>(),
+        };
+        self.squares.blocks.capacity() * size_of::<Block>()
+            + self.spent_tiles.capacity() * size_of::<Position2D>()
+            + self.deadlines_met.capacity() * size_of::<bool>()
+            + moves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::AbortReason;
+    use crate::tiles::{Pit, Teleporter, Wall};
+
+    // Two blocks whose ways to their goals cross at [1, 0].
+    fn two_blocks() -> Game {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([3, 0]));
+        game.add_block("blue".into(), Direction::Up, [1, -1], Some([1, 1]));
+        game
+    }
+
+    #[test]
+    fn test_solve_all_optimal_finds_every_ordering() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Up, [0, 0], Some([0, 1]));
+        game.add_block("blue".into(), Direction::Up, [5, 0], Some([5, 1]));
+
+        let mut solutions = game.solve_all_optimal(10);
+        solutions.sort();
+
+        assert_eq!(
+            solutions,
+            vec![
+                vec!["blue".to_string(), "red".to_string()],
+                vec!["red".to_string(), "blue".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_count_solutions_includes_longer_sequences() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Up, [0, 0], Some([0, 1]));
+        game.add_block("blue".into(), Direction::Up, [5, 0], None);
+
+        assert_eq!(game.count_solutions(0), 0);
+        assert_eq!(game.count_solutions(1), 1);
+        assert_eq!(game.count_solutions(3), 3);
+    }
+
+    #[test]
+    fn test_moves_copy_only_the_blocks() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([2, 0]));
+        game.add_block("blue".into(), Direction::Up, [1, 0], None);
+
+        let start = game.initial_board_state();
+        let next = start.move_square(&"red".to_string());
+        assert!(Arc::ptr_eq(&start.squares.colors, &next.squares.colors));
+        assert_eq!(next.squares["red"].position, [1, 0]);
+        assert_eq!(next.squares["blue"].position, [2, 0]);
+        assert!(next.squares.get("green").is_none());
+        assert_eq!(
+            next.squares.to_map(),
+            game.apply_move(&game.initial_state(), "red")
+                .unwrap()
+                .blocks
+        );
+    }
+
+    #[test]
+    fn test_states_outlive_the_game_on_another_thread() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([2, 0]));
+        game.add_arrow(Direction::Up, [1, 0]);
+        let start = game.initial_board_state();
+        drop(game);
+
+        let moved = thread::spawn(move || start.move_square(&"red".to_string()))
+            .join()
+            .unwrap();
+        assert_eq!(moved.squares["red"].position, [1, 0]);
+        assert_eq!(moved.squares["red"].direction, Direction::Up);
+        assert_eq!(moved.move_history.to_vec(), vec!["red".to_string()]);
+    }
+
+    #[test]
+    fn test_successors_skip_moving_straight_back() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([5, 5]));
+        game.add_block("blue".into(), Direction::Up, [3, 0], None);
+        game.add_arrow(Direction::Right, [0, 0]);
+        game.add_arrow(Direction::Left, [1, 0]);
+
+        let start = game.initial_board_state();
+        let next = start.move_square(&"red".to_string());
+        let moved: Vec<Color> = next
+            .successors()
+            .into_iter()
+            .map(|state| state.move_history.last().unwrap().clone())
+            .collect();
+        assert_eq!(moved, vec!["blue".to_string()]);
+
+        let blue = start.move_square(&"blue".to_string());
+        assert_eq!(blue.successors().len(), 2);
+        let counting = BoardState {
+            prune_inverses: false,
+            ..next
+        };
+        assert_eq!(counting.successors().len(), 2);
+    }
+
+    #[test]
+    fn test_successors_skip_repeated_positions() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([1, 0]));
+        game.add_block("blue".into(), Direction::Right, [1, 0], Some([0, 0]));
+        game.set_rules(Ruleset {
+            bounds: Some([[0, 0], [1, 0]]),
+            wrap: true,
+            ..Ruleset::default()
+        });
+
+        // Either block pushes the other around the row, so both moves swap them.
+        let successors = game.initial_board_state().successors();
+        assert_eq!(successors.len(), 1);
+        assert_eq!(successors[0].move_history.last(), Some(&"blue".to_string()));
+
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Up, [0, 0], Some([0, 1]));
+        game.set_rules(Ruleset {
+            bounds: Some([[0, 0], [0, 0]]),
+            wrap: true,
+            ..Ruleset::default()
+        });
+        assert!(game.initial_board_state().successors().is_empty());
+    }
+
+    #[test]
+    fn test_solution_counts_keep_moves_to_the_same_position() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([1, 0]));
+        game.add_block("blue".into(), Direction::Right, [1, 0], Some([0, 0]));
+        game.set_rules(Ruleset {
+            bounds: Some([[0, 0], [1, 0]]),
+            wrap: true,
+            ..Ruleset::default()
+        });
+
+        // Both moves swap the blocks, which solves the puzzle.
+        let mut solutions = game.solve_all_optimal(1);
+        solutions.sort();
+        assert_eq!(
+            solutions,
+            vec![vec!["blue".to_string()], vec!["red".to_string()]]
+        );
+        assert_eq!(game.count_solutions(1), 2);
+    }
+
+    #[test]
+    fn test_apply_moves_follows_arrows_and_pushes() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([2, 1]));
+        game.add_block("blue".into(), Direction::Down, [2, 1], None);
+        game.add_arrow(Direction::Up, [1, 0]);
+
+        let start = game.initial_state();
+        let state = game.apply_move(&start, "red").unwrap();
+        assert_eq!(
+            state.block("red"),
+            Some(&Block {
+                position: [1, 0],
+                direction: Direction::Up,
+            })
+        );
+
+        let state = game.apply_moves(&state, &["red".into()]).unwrap();
+        assert_eq!(state.block("red").unwrap().position, [1, 1]);
+        assert_eq!(state.block("blue").unwrap().position, [2, 1]);
+        assert!(game.apply_move(&state, "green").is_none());
+        let mut other = Game::new();
+        other.add_block("red".into(), Direction::Right, [0, 0], None);
+        assert!(game.apply_move(&other.initial_state(), "red").is_none());
+        assert!(other.apply_move(&state, "red").is_none());
+        assert!(!game.is_solved(&other.initial_state()));
+        assert_eq!(game.render_state(&other.initial_state()), "");
+
+        let blue_moved = game.apply_move(&start, "blue").unwrap();
+        assert_eq!(blue_moved.block("blue").unwrap().position, [2, 0]);
+        assert!(!game.is_solved(&blue_moved));
+    }
+
+    #[test]
+    fn test_describe_moves_reports_pushes_and_arrows() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], None);
+        game.add_block("blue".into(), Direction::Up, [1, 0], None);
+        game.add_block("green".into(), Direction::Up, [2, 0], None);
+        game.add_arrow(Direction::Down, [1, 0]);
+
+        let moves = game.describe_moves(&["red".into()]).unwrap();
+        assert_eq!(
+            moves[0],
+            Move {
+                block: ColorId(2),
+                direction: Direction::Right,
+                from: [0, 0],
+                to: [1, 0],
+                pushed: vec![ColorId(0), ColorId(1)],
+                redirected_to: Some(Direction::Down),
+            }
+        );
+        assert_eq!(
+            game.display_move(&moves[0]).to_string(),
+            "red right (0, 0) -> (1, 0); red pushes blue, blue pushes green; turned down by an arrow"
+        );
+        assert_eq!(
+            moves[0].push_chain(),
+            vec![(ColorId(2), ColorId(0)), (ColorId(0), ColorId(1))]
+        );
+        assert_eq!(game.color_id("green"), Some(ColorId(1)));
+        assert_eq!(game.color_name(ColorId(2)), Some(&"red".to_string()));
+        assert_eq!(game.color_name(ColorId(3)), None);
+        assert!(game.describe_moves(&["pink".into()]).is_none());
+    }
+
+    #[test]
+    fn test_solve_resumes_from_a_checkpoint() {
+        let game = two_blocks();
+
+        let mut saved = vec![];
+        let (moves, _) = game
+            .solve_checkpointed(10, None, None, Duration::ZERO, |checkpoint| {
+                saved.push(serde_json::to_string(checkpoint).unwrap())
+            })
+            .unwrap();
+        let checkpoint: Checkpoint = serde_json::from_str(&saved[0]).unwrap();
+        assert_eq!(checkpoint.open, vec![Vec::<Color>::new()]);
+
+        let (resumed, _) = game
+            .solve_checkpointed(10, None, Some(checkpoint), Duration::MAX, |_| {})
+            .unwrap();
+        assert_eq!(
+            resumed.map(|moves| moves.len()),
+            moves.map(|moves| moves.len())
+        );
+
+        let checkpoint = serde_json::from_str(&saved[0]).unwrap();
+        assert!(game
+            .solve_checkpointed(20, None, Some(checkpoint), Duration::MAX, |_| {})
+            .is_err());
+    }
+
+    #[test]
+    fn test_solve_raising_continues_past_a_low_bound() {
+        let game = two_blocks();
+
+        let (moves, _, bound) = game.solve_raising([1, 2], None);
+        assert_eq!((moves, bound), (None, Some(2)));
+
+        let (moves, stats, bound) = game.solve_raising([1, 2, 4, 8], None);
+        assert_eq!(bound, Some(8));
+        assert_eq!(moves.map(|moves| moves.len()), Some(5));
+        let restarted: usize = [1, 2, 4, 8]
+            .map(|bound| game.solve_with_stats(bound).1.expanded)
+            .iter()
+            .sum();
+        assert!(stats.expanded < restarted);
+    }
+
+    #[test]
+    fn test_solve_with_cancel_stops_the_search() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([3, 0]));
+
+        let token = CancellationToken::new();
+        let (moves, stats) = game.solve_with_cancel(10, token.clone());
+        assert_eq!(moves.map(|moves| moves.len()), Some(3));
+        assert!(!stats.cancelled);
+
+        token.cancel();
+        let (moves, stats) = game.solve_with_cancel(10, token);
+        assert_eq!(moves, None);
+        assert!(stats.cancelled);
+        assert_eq!(stats.expanded, 0);
+    }
+
+    #[test]
+    fn test_breadth_first_search_stops_at_the_limits() {
+        let game = two_blocks();
+        let options = SolverOptions::new(10).algorithm(Algorithm::BreadthFirst);
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let (moves, stats) = game.solve_with(&options.clone().cancel(token));
+        assert_eq!(moves, None);
+        assert!(stats.cancelled);
+        assert!(!stats.exhausted);
+
+        let (moves, stats) = game.solve_with(&options.clone().max_expanded(2));
+        assert_eq!(moves, None);
+        assert!(stats.limit_reached);
+        assert_eq!(stats.expanded, 2);
+
+        let (moves, stats) = game.solve_with(&options.timeout(Duration::ZERO));
+        assert_eq!(moves, None);
+        assert!(stats.timed_out);
+    }
+
+    #[test]
+    fn test_solve_with_applies_the_options() {
+        let game = two_blocks();
+
+        let (moves, _) = game.solve_with(&SolverOptions::new(10));
+        let solution = game.solve(10).into_solution().unwrap();
+        assert_eq!(moves, Some(solution.colors()));
+        assert_eq!(solution.length, 5);
+        assert_eq!(solution.block_colors[solution.moves[0].block.0], "blue");
+        assert_eq!(solution.moves[0].from, [1, -1]);
+
+        let optimal = SolverOptions::new(10).algorithm(Algorithm::BreadthFirst);
+        assert_eq!(game.solve_with(&optimal).0, game.solve_optimal(10));
+
+        let (moves, stats) = game.solve_with(&SolverOptions::new(10).max_expanded(2));
+        assert_eq!(moves, None);
+        assert!(stats.limit_reached);
+        assert_eq!(stats.expanded, 2);
+
+        let (_, stats) = game.solve_with(&SolverOptions::new(10));
+        assert!(stats.peak_memory > 0);
+        assert!(stats.elapsed > Duration::ZERO);
+        let mut wide = Game::new();
+        for (color, direction, start) in [
+            ("red", Direction::Right, [0, 0]),
+            ("blue", Direction::Up, [1, -1]),
+            ("green", Direction::Left, [3, 2]),
+            ("yellow", Direction::Down, [2, 4]),
+        ] {
+            wide.add_block(color.into(), direction, start, Some([9, 9]));
+        }
+        let quota = SolverOptions::new(12).max_memory(1);
+        let (moves, stats) = wide.solve_with(&quota);
+        assert_eq!(moves, None);
+        assert!(stats.limit_reached);
+        assert_eq!(stats.expanded, 1024);
+
+        let (moves, stats) = game.solve_with(&SolverOptions::new(10).max_depth(4));
+        assert_eq!(moves, None);
+        assert!(stats.depth_cut_off > 0);
+        assert_eq!(stats.cost_cut_off, 0);
+        let (moves, stats) = game.solve_with(&SolverOptions::new(10).max_cost(4));
+        assert_eq!(moves, None);
+        assert!(stats.cost_cut_off > 0);
+
+        let uniform = SolverOptions::new(10).heuristic(Heuristic::Zero);
+        let greedy = SolverOptions::new(10).weight(3.0);
+        let (uniform_moves, uniform_stats) = game.solve_with(&uniform);
+        let (greedy_moves, greedy_stats) = game.solve_with(&greedy);
+        assert!(uniform_moves.is_some() && greedy_moves.is_some());
+        assert!(greedy_stats.expanded <= uniform_stats.expanded);
+    }
+
+    #[test]
+    fn test_solve_anytime_reports_shorter_and_shorter_solutions() {
+        let game = two_blocks();
+
+        let options = SolverOptions::new(12)
+            .algorithm(Algorithm::Anytime)
+            .weight(4.0);
+        let mut found = vec![];
+        let (moves, stats) = game.solve_anytime(&options, |moves, _| found.push(moves.len()));
+
+        assert!(found.windows(2).all(|pair| pair[1] < pair[0]));
+        assert_eq!(found.last().copied(), moves.map(|moves| moves.len()));
+        assert_eq!(found.last(), Some(&5));
+        assert!(!stats.timed_out);
+        assert_eq!(
+            game.solve_with(&options).0.map(|moves| moves.len()),
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn test_solve_with_sma_star_stays_within_the_memory_budget() {
+        let game = two_blocks();
+
+        let options = SolverOptions::new(10).algorithm(Algorithm::SmaStar);
+        let (moves, _) = game.solve_with(&options.clone().max_nodes(50));
+        assert_eq!(moves.map(|moves| moves.len()), Some(5));
+        let (moves, stats) = game.solve_with(&options.max_nodes(2));
+        assert_eq!(moves, None);
+        assert!(stats.limit_reached);
+    }
+
+    #[test]
+    fn test_solve_with_compact_paths_finds_the_same_solution() {
+        let game = two_blocks();
+
+        let options = SolverOptions::new(10);
+        let (expected, _) = game.solve_with(&options);
+        let (moves, _) = game.solve_with(&options.compact_paths(true));
+        assert!(expected.is_some());
+        assert_eq!(moves, expected);
+    }
+
+    #[test]
+    fn test_solve_with_ida_star_finds_a_shortest_solution() {
+        let game = two_blocks();
+
+        let options = SolverOptions::new(10).algorithm(Algorithm::IdaStar);
+        let (moves, _) = game.solve_with(&options.clone().heuristic(Heuristic::Zero));
+        assert_eq!(moves.map(|moves| moves.len()), Some(5));
+        let (moves, stats) = game.solve_with(&options.table_size(0));
+        assert_eq!(moves.map(|moves| moves.len()), Some(5));
+        assert_eq!(stats.seen, 0);
+    }
+
+    #[test]
+    fn test_solve_portfolio_returns_the_first_solution() {
+        let game = two_blocks();
+
+        let entries: Vec<SolverOptions> = crate::solver::portfolio(&SolverOptions::new(10))
+            .into_iter()
+            .map(|(_, options)| options)
+            .collect();
+        let (moves, _, winner) = game.solve_portfolio(&entries);
+        let state = game
+            .apply_moves(&game.initial_state(), &moves.unwrap())
+            .unwrap();
+        assert!(game.is_solved(&state));
+        assert!(winner.is_some_and(|winner| winner < entries.len()));
+
+        let (moves, _, winner) = game.solve_portfolio(&[SolverOptions::new(3)]);
+        assert_eq!(moves, None);
+        assert_eq!(winner, None);
+
+        // On one thread the entries run in turn, so the first to solve it wins.
+        let in_turn = [SolverOptions::new(3), SolverOptions::new(10).threads(1)];
+        let (moves, _, winner) = game.solve_portfolio(&in_turn);
+        assert!(moves.is_some());
+        assert_eq!(winner, Some(1));
+    }
+
+    #[test]
+    fn test_solve_cached_prunes_what_an_optimal_run_proved() {
+        let game = two_blocks();
+        let mut cache = DeadEndCache::new();
+
+        let optimal = SolverOptions::new(10).algorithm(Algorithm::BreadthFirst);
+        let (moves, _) = game.solve_cached(&optimal, &mut cache);
+        assert_eq!(moves, game.solve_optimal(10));
+        assert!(!cache.is_empty());
+
+        let mut moved = Game::new();
+        moved.add_block("red".into(), Direction::Right, [5, -2], Some([8, -2]));
+        moved.add_block("blue".into(), Direction::Up, [6, -3], Some([6, -1]));
+        assert_eq!(moved.cache_key(), game.cache_key());
+
+        let astar = SolverOptions::new(10);
+        let (cached, cached_stats) = moved.solve_cached(&astar, &mut cache);
+        let (direct, direct_stats) = moved.solve_with(&astar);
+        assert_eq!(cached.map(|moves| moves.len()), Some(5));
+        assert_eq!(direct.map(|moves| moves.len()), Some(5));
+        assert!(cached_stats.expanded <= direct_stats.expanded);
+
+        let (moves, stats) = game.solve_cached(&SolverOptions::new(4), &mut cache);
+        assert_eq!(moves, None);
+        assert_eq!(stats.generated, 0);
+    }
+
+    #[test]
+    fn test_tie_breaking_is_deterministic() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Up, [0, 0], Some([0, 1]));
+        game.add_block("blue".into(), Direction::Up, [2, 0], Some([2, 1]));
+        game.add_block("green".into(), Direction::Down, [4, 0], Some([4, -1]));
+
+        for tie_break in [
+            TieBreak::LowerEstimate,
+            TieBreak::HigherCost,
+            TieBreak::LowerIndex,
+            TieBreak::Fifo,
+        ] {
+            let (moves, stats) = game.solve_with_tie_break(10, None, tie_break);
+            for _ in 0..5 {
+                let (again, again_stats) = game.solve_with_tie_break(10, None, tie_break);
+                assert_eq!(again, moves);
+                assert_eq!(again_stats.expanded, stats.expanded);
+            }
+        }
+        assert_eq!(
+            game.solve_with_tie_break(10, None, TieBreak::LowerIndex).0,
+            Some(vec!["blue".into(), "green".into(), "red".into()])
+        );
+    }
+
+    #[test]
+    fn test_solve_optimal_prefers_fewer_pushes() {
+        let mut game = Game::new();
+        game.add_block("blue".into(), Direction::Right, [0, 0], Some([4, 0]));
+        game.add_block("green".into(), Direction::Down, [0, 2], Some([0, 3]));
+        game.add_block("red".into(), Direction::Down, [0, 1], Some([0, 2]));
+        game.add_arrow(Direction::Up, [0, 0]);
+        let pushes = |moves: &[Color]| -> usize {
+            game.describe_moves(moves)
+                .unwrap()
+                .iter()
+                .map(|step| step.pushed.len())
+                .sum()
+        };
+
+        let all: Vec<usize> = game
+            .solve_all_optimal(10)
+            .iter()
+            .map(|moves| pushes(moves))
+            .collect();
+        assert_eq!((all.iter().min(), all.iter().max()), (Some(&1), Some(&3)));
+        let best = game.solve_optimal(10).unwrap();
+        assert_eq!((best.len(), pushes(&best)), (7, 1));
+    }
+
+    #[test]
+    fn test_check_heuristic_catches_manhattan_counting_a_push_twice() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([1, 0]));
+        game.add_block("blue".into(), Direction::Right, [1, 0], Some([2, 0]));
+
+        let check = game.check_heuristic(&SolverOptions::new(10), 4, 1000);
+        assert!(!check.admissible());
+        let worst = check.worst_overestimate.as_ref().unwrap();
+        assert_eq!((worst.estimate, worst.actual), (2, 1));
+        assert!(worst.state.is_empty());
+        assert!(!check.consistent());
+
+        let zero = SolverOptions::new(10).heuristic(Heuristic::Zero);
+        let check = game.check_heuristic(&zero, 4, 1000);
+        assert!(check.admissible() && check.consistent());
+        assert!(check.exact > 0);
+    }
+
+    #[test]
+    fn test_equivalence_up_to_symmetry_and_colors() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([2, 0]));
+        game.add_block("blue".into(), Direction::Up, [1, -1], Some([1, 1]));
+        game.add_arrow(Direction::Down, [3, 0]);
+
+        let mut mirrored = Game::new();
+        mirrored.add_block("green".into(), Direction::Left, [10, 5], Some([8, 5]));
+        mirrored.add_block("navy".into(), Direction::Up, [9, 4], Some([9, 6]));
+        mirrored.add_arrow(Direction::Down, [7, 5]);
+
+        let equivalence = game.equivalence(&mirrored).unwrap();
+        assert_eq!(equivalence.transform, "mirrored left to right");
+        assert_eq!(
+            equivalence.colors,
+            vec![
+                ("blue".to_string(), "navy".to_string()),
+                ("red".to_string(), "green".to_string())
+            ]
+        );
+        assert_eq!(game.canonical_form(), mirrored.canonical_form());
+
+        mirrored.add_arrow(Direction::Up, [7, 5]);
+        assert_eq!(game.equivalence(&mirrored), None);
+        assert_ne!(game.canonical_form(), mirrored.canonical_form());
+    }
+
+    #[test]
+    fn test_verify_reports_the_first_unknown_color() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Up, [0, 0], Some([0, 2]));
+
+        let verification = game.verify(&["red".into(), "green".into(), "red".into()]);
+        assert!(!verification.solved);
+        assert_eq!(verification.moves_applied, 1);
+        assert_eq!(verification.unknown_color, Some("green".to_string()));
+
+        let verification = game.verify(&["red".into(), "red".into()]);
+        assert!(verification.solved);
+        assert_eq!(verification.first_solved_after, Some(2));
+    }
+
+    #[test]
+    fn test_verify_solution_returns_the_final_state_or_the_first_bad_move() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Up, [0, 0], Some([0, 2]));
+        game.set_rules(Ruleset {
+            bounds: Some([[0, 0], [0, 2]]),
+            ..Ruleset::default()
+        });
+
+        let state = game.verify_solution(&["red".into(), "red".into()]).unwrap();
+        assert!(game.is_solved(&state));
+        assert_eq!(state.block("red").unwrap().position, [0, 2]);
+
+        let invalid = game
+            .verify_solution(&["red".into(), "red".into(), "red".into()])
+            .unwrap_err();
+        assert_eq!(
+            invalid,
+            InvalidMove {
+                index: 2,
+                color: "red".into(),
+                error: MoveError::Forbidden(Violation::OffBoard),
+            }
+        );
+        assert_eq!(
+            invalid.to_string(),
+            "Move 3 (red) would put a block off the board"
+        );
+        let unknown = game.verify_solution(&["green".into()]).unwrap_err();
+        assert_eq!((unknown.index, unknown.error), (0, MoveError::UnknownColor));
+    }
+
+    #[test]
+    fn test_record_solution_keeps_every_state() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Up, [0, 0], Some([0, 2]));
+        game.add_block("blue".into(), Direction::Left, [1, 1], None);
+
+        let moves: Vec<Color> = ["red", "red"].map(String::from).to_vec();
+        let record = game.record_solution(&moves).unwrap();
+        assert!(record.solved);
+        assert_eq!(record.states.len(), 3);
+        assert_eq!(record.states[1]["red"].position, [0, 1]);
+        assert_eq!(record.final_positions["red"], [0, 2]);
+
+        let yaml = serde_yaml::to_string(&record).unwrap();
+        assert_eq!(
+            serde_yaml::from_str::<SolutionRecord>(&yaml).unwrap(),
+            record
+        );
+        assert!(game.record_solution(&["green".into()]).is_none());
+    }
+
+    #[test]
+    fn test_solve_with_states_returns_every_position() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Up, [0, 0], Some([0, 2]));
+        game.add_block("blue".into(), Direction::Left, [1, 1], None);
+
+        let (solution, _) = game.solve_with_states(&SolverOptions::new(10));
+        let SolutionPath { moves, states } = solution.unwrap();
+        assert_eq!(states.len(), moves.len() + 1);
+        assert_eq!(states[0], game.initial_state());
+        for (i, color) in moves.iter().enumerate() {
+            assert_eq!(
+                game.apply_move(&states[i], color).as_ref(),
+                Some(&states[i + 1])
+            );
+        }
+        assert!(game.is_solved(states.last().unwrap()));
+        assert!(game.states_along(&["green".into()]).is_none());
+    }
+
+    #[test]
+    fn test_diff_moves_lists_only_what_changed() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], None);
+        game.add_block("blue".into(), Direction::Down, [1, 0], None);
+        game.add_block("green".into(), Direction::Up, [5, 5], None);
+        game.add_arrow(Direction::Up, [2, 0]);
+
+        let diffs = game.diff_moves(&["red".into()]).unwrap();
+        assert_eq!(diffs.len(), 1);
+        let [moved, pushed] = &diffs[0][..] else {
+            panic!("expected two changes, got {:?}", diffs[0]);
+        };
+        assert_eq!(moved.to_string(), "red moved (0, 0) -> (1, 0)");
+        assert_eq!(
+            pushed.to_string(),
+            "blue pushed (1, 0) -> (2, 0), turned down -> up by an arrow"
+        );
+        assert!(game.diff_moves(&["purple".into()]).is_none());
+    }
+
+    #[test]
+    fn test_explain_moves_names_each_moves_purpose() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([2, 0]));
+        game.add_block("blue".into(), Direction::Down, [1, 0], Some([0, -3]));
+        game.add_arrow(Direction::Left, [2, -1]);
+
+        let moves: Vec<Color> = vec!["red".into(), "blue".into(), "blue".into()];
+        let explained = game.explain_moves(&moves).unwrap();
+        let purposes: Vec<Purpose> = explained.iter().map(|step| step.purpose).collect();
+        assert_eq!(
+            purposes,
+            [Purpose::Pushing, Purpose::Redirecting, Purpose::Positioning]
+        );
+        assert_eq!(explained[0].closer, ["red"]);
+        assert_eq!(explained[0].farther, ["blue"]);
+        assert_eq!(explained[1].closer, ["blue"]);
+        assert!(explained[1].farther.is_empty());
+        for pair in explained.windows(2) {
+            assert_eq!(pair[0].estimate_after, pair[1].estimate_before);
+        }
+        assert!(explained[0].estimate_before > explained[2].estimate_after);
+        assert_eq!(
+            game.display_explanation(&explained[1]).to_string(),
+            "redirecting: blue down (2, 0) -> (2, -1); turned left by an arrow; estimate 6 -> 5; \
+             closer: blue"
+        );
+        assert!(game.explain_moves(&["purple".into()]).is_none());
+    }
+
+    #[test]
+    fn test_predecessors_undo_pushes_and_arrows() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], None);
+        game.add_block("blue".into(), Direction::Down, [1, 0], None);
+        game.add_block("green".into(), Direction::Up, [4, 4], None);
+        game.add_arrow(Direction::Up, [2, 0]);
+
+        let start = game.initial_state();
+        let after = game.apply_move(&start, "red").unwrap();
+        let predecessors = game.predecessors(&after);
+        assert!(predecessors.contains(&("red".to_string(), start)));
+        for (color, before) in &predecessors {
+            assert_eq!(game.apply_move(before, color).as_ref(), Some(&after));
+        }
+    }
+
+    #[test]
+    fn test_any_of_several_goals_will_do() {
+        let game: Game = serde_yaml::from_str(
+            "blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n    \
+             goal: [[5, 0], [2, 0]]\n  - color: blue\n    direction: up\n    position: [4, 4]\n    \
+             goal: [4, 5]\n",
+        )
+        .unwrap();
+        assert_eq!(game.goals_of("red"), [[5, 0], [2, 0]]);
+        assert_eq!(game.heuristic(&game.initial_state()), 3);
+
+        let moves = game.solve_optimal(10).unwrap();
+        assert_eq!(moves.len(), 3);
+        let state = game.apply_moves(&game.initial_state(), &moves).unwrap();
+        assert_eq!(
+            game.goals_reached(&state),
+            vec![("blue".to_string(), [4, 5]), ("red".to_string(), [2, 0])]
+        );
+
+        let yaml = game.to_yaml();
+        assert!(yaml.contains("goal: [[2, 0], [5, 0]]"));
+        let again: Game = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(again.to_yaml(), yaml);
+    }
+
+    #[test]
+    fn test_tile_grid_agrees_with_the_arrows_and_avoided_cells() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], None);
+        game.add_arrow(Direction::Up, [-2, 3]);
+        game.add_arrow(Direction::Left, [4, -1]);
+        game.avoid([1, 1]);
+        assert!(matches!(game.board().tiles, Tiles::Grid { .. }));
+        for x in -4..7 {
+            for y in -3..6 {
+                let position = [x, y];
+                assert_eq!(game.arrow_at(&position), game.arrows.get(&position));
+                assert_eq!(
+                    game.may_enter("red", &position),
+                    !game.avoided.contains(&position)
+                );
+            }
+        }
+
+        game.remove_arrow(&[4, -1]);
+        assert_eq!(game.arrow_at(&[4, -1]), None);
+        game.add_arrow(Direction::Down, [i32::MAX, i32::MIN]);
+        assert!(matches!(game.board().tiles, Tiles::Sparse { .. }));
+        assert_eq!(game.arrow_at(&[i32::MAX, i32::MIN]), Some(&Direction::Down));
+        assert_eq!(game.arrow_at(&[-2, 3]), Some(&Direction::Up));
+        assert!(!game.may_enter("red", &[1, 1]));
+    }
+
+    #[test]
+    fn test_blocks_never_enter_cells_they_avoid() {
+        let game: Game = serde_yaml::from_str(
+            "blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n    \
+             goal: [3, 0]\n    avoid: [[1, 1]]\n  - color: blue\n    direction: up\n    \
+             position: [1, -1]\n  - color: green\n    direction: down\n    position: [1, 2]\n\
+             avoid: [[2, 0]]\n",
+        )
+        .unwrap();
+        assert!(!game.may_enter("red", &[1, 1]));
+        assert!(game.may_enter("blue", &[1, 1]));
+        assert!(!game.may_enter("blue", &[2, 0]));
+
+        // Red cannot cross the lava at (2, 0), and blue cannot push red through it either.
+        assert_eq!(game.solve_optimal(12), None);
+        let start = game.initial_state();
+        let red = game.apply_move(&start, "red").unwrap();
+        assert_eq!(game.apply_move(&red, "red"), None);
+        let verification = game.verify(&["red".into(), "red".into()]);
+        assert_eq!(verification.forbidden_move, Some("red".to_string()));
+        assert_eq!(verification.moves_applied, 1);
+
+        let yaml = game.to_yaml();
+        assert!(yaml.contains("    avoid: [[1, 1]]\n"));
+        assert!(yaml.ends_with("avoid: [[2, 0]]\n"));
+        assert_eq!(serde_yaml::from_str::<Game>(&yaml).unwrap().to_yaml(), yaml);
+    }
+
+    #[test]
+    fn test_to_yaml_quotes_colors_yaml_would_misread() {
+        let colors = [
+            "true",
+            "123",
+            "null",
+            "a: b",
+            "#red",
+            "say \"hi\"\\",
+            "dark-red",
+        ];
+        let mut game = Game::new();
+        for (x, color) in colors.iter().enumerate() {
+            game.add_block(color.to_string(), Direction::Up, [x as i32, 0], None);
+        }
+
+        let yaml = game.to_yaml();
+        assert!(yaml.contains("  - color: \"true\"\n"));
+        assert!(yaml.contains("  - color: dark-red\n"));
+        let read: Game = serde_yaml::from_str(&yaml).unwrap();
+        for color in colors {
+            assert_eq!(read.initial_state[color], game.initial_state[color]);
+        }
+        assert_eq!(read.to_yaml(), yaml);
+    }
+
+    #[test]
+    fn test_deadlines_order_the_moves() {
+        let game: Game = serde_yaml::from_str(
+            "blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n    \
+             goal: [1, 0]\n    deadline: {by: 4, stay: true}\n  - color: blue\n    \
+             direction: up\n    position: [5, 0]\n    goal: [5, 3]\n    deadline: 3\n",
+        )
+        .unwrap();
+        assert_eq!(
+            game.deadline_of("blue"),
+            Some(Deadline { by: 3, stay: false })
+        );
+
+        let solution = game.solve_optimal(10).unwrap();
+        assert_eq!(solution, vec!["blue", "blue", "blue", "red"]);
+        assert!(game.verify(&solution).solved);
+
+        let late = game.verify(&["red".into(), "blue".into(), "blue".into(), "blue".into()]);
+        assert_eq!(late.missed_deadline, Some("blue".to_string()));
+        assert_eq!(late.moves_applied, 2);
+        let left = game.verify(&["red".into(), "red".into()]);
+        assert_eq!(left.missed_deadline, Some("red".to_string()));
+        assert_eq!(game.states_along(&["red".into(), "red".into()]), None);
+
+        let yaml = game.to_yaml();
+        assert!(yaml.contains("    deadline: {by: 4, stay: true}\n"));
+        assert!(yaml.contains("    deadline: 3\n"));
+        assert_eq!(serde_yaml::from_str::<Game>(&yaml).unwrap().to_yaml(), yaml);
+
+        let mut impossible = game.clone();
+        impossible.set_deadline("blue", Some(Deadline { by: 2, stay: false }));
+        assert_eq!(impossible.solve_optimal(10), None);
+    }
+
+    #[test]
+    fn test_arrow_push_rules_for_pushed_blocks_on_arrows() {
+        // Red pushes blue onto an up arrow, where green sits, and green towards a down arrow.
+        let base = "blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n  \
+                    - color: blue\n    direction: left\n    position: [1, 0]\n  \
+                    - color: green\n    direction: left\n    position: [2, 0]\narrows:\n  \
+                    - direction: up\n    position: [2, 0]\n  - direction: down\n    \
+                    position: [3, 0]\n";
+        let after = |rule: &str| {
+            let game: Game =
+                serde_yaml::from_str(&format!("{}rules:\n  arrow_push: {}\n", base, rule)).unwrap();
+            let state = game.apply_move(&game.initial_state(), "red").unwrap();
+            let blue = state.block("blue").unwrap().clone();
+            let green = state.block("green").unwrap().clone();
+            (game, blue, green)
+        };
+        let block = |position: Position2D, direction: Direction| Block {
+            position,
+            direction,
+        };
+
+        let (game, blue, green) = after("turn");
+        assert_eq!(game.rules().arrow_push, ArrowPush::Turn);
+        assert_eq!(blue, block([2, 0], Direction::Up));
+        assert_eq!(green, block([3, 0], Direction::Down));
+        assert!(!game.to_yaml().contains("rules"));
+
+        let (game, blue, green) = after("keep");
+        assert_eq!(blue, block([2, 0], Direction::Left));
+        assert_eq!(green, block([3, 0], Direction::Left));
+        assert!(game.to_yaml().ends_with("\nrules:\n  arrow_push: keep\n"));
+
+        let (game, blue, green) = after("follow");
+        assert_eq!(blue, block([2, 0], Direction::Up));
+        assert_eq!(green, block([2, 1], Direction::Left));
+        assert_eq!(
+            serde_yaml::from_str::<Game>(&game.to_yaml())
+                .unwrap()
+                .rules()
+                .arrow_push,
+            ArrowPush::Follow
+        );
+
+        // A block moving on its own turns on an arrow under every rule, and under `follow` it
+        // pushes the way the arrow points.
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], None);
+        game.add_block("blue".into(), Direction::Down, [1, 0], None);
+        game.add_arrow(Direction::Up, [1, 0]);
+        for (rule, pushed_to) in [
+            (ArrowPush::Turn, [2, 0]),
+            (ArrowPush::Keep, [2, 0]),
+            (ArrowPush::Follow, [1, 1]),
+        ] {
+            game.set_rules(Ruleset {
+                arrow_push: rule,
+                ..Ruleset::default()
+            });
+            let state = game.apply_move(&game.initial_state(), "red").unwrap();
+            assert_eq!(
+                state.block("red").unwrap().clone(),
+                block([1, 0], Direction::Up)
+            );
+            assert_eq!(state.block("blue").unwrap().position, pushed_to);
+        }
+    }
+
+    #[test]
+    fn test_rules_consume_arrows_bound_the_board_and_limit_pushes() {
+        let game = |blocks: &str, rules: &str| -> Game {
+            serde_yaml::from_str(&format!("blocks:\n{}rules:\n{}", blocks, rules)).unwrap()
+        };
+        let red = "  - color: red\n    direction: right\n    position: [0, 0]\n";
+        let moves =
+            |moves: &[&str]| -> Vec<Color> { moves.iter().map(|m| m.to_string()).collect() };
+
+        // Red uses up the arrow, so blue keeps facing left when it lands there.
+        let mut consuming = game(
+            &format!(
+                "{}  - color: blue\n    direction: left\n    position: [2, 0]\n",
+                red
+            ),
+            "  consume_arrows: true\n",
+        );
+        consuming.add_arrow(Direction::Up, [1, 0]);
+        let states = consuming
+            .states_along(&moves(&["red", "red", "blue"]))
+            .unwrap();
+        assert_eq!(states[1].block("red").unwrap().direction, Direction::Up);
+        assert_eq!(states[3].block("blue").unwrap().direction, Direction::Left);
+        assert_eq!(
+            consuming.to_yaml().matches("consume_arrows: true").count(),
+            1
+        );
+
+        let bounded = game(red, "  bounds: [[0, 0], [1, 0]]\n");
+        let verification = bounded.verify(&moves(&["red", "red"]));
+        assert_eq!(verification.forbidden_move, Some("red".to_string()));
+        assert_eq!(verification.violation, Some(Violation::OffBoard));
+
+        let wrapping = game(
+            "  - color: red\n    direction: right\n    position: [1, 0]\n    goal: [0, 0]\n",
+            "  bounds: [[0, 0], [3, 0]]\n  wrap: true\n",
+        );
+        assert_eq!(wrapping.solve_optimal(10).map(|moves| moves.len()), Some(3));
+
+        let line = format!(
+            "{}  - color: blue\n    direction: up\n    position: [1, 0]\n  \
+             - color: green\n    direction: up\n    position: [2, 0]\n",
+            red
+        );
+        let weak = game(&line, "  push_strength: 1\n");
+        assert_eq!(
+            weak.verify(&moves(&["red"])).violation,
+            Some(Violation::TooManyPushes)
+        );
+        assert!(weak.verify(&moves(&["blue"])).violation.is_none());
+        let strong = game(&line, "  push_strength: 2\n");
+        assert!(strong.verify(&moves(&["red"])).violation.is_none());
+    }
+
+    #[test]
+    fn test_rules_stay_on_goal_forbid_pushing_a_block_off_its_goal() {
+        let yaml = "blocks:\n  \
+            - color: red\n    direction: right\n    position: [0, 0]\n    goal: [1, 0]\n  \
+            - color: green\n    direction: up\n    position: [1, -1]\n    goal: [1, 1]\n";
+        let moves = ["red", "green", "green"].map(String::from);
+        let anywhere: Game = serde_yaml::from_str(yaml).unwrap();
+        assert!(anywhere.verify(&moves[..2]).violation.is_none());
+
+        let staying: Game =
+            serde_yaml::from_str(&format!("{}rules:\n  stay_on_goal: true\n", yaml)).unwrap();
+        let verification = staying.verify(&moves[..2]);
+        assert_eq!(verification.forbidden_move, Some("green".to_string()));
+        assert_eq!(verification.violation, Some(Violation::LeftGoal));
+        assert_eq!(
+            staying.solve_optimal(5),
+            Some(["green", "green", "red"].map(String::from).to_vec())
+        );
+        assert!(staying.to_yaml().contains("  stay_on_goal: true\n"));
+
+        // A block that starts on its goal has already reached it.
+        let mut started = Game::new();
+        started.add_block("red".into(), Direction::Right, [1, 0], Some([1, 0]));
+        started.set_rules(staying.rules().clone());
+        assert_eq!(
+            started.verify(&["red".to_string()]).violation,
+            Some(Violation::LeftGoal)
+        );
+    }
+
+    #[test]
+    fn test_pushing_a_long_line_needs_little_stack() {
+        let mut game = Game::new();
+        for x in 0..5000 {
+            game.add_block(format!("b{:04}", x), Direction::Right, [x, 0], None);
+        }
+
+        // Far less stack than a frame per pushed block would need.
+        let moved = thread::Builder::new()
+            .stack_size(128 * 1024)
+            .spawn(move || {
+                let state = game.apply_move(&game.initial_state(), "b0000").unwrap();
+                state.block("b4999").unwrap().position
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(moved, [5000, 0]);
+    }
+
+    #[test]
+    fn test_moves_past_the_largest_coordinate_are_not_allowed() {
+        let mut game = Game::new();
+        game.add_block(
+            "red".into(),
+            Direction::Right,
+            [i32::MAX, 0],
+            Some([i32::MIN, 0]),
+        );
+        game.add_block(
+            "blue".into(),
+            Direction::Left,
+            [i32::MIN, 5],
+            Some([i32::MAX, 5]),
+        );
+        game.add_arrow(Direction::Down, [0, 0]);
+
+        let start = game.initial_state();
+        assert_eq!(game.apply_move(&start, "red"), None);
+        assert_eq!(game.states_along(&["red".into()]), None);
+        assert_eq!(game.predecessors(&start).len(), 2);
+        assert_eq!(game.solve(3), SolveOutcome::ProvenUnsolvable);
+        assert_eq!(game.solve_optimal(3), None);
+    }
+
+    #[test]
+    fn test_follow_push_round_a_full_loop_moves_nothing() {
+        let mut game = Game::new();
+        game.set_rules(Ruleset {
+            arrow_push: ArrowPush::Follow,
+            ..Ruleset::default()
+        });
+        for (position, direction) in [
+            ([0, 0], Direction::Right),
+            ([1, 0], Direction::Up),
+            ([1, 1], Direction::Left),
+            ([0, 1], Direction::Down),
+        ] {
+            game.add_arrow(direction.clone(), position);
+            game.add_block(format!("{:?}", position), direction, position, None);
+        }
+        game.add_block("red".into(), Direction::Right, [-1, 0], None);
+
+        let start = game.initial_state();
+        assert_eq!(game.apply_move(&start, "red"), Some(start.clone()));
+        let moves = game.describe_moves(&["red".into()]).unwrap();
+        assert!(moves[0].push_chain().is_empty());
+        assert_eq!(
+            game.verify_solution(&["red".into()]).unwrap_err().error,
+            MoveError::NoEffect
+        );
+    }
+
+    #[test]
+    fn test_walls_pits_and_teleporters() {
+        let moves =
+            |moves: &[&str]| -> Vec<Color> { moves.iter().map(|m| m.to_string()).collect() };
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], None);
+        game.add_block("blue".into(), Direction::Down, [1, 1], None);
+        game.add_tile([1, 0], Arc::new(Pit));
+        game.add_tile([-1, 1], Arc::new(Wall));
+
+        // The pit takes one block, and none after it.
+        assert!(game.verify(&moves(&["blue"])).violation.is_none());
+        let verification = game.verify(&moves(&["red", "red", "blue"]));
+        assert_eq!(verification.forbidden_move, Some("blue".to_string()));
+        assert_eq!(verification.violation, Some(Violation::Blocked));
+        let states = game.states_along(&moves(&["red", "red"])).unwrap();
+        assert_eq!(states[2].block("red").unwrap().position, [2, 0]);
+        assert!(game
+            .render_after(&moves(&["red", "red"]))
+            .starts_with("%.B.\n..*R\n"));
+
+        let mut walled = game.clone();
+        walled.add_block("green".into(), Direction::Left, [0, 1], None);
+        assert_eq!(
+            walled.verify(&moves(&["green"])).violation,
+            Some(Violation::Blocked)
+        );
+
+        // Red lands on [5, 0] and pushes what is there on in its own direction.
+        let mut teleporting = Game::new();
+        teleporting.add_block("red".into(), Direction::Right, [0, 0], None);
+        teleporting.add_block("blue".into(), Direction::Up, [5, 0], Some([6, 0]));
+        teleporting.add_tile([1, 0], Arc::new(Teleporter { to: [5, 0] }));
+        let states = teleporting.states_along(&moves(&["red"])).unwrap();
+        assert_eq!(states[1].block("red").unwrap().position, [5, 0]);
+        assert_eq!(states[1].block("blue").unwrap().position, [6, 0]);
+        assert_eq!(teleporting.solve_optimal(3), Some(moves(&["red"])));
+
+        let yaml = teleporting.to_yaml();
+        assert!(
+            yaml.contains("tiles:\n  - kind: teleporter\n    to: [5, 0]\n    position: [1, 0]\n")
+        );
+        let read: Game = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(read.to_yaml(), yaml);
+        let mut shifted = teleporting.clone();
+        shifted.add_block("green".into(), Direction::Up, [-2, -1], None);
+        let tiles = shifted.normalized().tiles()[0].1.spec();
+        assert_eq!(tiles, TileSpec::Teleporter { to: [7, 1] });
+        assert!(serde_yaml::from_str::<Game>(
+            "blocks: []\ntiles:\n  - kind: door\n    position: [0, 0]\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_optimize_solution_removes_detours() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Up, [0, 0], Some([0, 2]));
+        game.add_block("blue".into(), Direction::Right, [0, 3], None);
+
+        let moves: Vec<Color> = ["blue", "red", "blue", "red"].map(String::from).to_vec();
+        assert!(game.verify(&moves).solved);
+
+        let optimized = game.optimize_solution(&moves, 4);
+        assert_eq!(optimized, vec!["red".to_string(), "red".to_string()]);
+    }
+
+    #[test]
+    fn test_normalized_moves_bounding_box_to_origin() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Up, [-2, 5], Some([-2, 7]));
+        game.add_block("blue".into(), Direction::Left, [3, 4], None);
+        game.add_arrow(Direction::Down, [0, 6]);
+
+        assert_eq!(
+            game.normalized().to_yaml(),
+            "blocks:
+  - color: blue
+    direction: left
+    position: [5, 0]
+
+  - color: red
+    direction: up
+    position: [0, 1]
+    goal: [0, 3]
+
+arrows:
+  - direction: down
+    position: [2, 2]
+"
+        );
+    }
+
+    #[test]
+    fn test_solve_tells_a_move_bound_from_an_unsolvable_puzzle() {
+        let game = |rules: &str| -> Game {
+            serde_yaml::from_str(&format!(
+                "blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n    \
+                 goal: {}\n{}",
+                "[-1, 0]", rules
+            ))
+            .unwrap()
+        };
+        assert_eq!(game("").solve(5), SolveOutcome::NoSolutionWithinBound);
+        let bounded = game("rules:\n  bounds: [[-1, 0], [2, 0]]\n");
+        assert_eq!(bounded.solve(5), SolveOutcome::ProvenUnsolvable);
+        let (_, stats) =
+            bounded.solve_with(&SolverOptions::new(5).algorithm(Algorithm::BreadthFirst));
+        assert!(stats.exhausted);
+
+        let wrapping = game("rules:\n  bounds: [[-1, 0], [2, 0]]\n  wrap: true\n");
+        assert_eq!(wrapping.solve(2), SolveOutcome::NoSolutionWithinBound);
+        assert_eq!(
+            wrapping.solve(3).solution().map(Solution::colors),
+            Some(vec!["red".to_string(); 3])
+        );
+
+        let stats = SearchStats {
+            timed_out: true,
+            exhausted: true,
+            ..SearchStats::default()
+        };
+        assert_eq!(
+            SolveOutcome::from_search(None, &stats),
+            SolveOutcome::Aborted(AbortReason::TimedOut)
+        );
+    }
+
+    #[test]
+    fn test_games_and_states_display_as_boards() {
+        let game: Game = serde_yaml::from_str(
+            "blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n    \
+             goal: [1, 0]\narrows:\n  - direction: up\n    position: [2, 0]\n",
+        )
+        .unwrap();
+        assert_eq!(game.to_string(), "Rr^\nred at (0, 0) facing right\n");
+        let solved = game.apply_move(&game.initial_state(), "red").unwrap();
+        assert_eq!(
+            format!("{}", game.display_state(&solved)),
+            "R^\nred at (1, 0) facing right\n"
+        );
+    }
+
+    #[test]
+    fn test_render_describes_a_board_too_large_to_draw() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], None);
+        game.add_block("blue".into(), Direction::Up, [i32::MAX, i32::MIN], None);
+        assert_eq!(
+            game.to_string(),
+            "The board is 2147483648 by 2147483649 cells, too large to draw\n\
+             blue at (2147483647, -2147483648) facing up\nred at (0, 0) facing right\n"
+        );
+    }
+
+    #[test]
+    fn test_solve_after_counts_the_bound_from_the_current_position() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([3, 0]));
+        let moves = vec!["red".to_string(); 2];
+
+        let (rest, _) = game.solve_after(&moves, &SolverOptions::new(1));
+        assert_eq!(rest, Some(vec!["red".to_string()]));
+        let (rest, stats) = game.solve_after(&moves, &SolverOptions::new(0));
+        assert_eq!(rest, None);
+        assert!(!stats.exhausted);
     }
 }
@@ -0,0 +1,406 @@
+use crate::game::{Color, Direction, Game, Position2D, PuzzleState};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+const COLORS: [&str; 8] = [
+    "red", "blue", "green", "yellow", "navy", "orange", "purple", "pink",
+];
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+pub struct GeneratorOptions {
+    pub blocks: usize,
+    pub arrows: usize,
+    pub size: i32,
+    pub min_moves: usize,
+    pub max_moves: i32,
+    /// Only accept puzzles whose optimal solution is exactly this long, instead of at least
+    /// `min_moves`. It cannot be more than `max_moves`.
+    pub exact_moves: Option<usize>,
+    pub attempts: usize,
+}
+
+impl GeneratorOptions {
+    /// Checks that the blocks and arrows can each be placed on distinct cells of the area.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.size < 1 {
+            return Err("the size must be at least 1".to_string());
+        }
+        let cells = (self.size as u64).pow(2);
+        if self.blocks as u64 > cells {
+            return Err(format!(
+                "{} blocks do not fit in a {}x{} area",
+                self.blocks, self.size, self.size
+            ));
+        }
+        if self.arrows as u64 > cells {
+            return Err(format!(
+                "{} arrows do not fit in a {}x{} area",
+                self.arrows, self.size, self.size
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Places blocks and arrows at random and sets the goals by playing random moves. The options
+/// must pass [`GeneratorOptions::validate`].
+pub fn random_game(options: &GeneratorOptions, rng: &mut Rng) -> Game {
+    let mut starts = HashSet::new();
+    let mut blocks = vec![];
+    for color in COLORS.iter().take(options.blocks) {
+        let start = random_cell(options.size, rng, &starts);
+        starts.insert(start);
+        let direction = DIRECTIONS[rng.below(DIRECTIONS.len())].clone();
+        blocks.push((color.to_string(), direction, start));
+    }
+
+    let mut taken = HashSet::new();
+    let mut arrows = vec![];
+    for _ in 0..options.arrows {
+        let position = random_cell(options.size, rng, &taken);
+        taken.insert(position);
+        let direction = DIRECTIONS[rng.below(DIRECTIONS.len())].clone();
+        arrows.push((direction, position));
+    }
+
+    let mut game = Game::new();
+    for (color, direction, start) in &blocks {
+        game.add_block(color.clone(), direction.clone(), *start, None);
+    }
+    for (direction, position) in &arrows {
+        game.add_arrow(direction.clone(), *position);
+    }
+
+    let walk: Vec<Color> = (0..options.max_moves)
+        .map(|_| blocks[rng.below(blocks.len())].0.clone())
+        .collect();
+    let goals = game.positions_after(&walk);
+
+    let mut game = Game::new();
+    for (color, direction, start) in blocks {
+        let goal = goals[&color];
+        game.add_block(color, direction, start, Some(goal));
+    }
+    for (direction, position) in arrows {
+        game.add_arrow(direction, position);
+    }
+
+    game
+}
+
+pub fn generate(options: &GeneratorOptions, rng: &mut Rng) -> Option<(Game, Vec<Color>)> {
+    (0..options.attempts).find_map(|_| attempt(options, rng))
+}
+
+/// What [`generate_many`] did.
+#[derive(Debug, Default)]
+pub struct BatchStats {
+    /// Candidates drawn and solved.
+    pub tried: usize,
+    pub accepted: usize,
+    /// Candidates that passed but were equivalent to a puzzle already accepted.
+    pub duplicates: usize,
+}
+
+/// Generates up to `count` puzzles on `threads` threads, passing each to `accept` as soon as it
+/// is accepted. Candidates must pass the same checks as in [`generate`] and must not be
+/// equivalent to a puzzle accepted before them, up to moving, rotating, reflecting and renaming
+/// colors. It gives up after `options.attempts` candidates for every puzzle asked for, and stops
+/// early when `accept` returns false.
+///
+/// Candidate `i` is drawn from its own generator, seeded from `seed` and `i`, and candidates are
+/// accepted in order of `i`, so the same seed gives the same puzzles on any number of threads.
+pub fn generate_many(
+    options: &GeneratorOptions,
+    count: usize,
+    threads: usize,
+    seed: u64,
+    mut accept: impl FnMut(Game, Vec<Color>) -> bool,
+) -> BatchStats {
+    let mut stats = BatchStats::default();
+    let limit = options.attempts.saturating_mul(count);
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            let sender = sender.clone();
+            let (next, stop) = (&next, &stop);
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    if index >= limit {
+                        break;
+                    }
+                    let mut rng = Rng::new(Rng::new(seed ^ index as u64).next_u64());
+                    if sender.send((index, attempt(options, &mut rng))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        // Candidates finish out of order, so each waits here until those before it are done.
+        let mut finished = BTreeMap::new();
+        let mut seen = HashSet::new();
+        'candidates: for (index, candidate) in receiver {
+            finished.insert(index, candidate);
+            while let Some(candidate) = finished.remove(&stats.tried) {
+                stats.tried += 1;
+                let Some((game, moves)) = candidate else {
+                    continue;
+                };
+                if !seen.insert(game.canonical_form()) {
+                    stats.duplicates += 1;
+                    continue;
+                }
+                stats.accepted += 1;
+                if !accept(game, moves) || stats.accepted >= count {
+                    stop.store(true, Ordering::Relaxed);
+                    break 'candidates;
+                }
+            }
+        }
+    });
+
+    stats
+}
+
+// Draws one candidate and solves it, returning it normalized if it passes.
+fn attempt(options: &GeneratorOptions, rng: &mut Rng) -> Option<(Game, Vec<Color>)> {
+    let game = random_game(options, rng);
+
+    let moves = game.solve_optimal(options.max_moves)?;
+    match options.exact_moves {
+        Some(exact_moves) if moves.len() != exact_moves => return None,
+        None if moves.len() < options.min_moves => return None,
+        _ => {}
+    }
+
+    let game = game.normalized();
+    // Normalizing moves the board, so check that the puzzle written out still needs exactly as
+    // many moves as the one that was solved.
+    let moves = game.solve_optimal(options.max_moves)?;
+    options
+        .exact_moves
+        .is_none_or(|exact_moves| moves.len() == exact_moves)
+        .then_some((game, moves))
+}
+
+/// Builds a puzzle by walking up to `depth` moves backwards from `solved`, whose blocks sit where
+/// the puzzle ends: each block with a goal must be on it. The walk never steps to a position it
+/// has been in or one that is already solved, and stops early if every way back does. Returns the puzzle, which starts where
+/// the walk ended, and the walk forwards, which solves it.
+pub fn scramble(solved: &Game, depth: usize, rng: &mut Rng) -> Result<(Game, Vec<Color>), String> {
+    let mut state = solved.initial_state();
+    for (color, block) in state.blocks() {
+        let goals = solved.goals_of(color);
+        if !goals.is_empty() && !goals.contains(&block.position) {
+            return Err(format!("{} is not on its goal", color));
+        }
+    }
+
+    let mut seen = HashSet::from([state.clone()]);
+    let mut walk = vec![];
+    for _ in 0..depth {
+        let mut predecessors: Vec<(Color, PuzzleState)> = solved
+            .predecessors(&state)
+            .into_iter()
+            .filter(|(_, before)| !seen.contains(before) && !solved.is_solved(before))
+            .collect();
+        if predecessors.is_empty() {
+            break;
+        }
+        let (color, before) = predecessors.swap_remove(rng.below(predecessors.len()));
+        seen.insert(before.clone());
+        walk.push(color);
+        state = before;
+    }
+    walk.reverse();
+
+    let mut game = Game::new();
+    for (color, block) in state.blocks() {
+        game.add_block(color.clone(), block.direction.clone(), block.position, None);
+        for goal in solved.goals_of(color) {
+            game.add_goal(color, *goal);
+        }
+    }
+    for (position, direction) in solved.arrows() {
+        game.add_arrow(direction, position);
+    }
+
+    Ok((game, walk))
+}
+
+fn random_cell(size: i32, rng: &mut Rng, taken: &HashSet<Position2D>) -> Position2D {
+    loop {
+        let cell = [
+            rng.below(size as usize) as i32,
+            rng.below(size as usize) as i32,
+        ];
+        if !taken.contains(&cell) {
+            return cell;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_is_reproducible_and_respects_min_moves() {
+        let options = GeneratorOptions {
+            blocks: 2,
+            arrows: 2,
+            size: 4,
+            min_moves: 4,
+            max_moves: 8,
+            exact_moves: None,
+            attempts: 100,
+        };
+
+        let (game, moves) = generate(&options, &mut Rng::new(7)).unwrap();
+        let (again, _) = generate(&options, &mut Rng::new(7)).unwrap();
+
+        assert!(moves.len() >= 4);
+        assert!(game.verify(&moves).solved);
+        assert_eq!(game.to_yaml(), again.to_yaml());
+    }
+
+    #[test]
+    fn test_generate_many_is_distinct_and_independent_of_threads() {
+        let options = GeneratorOptions {
+            blocks: 2,
+            arrows: 1,
+            size: 3,
+            min_moves: 2,
+            max_moves: 6,
+            exact_moves: None,
+            attempts: 50,
+        };
+
+        let mut puzzles = vec![];
+        let mut forms = HashSet::new();
+        let stats = generate_many(&options, 5, 4, 11, |game, moves| {
+            assert!(game.verify(&moves).solved);
+            puzzles.push(game.to_yaml());
+            forms.insert(game.canonical_form());
+            true
+        });
+        assert_eq!(stats.accepted, 5);
+        assert_eq!(puzzles.len(), 5);
+        assert!(stats.tried >= 5 + stats.duplicates);
+
+        let mut alone = vec![];
+        generate_many(&options, 5, 1, 11, |game, _| {
+            alone.push(game.to_yaml());
+            true
+        });
+        assert_eq!(alone, puzzles);
+        assert_eq!(forms.len(), 5);
+
+        let mut first = vec![];
+        let stats = generate_many(&options, 5, 4, 11, |game, _| {
+            first.push(game);
+            false
+        });
+        assert_eq!((stats.accepted, first.len()), (1, 1));
+    }
+
+    #[test]
+    fn test_scramble_walks_back_from_the_solved_board() {
+        let mut solved = Game::new();
+        solved.add_block("red".into(), Direction::Up, [0, 0], Some([0, 0]));
+        solved.add_block("blue".into(), Direction::Left, [2, 1], Some([2, 1]));
+        solved.add_block("green".into(), Direction::Down, [1, 3], None);
+        solved.add_arrow(Direction::Right, [1, 1]);
+
+        let (game, moves) = scramble(&solved, 10, &mut Rng::new(7)).unwrap();
+        assert_eq!(moves.len(), 10);
+        assert!(game.verify(&moves).solved);
+        assert_eq!(game.goals_of("red"), [[0, 0]]);
+        assert_eq!(game.arrows(), solved.arrows());
+        let (again, _) = scramble(&solved, 10, &mut Rng::new(7)).unwrap();
+        assert_eq!(game.to_yaml(), again.to_yaml());
+
+        solved.set_goal("red", Some([5, 5]));
+        assert!(scramble(&solved, 10, &mut Rng::new(7)).is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_areas_too_small() {
+        let mut options = GeneratorOptions {
+            blocks: 3,
+            arrows: 2,
+            size: 5,
+            min_moves: 1,
+            max_moves: 20,
+            exact_moves: None,
+            attempts: 1,
+        };
+        assert!(options.validate().is_ok());
+
+        for size in [0, -3] {
+            options.size = size;
+            assert!(options.validate().is_err());
+        }
+        options.size = 1;
+        assert!(options.validate().is_err());
+        options.size = 2;
+        assert!(options.validate().is_ok());
+        options.arrows = 10;
+        assert!(options.validate().is_err());
+        options.arrows = 4;
+        assert!(options.validate().is_ok());
+    }
+
+    #[test]
+    fn test_generate_hits_an_exact_length() {
+        let options = GeneratorOptions {
+            blocks: 2,
+            arrows: 2,
+            size: 4,
+            min_moves: 1,
+            max_moves: 8,
+            exact_moves: Some(5),
+            attempts: 1000,
+        };
+
+        let (game, moves) = generate(&options, &mut Rng::new(3)).unwrap();
+        assert_eq!(moves.len(), 5);
+        assert_eq!(game.solve_optimal(8).map(|moves| moves.len()), Some(5));
+    }
+}
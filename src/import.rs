@@ -0,0 +1,222 @@
+use crate::game::{Color, Direction, Game};
+use serde::{Deserialize, Serialize};
+
+// Levels as the original game and most fan dumps write them: `squares`, `circles` (the goals),
+// and `arrows`, each with `x` and `y` in screen coordinates, so y grows downwards.
+#[derive(Deserialize)]
+struct OriginalLevel {
+    #[serde(default)]
+    name: Option<String>,
+    squares: Vec<Square>,
+    #[serde(default)]
+    circles: Vec<Circle>,
+    #[serde(default)]
+    arrows: Vec<Arrow>,
+}
+
+#[derive(Deserialize)]
+struct Square {
+    x: i32,
+    y: i32,
+    color: String,
+    #[serde(alias = "direction")]
+    dir: String,
+}
+
+#[derive(Deserialize)]
+struct Circle {
+    x: i32,
+    y: i32,
+    color: String,
+}
+
+#[derive(Deserialize)]
+struct Arrow {
+    x: i32,
+    y: i32,
+    #[serde(alias = "direction")]
+    dir: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OriginalFile {
+    Level(OriginalLevel),
+    Levels(Vec<OriginalLevel>),
+    Collection { levels: Vec<OriginalLevel> },
+}
+
+// Accepts JSON as well as JavaScript object literals with bare keys and single quotes, since
+// YAML flow syntax covers both. Anything around the outermost brackets, such as
+// `var levels = ...;`, is ignored. Levels without a name are numbered from 1.
+pub fn import_original(text: &str) -> Result<Vec<(String, Game)>, String> {
+    let start = text.find(['[', '{']).unwrap_or(0);
+    let end = text.rfind([']', '}']).map_or(text.len(), |end| end + 1);
+    let file: OriginalFile = serde_yaml::from_str(text.get(start..end).unwrap_or(text))
+        .map_err(|error| format!("not a level definition: {}", error))?;
+    let levels = match file {
+        OriginalFile::Level(level) => vec![level],
+        OriginalFile::Levels(levels) | OriginalFile::Collection { levels } => levels,
+    };
+
+    levels
+        .into_iter()
+        .enumerate()
+        .map(|(i, level)| {
+            let name = level
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("level_{:02}", i + 1));
+            let game = convert(level).map_err(|error| format!("{}: {}", name, error))?;
+            Ok((name, game))
+        })
+        .collect()
+}
+
+fn convert(level: OriginalLevel) -> Result<Game, String> {
+    let mut game = Game::new();
+    for square in &level.squares {
+        if game.colors().contains(&square.color) {
+            return Err(format!("there are two {} squares", square.color));
+        }
+        let goal = level
+            .circles
+            .iter()
+            .find(|circle| circle.color == square.color)
+            .map(|circle| [circle.x, -circle.y]);
+        game.add_block(
+            square.color.clone(),
+            direction(&square.dir)?,
+            [square.x, -square.y],
+            goal,
+        );
+    }
+    if let Some(circle) = level
+        .circles
+        .iter()
+        .find(|circle| !game.colors().contains(&circle.color))
+    {
+        return Err(format!("the {} circle has no square", circle.color));
+    }
+    for arrow in &level.arrows {
+        game.add_arrow(direction(&arrow.dir)?, [arrow.x, -arrow.y]);
+    }
+
+    Ok(game)
+}
+
+fn direction(name: &str) -> Result<Direction, String> {
+    match name.to_ascii_lowercase().as_str() {
+        "up" | "u" => Ok(Direction::Up),
+        "down" | "d" => Ok(Direction::Down),
+        "left" | "l" => Ok(Direction::Left),
+        "right" | "r" => Ok(Direction::Right),
+        _ => Err(format!("unknown direction `{}`", name)),
+    }
+}
+
+// A solution as the original game replays it: the square tapped for each move, at its screen
+// position when tapped, in the coordinates `import_original` reads.
+#[derive(Serialize)]
+struct Replay {
+    taps: Vec<Tap>,
+}
+
+#[derive(Serialize)]
+struct Tap {
+    x: i32,
+    y: i32,
+    color: Color,
+}
+
+// Written as JSON, e.g. `{"taps":[{"x":0,"y":0,"color":"red"}]}`. `None` if a move names a block
+// the puzzle does not have or one sits at a y that cannot be flipped.
+pub fn export_replay(game: &Game, moves: &[Color]) -> Option<String> {
+    let taps = game
+        .describe_moves(moves)?
+        .into_iter()
+        .zip(moves)
+        .map(|(step, color)| {
+            Some(Tap {
+                x: step.from[0],
+                y: step.from[1].checked_neg()?,
+                color: color.clone(),
+            })
+        })
+        .collect::<Option<Vec<Tap>>>()?;
+    serde_json::to_string(&Replay { taps }).ok()
+}
+
+// Several levels are written as a pack that `solve-all` reads.
+pub fn to_pack_yaml(levels: &[(String, Game)]) -> String {
+    let puzzles: Vec<String> = levels
+        .iter()
+        .map(|(name, game)| {
+            let body: Vec<String> = game
+                .to_yaml()
+                .lines()
+                .map(|line| match line {
+                    "" => String::new(),
+                    _ => format!("    {}", line),
+                })
+                .collect();
+            format!("  - name: {}\n{}\n", name, body.join("\n"))
+        })
+        .collect();
+    format!("puzzles:\n{}", puzzles.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack::parse_pack;
+
+    #[test]
+    fn test_imports_javascript_levels() {
+        let text = "var levels = [
+            {squares: [{x: 0, y: 0, color: 'red', dir: 'down'}], circles: [{x: 0, y: 2, color: 'red'}]},
+            {name: 'turn', squares: [{x: 0, y: 0, color: 'blue', dir: 'right'}],
+             circles: [{x: 1, y: -1, color: 'blue'}], arrows: [{x: 1, y: 0, dir: 'up'}]}
+        ];";
+
+        let levels = import_original(text).unwrap();
+        assert_eq!(levels[0].0, "level_01");
+        assert_eq!(
+            levels[0].1.to_yaml(),
+            "blocks:\n  - color: red\n    direction: down\n    position: [0, 0]\n    goal: [0, -2]\n"
+        );
+        assert_eq!(
+            levels[1]
+                .1
+                .solve(5)
+                .solution()
+                .map(|solution| solution.length),
+            Some(2)
+        );
+
+        let pack = parse_pack(to_pack_yaml(&levels).as_bytes()).unwrap();
+        assert_eq!(pack[1].name, "turn");
+        assert_eq!(
+            pack[1].game.as_ref().unwrap().to_yaml(),
+            levels[1].1.to_yaml()
+        );
+
+        assert!(import_original("{squares: [], circles: [{x: 0, y: 0, color: red}]}").is_err());
+    }
+
+    #[test]
+    fn test_exports_taps_in_screen_coordinates() {
+        let levels = import_original(
+            "{squares: [{x: 0, y: 0, color: blue, dir: right}], \
+             circles: [{x: 1, y: -1, color: blue}], arrows: [{x: 1, y: 0, dir: up}]}",
+        )
+        .unwrap();
+        let game = &levels[0].1;
+        let moves = game.solve(5).into_solution().unwrap().colors();
+        assert_eq!(
+            export_replay(game, &moves).unwrap(),
+            r#"{"taps":[{"x":0,"y":0,"color":"blue"},{"x":1,"y":0,"color":"blue"}]}"#
+        );
+        assert_eq!(export_replay(game, &["green".to_string()]), None);
+    }
+}
@@ -0,0 +1,49 @@
+pub mod analysis;
+#[cfg(feature = "game")]
+pub mod cache;
+#[cfg(all(feature = "json", feature = "yaml"))]
+pub mod diagnostics;
+#[cfg(feature = "game")]
+pub mod dot;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "game")]
+pub mod fifteen;
+#[cfg(feature = "game")]
+pub mod game;
+#[cfg(feature = "game")]
+pub mod generator;
+#[cfg(feature = "game")]
+mod heuristics;
+#[cfg(all(feature = "json", feature = "yaml"))]
+pub mod import;
+#[cfg(feature = "game")]
+pub mod minimizer;
+#[cfg(feature = "game")]
+pub mod notation;
+#[cfg(feature = "yaml")]
+pub mod pack;
+#[cfg(feature = "game")]
+pub mod parse;
+pub mod puzzle;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "game")]
+pub mod rating;
+#[cfg(feature = "game")]
+pub mod render;
+#[cfg(feature = "game")]
+pub mod rules;
+#[cfg(feature = "screenshot")]
+pub mod screenshot;
+pub mod search;
+#[cfg(feature = "game")]
+pub mod sokoban;
+#[cfg(feature = "game")]
+pub mod solver;
+#[cfg(feature = "game")]
+mod symmetry;
+#[cfg(feature = "game")]
+pub mod tiles;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -1,22 +1,2583 @@
-mod game;
-mod heuristics;
-mod search;
+mod bench;
+mod config;
+mod dedupe;
+mod edit;
+mod play;
+mod repl;
+mod rpc;
+mod serve;
+mod session;
+mod summary;
 
-use std::env;
-use std::fs::File;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info_span, Level};
 
-use game::Game;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap_complete::Shell;
+use config::{Algorithm, Config, Format};
+use notify::{RecursiveMode, Watcher};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use solver_of_squares::cache::DeadEndCache;
+use solver_of_squares::game::{Checkpoint, Color, ExpansionRecord, Game};
+use solver_of_squares::generator::{GeneratorOptions, Rng};
+use solver_of_squares::parse::{self, parse_game};
+use solver_of_squares::puzzle::Puzzle;
+use solver_of_squares::search::{self, OpenList, Progress, SearchStats, TieBreak};
+use solver_of_squares::solver::{self, AbortReason, SolveOutcome, SolverOptions};
+use solver_of_squares::{
+    diagnostics, dot, fifteen, generator, import, minimizer, notation, pack, puzzle, rating, render,
+};
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let path = &args[1];
-    let file = File::open(path).expect("could not open file");
-    let game: Game = serde_yaml::from_reader(file).expect("could not parse input file");
+#[derive(Parser)]
+#[command(about = "A solver for Game about Squares")]
+#[command(args_conflicts_with_subcommands = true)]
+#[command(after_help = "Exit codes:
+  0  solved, or the command completed
+  1  no solution within the move bound
+  2  the puzzle or another input file could not be read
+  3  a timeout or other limit was hit
+  4  invalid command line arguments
+  5  an output file could not be written")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    if let Some(moves) = game.solve(50) {
+    #[command(flatten)]
+    solve: SolveArgs,
+
+    /// Log more detail to stderr (-v for progress, -vv for search internals)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Only log errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Read default options from this TOML file instead of ~/.config/solver-of-squares/config.toml
+    #[arg(long, value_name = "PATH", global = true)]
+    config: Option<PathBuf>,
+
+    /// Number of threads used to solve or generate several puzzles at once, or to race --portfolio searches
+    #[arg(long, global = true, value_parser = clap::value_parser!(u16).range(1..))]
+    threads: Option<u16>,
+
+    /// Print a manual page in roff format and exit
+    #[arg(long, exclusive = true)]
+    man: bool,
+
+    /// Answer JSON-RPC requests on stdin and stdout, for editors that run the solver as a subprocess
+    #[arg(long, exclusive = true)]
+    stdio: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Solve a puzzle (the default when no subcommand is given)
+    Solve(Box<SolveArgs>),
+    /// Count the distinct solutions up to a move bound
+    Count(CountArgs),
+    /// Replay a move list and check whether it solves the puzzle
+    Verify(VerifyArgs),
+    /// Translate a puzzle to the origin and write it back out in a canonical order
+    Normalize(NormalizeArgs),
+    /// Convert levels from the original game's format to puzzle YAML
+    Import(ImportArgs),
+    /// Check whether two puzzles are the same up to position, rotation, reflection, and colors
+    Diff(DiffArgs),
+    /// Find the puzzles in a directory that are the same up to position, rotation, reflection, and colors
+    Dedupe(DedupeArgs),
+    /// Generate a random puzzle that is solvable in at least a minimum number of moves
+    Generate(GenerateArgs),
+    /// Build a puzzle by playing moves backwards from a solved board, so it is always solvable
+    Scramble(ScrambleArgs),
+    /// Estimate how difficult a puzzle is from solver metrics
+    Rate(RateArgs),
+    /// Enumerate the reachable states and report dead ends and distances to the goal
+    Analyze(AnalyzeArgs),
+    /// Compare the heuristic with the true distance to the goal on small puzzles and report where it overestimates or is inconsistent
+    CheckHeuristic(CheckHeuristicArgs),
+    /// Report which arrows are redundant and write the puzzle without them
+    Minimize(MinimizeArgs),
+    /// Draw a puzzle's starting position without solving it
+    Render(RenderArgs),
+    /// Solve every puzzle in a level pack and print a summary table
+    SolveAll(SolveAllArgs),
+    /// Run several algorithms on each puzzle and compare time, nodes, and solution length
+    Bench(BenchArgs),
+    /// Play a puzzle interactively in the terminal
+    Play(PlayArgs),
+    /// Design a puzzle in the terminal while the solver checks it
+    Edit(EditArgs),
+    /// Explore puzzles and the solver interactively from a command prompt
+    Repl(ReplArgs),
+    /// Serve POST /solve and POST /validate over HTTP
+    Serve(ServeArgs),
+    /// Watch puzzle files and publish their problems on stdout as language server diagnostics
+    Diagnostics(DiagnosticsArgs),
+    /// Solve an N×N sliding tile puzzle with the same search
+    Fifteen(FifteenArgs),
+    /// Print a shell completion script
+    Completions(CompletionsArgs),
+    /// List the solves in a --log-dir session log, and check or draw their solutions again
+    ReplayLog(ReplayLogArgs),
+}
+
+#[derive(Args)]
+struct SolveArgs {
+    /// Paths to YAML files describing puzzles, or `-` for stdin; several files are solved in parallel
+    paths: Vec<PathBuf>,
+
+    /// Give up on a puzzle if it cannot be solved within this many moves
+    #[arg(long, default_value_t = 50)]
+    max_moves: i32,
+
+    /// Give up on a puzzle after this many seconds
+    #[arg(long, value_name = "SECONDS", value_parser = parse_timeout)]
+    timeout: Option<Duration>,
+
+    /// Search breadth-first for a shortest solution, and among those the one that pushes other blocks the fewest times
+    #[arg(long, conflicts_with_all = ["all_optimal", "timeout", "raise_bound", "tie_break", "trace_search", "dot", "checkpoint"])]
+    optimal: bool,
+
+    /// If there is no solution within --max-moves, keep doubling the bound up to LIMIT, continuing the search instead of restarting it
+    #[arg(long, value_name = "LIMIT", conflicts_with_all = ["all_optimal", "trace_search", "dot", "checkpoint"])]
+    raise_bound: Option<i32>,
+
+    /// Keep at most N positions in memory, dropping the least promising ones (SMA*)
+    #[arg(long, value_name = "N", conflicts_with_all = ["optimal", "all_optimal", "raise_bound", "tie_break", "trace_search", "dot", "checkpoint"])]
+    max_nodes: Option<usize>,
+
+    /// Search depth-first under a rising bound (IDA*), using little memory
+    #[arg(long, conflicts_with_all = ["optimal", "all_optimal", "max_nodes", "anytime", "raise_bound", "tie_break", "trace_search", "dot", "checkpoint"])]
+    ida: bool,
+
+    /// Remember at most N positions between IDA* rounds; 0 turns the table off
+    #[arg(long, value_name = "N", requires = "ida")]
+    table_size: Option<usize>,
+
+    /// Race A*, weighted A*, IDA*, and greedy search on separate threads and keep the first solution found
+    #[arg(long, conflicts_with_all = ["optimal", "all_optimal", "max_nodes", "ida", "anytime", "raise_bound", "trace_search", "dot", "checkpoint", "cache"])]
+    portfolio: bool,
+
+    /// Print the search speed, nodes expanded, current depth, and open-set memory to stderr every second
+    #[arg(long, conflicts_with_all = ["optimal", "all_optimal", "max_nodes", "ida", "portfolio", "anytime", "raise_bound", "trace_search", "dot", "checkpoint", "cache"])]
+    live_stats: bool,
+
+    /// Print a first solution quickly from a search weighted by WEIGHT, then each shorter one found until the search finishes or times out
+    #[arg(long, value_name = "WEIGHT", num_args = 0..=1, default_missing_value = "3", conflicts_with_all = ["optimal", "all_optimal", "max_nodes", "raise_bound", "tie_break", "trace_search", "dot", "checkpoint"])]
+    anytime: Option<f64>,
+
+    /// How to order positions that look equally promising: lower-estimate, higher-cost, lower-index, or fifo
+    #[arg(long, value_name = "STRATEGY", conflicts_with_all = ["all_optimal", "raise_bound", "trace_search", "dot", "checkpoint"])]
+    tie_break: Option<TieBreak>,
+
+    /// How A* holds the positions it has yet to expand: heap, or buckets for constant-time queueing on big searches at the cost of the pushes ordering and the lower-index tie-break
+    #[arg(long, value_name = "KIND", conflicts_with_all = ["optimal", "all_optimal", "max_nodes", "ida", "anytime", "raise_bound", "trace_search", "dot", "checkpoint"])]
+    open_list: Option<OpenList>,
+
+    /// Store only the last move with each position and rebuild the solution at the end, to save memory on long searches
+    #[arg(long, conflicts_with_all = ["optimal", "all_optimal", "raise_bound", "trace_search", "dot", "checkpoint"])]
+    compact_paths: bool,
+
+    /// Start the blocks named here from these cells and directions instead, e.g. `red=(2,3,up);blue=(0,0,left)`, or from a file of such placements
+    #[arg(long, value_name = "PLACEMENTS|PATH")]
+    from: Option<String>,
+
+    /// Keep running and solve the puzzle again every time the file is saved
+    #[arg(long)]
+    watch: bool,
+
+    /// Report every distinct solution of minimal length instead of the first one found
+    #[arg(long)]
+    all_optimal: bool,
+
+    /// Try to shorten the solution by re-solving windows of up to WINDOW moves
+    #[arg(long, value_name = "WINDOW", num_args = 0..=1, default_missing_value = "6")]
+    optimize: Option<usize>,
+
+    /// Only reveal the first K moves of the solution
+    #[arg(long, value_name = "K", conflicts_with = "all_optimal")]
+    hint: Option<usize>,
+
+    /// Describe every move: where the block went, what it pushed, and any arrow that turned it
+    #[arg(long, conflicts_with = "all_optimal")]
+    detailed: bool,
+
+    /// For each move, list only the blocks that changed: the one moved, the ones pushed, and any arrow turns
+    #[arg(long, conflicts_with = "all_optimal")]
+    diff: bool,
+
+    /// For each move, say whether it positions a block, turns it on an arrow, or pushes others, how it changes the heuristic's estimate, and which blocks it brings nearer their goals
+    #[arg(long, conflicts_with = "all_optimal")]
+    explain: bool,
+
+    /// Also print the solution in compact notation, e.g. `R@(2,3)->U(2,4) push B`
+    #[arg(long, conflicts_with = "all_optimal")]
+    notation: bool,
+
+    /// Write the moves, the board after every move, and the final positions to this YAML file
+    #[arg(long, value_name = "PATH", conflicts_with = "all_optimal")]
+    solution_out: Option<PathBuf>,
+
+    /// Write the taps that replay the solution in the original game to this JSON file
+    #[arg(long, value_name = "PATH", conflicts_with = "all_optimal")]
+    replay_out: Option<PathBuf>,
+
+    /// Write a standalone HTML page that plays the solution back move by move to this file
+    #[arg(long, value_name = "PATH", conflicts_with = "all_optimal")]
+    html: Option<PathBuf>,
+
+    /// Print search statistics: set sizes, duplicates, branching factor, and heuristic accuracy
+    #[arg(long, conflicts_with = "all_optimal")]
+    stats: bool,
+
+    /// With --hint, also print the board after the revealed moves
+    #[arg(long, requires = "hint")]
+    show_board: bool,
+
+    /// Write one JSON record per expanded search node to this file
+    #[arg(long, value_name = "PATH", conflicts_with = "all_optimal")]
+    trace_search: Option<PathBuf>,
+
+    /// Write the explored search tree as a Graphviz DOT graph, with the solution path highlighted
+    #[arg(long, value_name = "PATH", conflicts_with = "all_optimal")]
+    dot: Option<PathBuf>,
+
+    /// Only include search nodes up to this many moves deep in the DOT graph
+    #[arg(long, value_name = "DEPTH", requires = "dot")]
+    dot_depth: Option<i32>,
+
+    /// Skip positions that earlier --optimal runs recorded in this file as unable to finish within the bound, and record what this run proves
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["all_optimal", "raise_bound", "anytime", "trace_search", "dot", "checkpoint"])]
+    cache: Option<PathBuf>,
+
+    /// Periodically save the search frontier to this file so a long search can be resumed
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["all_optimal", "trace_search", "dot"])]
+    checkpoint: Option<PathBuf>,
+
+    /// How often to save the checkpoint, e.g. `90`, `60s`, `5m`, or `1h`
+    #[arg(long, value_name = "DURATION", default_value = "60s", value_parser = parse_duration, requires = "checkpoint")]
+    checkpoint_interval: Duration,
+
+    /// Continue the search saved in the --checkpoint file instead of starting over
+    #[arg(long, requires = "checkpoint")]
+    resume: bool,
+
+    /// Append the puzzle, options, outcome, solution, and statistics of every solve to a JSONL session log in this directory
+    #[arg(long, value_name = "DIR", conflicts_with = "all_optimal")]
+    log_dir: Option<PathBuf>,
+
+    /// Write a row per puzzle to this file as CSV: whether it was solved, the moves, the time, the nodes, and any limit hit
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["all_optimal", "hint", "from", "watch", "trace_search", "dot", "checkpoint", "solution_out", "replay_out", "html", "anytime", "portfolio", "live_stats", "cache"])]
+    csv: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct CountArgs {
+    /// Path to a YAML file describing the puzzle, or `-` for stdin
+    path: PathBuf,
+
+    /// Only count solutions with at most this many moves
+    #[arg(short = 'n', long)]
+    max_moves: usize,
+}
+
+#[derive(Args)]
+struct VerifyArgs {
+    /// Path to a YAML file describing the puzzle, or `-` for stdin
+    path: PathBuf,
+
+    /// Path to a YAML file listing the moves, either as a sequence of colors or under `moves`, or a file of moves in `--notation` form
+    solution: PathBuf,
+}
+
+#[derive(Args)]
+struct NormalizeArgs {
+    /// Path to a YAML file describing the puzzle, or `-` for stdin
+    path: PathBuf,
+
+    /// Write the normalized puzzle here instead of to stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ImportArgs {
+    /// Path to a JSON or JavaScript level definition, or a list of them, a PNG screenshot, or `-`
+    /// for stdin
+    path: PathBuf,
+
+    /// Write the puzzle here instead of to stdout; several levels are written as a pack
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Width of a board cell in a screenshot, in pixels
+    #[cfg(feature = "screenshot")]
+    #[arg(long, default_value_t = 100)]
+    cell_size: u32,
+}
+
+#[derive(Args)]
+struct DiffArgs {
+    /// Path to a YAML file describing the first puzzle, or `-` for stdin
+    first: PathBuf,
+
+    /// Path to a YAML file describing the second puzzle
+    second: PathBuf,
+}
+
+#[derive(Args)]
+struct DedupeArgs {
+    /// Directory of puzzle files; the first file of each group, in name order, is kept
+    dir: PathBuf,
+
+    /// Delete the duplicates
+    #[arg(long, conflicts_with = "link")]
+    delete: bool,
+
+    /// Replace each duplicate with a hard link to the file kept
+    #[arg(long)]
+    link: bool,
+}
+
+#[derive(Args)]
+struct GenerateArgs {
+    /// Number of blocks, each with a goal
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u8).range(1..=8))]
+    blocks: u8,
+
+    /// Number of arrows
+    #[arg(long, default_value_t = 2)]
+    arrows: usize,
+
+    /// Width and height of the square area that blocks, goals, and arrows are placed in
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(i32).range(1..))]
+    size: i32,
+
+    /// Only accept puzzles whose optimal solution is at least this long
+    #[arg(long, default_value_t = 1)]
+    min_moves: usize,
+
+    /// Number of random moves played to place the goals, which bounds the solution length
+    #[arg(long, default_value_t = 20)]
+    max_moves: i32,
+
+    /// Only accept puzzles whose optimal solution is exactly N moves, checked again on the puzzle written out
+    #[arg(long, value_name = "N", conflicts_with = "min_moves")]
+    exact_moves: Option<usize>,
+
+    /// Number of candidates to try before giving up, for each puzzle asked for
+    #[arg(long, default_value_t = 1000)]
+    attempts: usize,
+
+    /// Seed for the random number generator
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Write the puzzle here instead of to stdout
+    #[arg(short, long, conflicts_with = "output_dir")]
+    output: Option<PathBuf>,
+
+    /// Generate this many distinct puzzles on every core, written to --output-dir
+    #[arg(long, value_name = "N", requires = "output_dir", value_parser = clap::value_parser!(u32).range(1..))]
+    count: Option<u32>,
+
+    /// Write each puzzle to its own numbered file in this directory as soon as it is accepted
+    #[arg(long, value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ScrambleArgs {
+    /// Path to a YAML file with every block where the puzzle should end, on its goal if it has one
+    #[arg(long, value_name = "PATH")]
+    from: PathBuf,
+
+    /// Number of moves to play backwards, which bounds the solution length
+    #[arg(long, default_value_t = 15)]
+    depth: usize,
+
+    /// Seed for the random number generator
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Write the puzzle here instead of to stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct RateArgs {
+    /// Path to a YAML file describing the puzzle, or `-` for stdin
+    path: PathBuf,
+
+    /// Give up if the puzzle cannot be solved within this many moves
+    #[arg(long, default_value_t = 50)]
+    max_moves: i32,
+}
+
+#[derive(Args)]
+struct PlayArgs {
+    /// Path to a YAML file describing the puzzle
+    path: PathBuf,
+
+    /// Move bound used when asking the solver for help
+    #[arg(long, default_value_t = 50)]
+    max_moves: i32,
+}
+
+#[derive(Args)]
+struct EditArgs {
+    /// Path to the YAML file to edit; it is created on the first save if it does not exist
+    path: PathBuf,
+
+    /// Report puzzles as unsolvable if they need more than this many moves
+    #[arg(long, default_value_t = 30)]
+    max_moves: i32,
+}
+
+#[derive(Args)]
+struct ReplArgs {
+    /// Puzzle to load at startup
+    path: Option<PathBuf>,
+
+    /// Initial move bound for `solve`; change it with `set max-moves N`
+    #[arg(long, default_value_t = 50)]
+    max_moves: i32,
+}
+
+#[derive(Args)]
+struct ServeArgs {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1")]
+    bind: String,
+
+    /// Port to listen on
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+
+    /// Highest move bound a request may ask for
+    #[arg(long, default_value_t = 50)]
+    max_moves: i32,
+
+    /// Longest a request may search, in seconds
+    #[arg(long, value_name = "SECONDS", default_value = "10", value_parser = parse_timeout)]
+    timeout: Duration,
+
+    /// Most positions a request may expand
+    #[arg(long, value_name = "N")]
+    max_expanded: Option<usize>,
+
+    /// Most bytes a request's search may be estimated to hold
+    #[arg(long, value_name = "BYTES")]
+    max_memory: Option<usize>,
+
+    /// Number of requests handled at once; further requests wait their turn
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    workers: u16,
+}
+
+#[derive(Args)]
+struct DiagnosticsArgs {
+    /// Puzzle files to check
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Report puzzles as unsolvable if they need more than this many moves
+    #[arg(long, default_value_t = 30)]
+    max_moves: i32,
+
+    /// Longest to search each puzzle for a solution, in seconds
+    #[arg(long, value_name = "SECONDS", default_value = "5", value_parser = parse_timeout)]
+    timeout: Duration,
+
+    /// Check each file once and exit instead of watching for changes
+    #[arg(long)]
+    once: bool,
+}
+
+#[derive(Args)]
+struct FifteenArgs {
+    /// Path to a text file with the tiles row by row, `_` or 0 for the blank, or `-` for stdin
+    path: PathBuf,
+
+    /// Give up if the puzzle cannot be solved within this many moves
+    #[arg(long, default_value_t = 80)]
+    max_moves: i32,
+
+    /// Give up after this many seconds
+    #[arg(long, value_name = "SECONDS", value_parser = parse_timeout)]
+    timeout: Option<Duration>,
+
+    /// Estimate of the remaining moves: manhattan or misplaced
+    #[arg(long, default_value = "manhattan")]
+    heuristic: fifteen::Heuristic,
+}
+
+#[derive(Args)]
+struct AnalyzeArgs {
+    /// Path to a YAML file describing the puzzle, or `-` for stdin
+    path: PathBuf,
+
+    /// Only enumerate states up to this many moves from the start
+    #[arg(long, default_value_t = 20)]
+    max_depth: usize,
+
+    /// Stop after this many distinct states
+    #[arg(long, default_value_t = 1_000_000)]
+    max_states: usize,
+}
+
+#[derive(Args)]
+struct CheckHeuristicArgs {
+    /// Paths to YAML files describing puzzles; without any, random puzzles are checked
+    paths: Vec<PathBuf>,
+
+    /// Estimate to check: manhattan or zero
+    #[arg(long, default_value = "manhattan")]
+    heuristic: solver::Heuristic,
+
+    /// Multiply the estimate by this before comparing it
+    #[arg(long, default_value_t = 1.0)]
+    weight: f64,
+
+    /// Only enumerate states up to this many moves from the start
+    #[arg(long, default_value_t = 12)]
+    max_depth: usize,
+
+    /// Stop after this many distinct states per puzzle
+    #[arg(long, default_value_t = 200_000)]
+    max_states: usize,
+
+    /// Number of random puzzles to check when no paths are given
+    #[arg(long, default_value_t = 20)]
+    samples: usize,
+
+    /// Number of blocks in each random puzzle
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u8).range(1..=8))]
+    blocks: u8,
+
+    /// Number of arrows in each random puzzle
+    #[arg(long, default_value_t = 2)]
+    arrows: usize,
+
+    /// Width and height of the area random puzzles are placed in
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(i32).range(1..))]
+    size: i32,
+
+    /// Seed for the random number generator
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+}
+
+#[derive(Args)]
+struct MinimizeArgs {
+    /// Path to a YAML file describing the puzzle, or `-` for stdin
+    path: PathBuf,
+
+    /// Give up if the puzzle cannot be solved within this many moves
+    #[arg(long, default_value_t = 50)]
+    max_moves: i32,
+
+    /// Write the minimized puzzle here
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct SolveAllArgs {
+    /// Path to a YAML file with a list of named puzzles under `puzzles`
+    path: PathBuf,
+
+    /// Give up on a puzzle if it cannot be solved within this many moves
+    #[arg(long, default_value_t = 50)]
+    max_moves: i32,
+
+    /// Also write a row per puzzle to this file as CSV
+    #[arg(long, value_name = "PATH")]
+    csv: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct RenderArgs {
+    /// Path to a YAML file describing the puzzle, or `-` for stdin
+    path: PathBuf,
+
+    /// Plain characters without colors, even on a terminal
+    #[arg(long, conflicts_with = "svg")]
+    ascii: bool,
+
+    /// An SVG image instead of text
+    #[arg(long)]
+    svg: bool,
+
+    /// Write to this file instead of stdout
+    #[arg(long, short, value_name = "PATH")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct BenchArgs {
+    /// Paths to YAML files describing puzzles
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Comma-separated algorithms to compare: astar, idastar, breadth-first, sma, greedy, or weighted:W for A* with the heuristic weighted by W
+    #[arg(
+        long,
+        value_name = "LIST",
+        value_delimiter = ',',
+        default_value = "astar,weighted:2,idastar,greedy"
+    )]
+    algorithms: Vec<bench::Configuration>,
+
+    /// Give up on a puzzle if it cannot be solved within this many moves
+    #[arg(long, default_value_t = 50)]
+    max_moves: i32,
+
+    /// Give up on a run after this many seconds
+    #[arg(long, value_name = "SECONDS", value_parser = parse_timeout)]
+    timeout: Option<Duration>,
+
+    /// Also write every run to this file as CSV
+    #[arg(long, value_name = "PATH")]
+    csv: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ReplayLogArgs {
+    /// A session log written by --log-dir, or the directory holding them
+    path: PathBuf,
+
+    /// Replay each solution on the logged puzzle and report any that no longer solve it
+    #[arg(long)]
+    verify: bool,
+
+    /// Draw the board after every move of each solution
+    #[arg(long)]
+    render: bool,
+}
+
+#[derive(Args)]
+struct CompletionsArgs {
+    /// Shell to generate completions for: bash, zsh, fish, powershell, or elvish
+    shell: Shell,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SolutionFile {
+    Moves(Vec<Color>),
+    Document { moves: Vec<Color> },
+}
+
+/// Process exit codes, so scripts can tell the outcomes apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    /// The puzzle was solved, or the command completed.
+    Success = 0,
+    /// There is no solution within the move bound (or the moves given do not solve the puzzle).
+    NoSolution = 1,
+    /// The puzzle, or another input file, could not be read or parsed.
+    InvalidPuzzle = 2,
+    /// The search stopped because it hit a timeout or another limit.
+    LimitReached = 3,
+    /// The command line arguments were invalid.
+    Usage = 4,
+    /// An output file could not be written.
+    OutputError = 5,
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
+impl Status {
+    // When a batch has several outcomes, invalid input is the most important to report and
+    // success the least.
+    fn severity(self) -> u8 {
+        match self {
+            Status::Success => 0,
+            Status::NoSolution => 1,
+            Status::LimitReached => 2,
+            Status::InvalidPuzzle => 3,
+            Status::OutputError => 4,
+            Status::Usage => 5,
+        }
+    }
+
+    fn worst(self, other: Status) -> Status {
+        if other.severity() > self.severity() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+// Flags given on the command line win over the config file, which wins over clap's defaults.
+fn configure<T>(matches: &ArgMatches, id: &str, value: Option<T>, target: &mut T) {
+    if let Some(value) = value {
+        if matches.value_source(id) != Some(ValueSource::CommandLine) {
+            *target = value;
+        }
+    }
+}
+
+fn from_command_line(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+impl SolveArgs {
+    fn configure(&mut self, config: &Config, matches: &ArgMatches) {
+        configure(matches, "max_moves", config.max_moves, &mut self.max_moves);
+        configure(
+            matches,
+            "timeout",
+            config.timeout.map(Some),
+            &mut self.timeout,
+        );
+        configure(
+            matches,
+            "tie_break",
+            config.tie_break.map(Some),
+            &mut self.tie_break,
+        );
+
+        // --optimal cannot be combined with these, so a configured algorithm gives way to them.
+        let overridden = [
+            "optimal",
+            "max_nodes",
+            "ida",
+            "portfolio",
+            "anytime",
+            "all_optimal",
+            "timeout",
+            "raise_bound",
+            "tie_break",
+            "trace_search",
+            "dot",
+            "checkpoint",
+        ]
+        .iter()
+        .any(|id| from_command_line(matches, id));
+        if config.algorithm == Some(Algorithm::Optimal) && !overridden {
+            self.optimal = true;
+            self.timeout = None;
+            self.tie_break = None;
+        }
+
+        if !self.all_optimal {
+            match config.format {
+                Some(Format::Detailed) => self.detailed = true,
+                Some(Format::Notation) => self.notation = true,
+                Some(Format::Diff) => self.diff = true,
+                Some(Format::Plain) | None => {}
+            }
+        }
+    }
+}
+
+impl Command {
+    fn configure(&mut self, config: &Config, matches: &ArgMatches) {
+        match self {
+            Command::Solve(args) => args.configure(config, matches),
+            Command::Rate(RateArgs { max_moves, .. })
+            | Command::Minimize(MinimizeArgs { max_moves, .. })
+            | Command::SolveAll(SolveAllArgs { max_moves, .. })
+            | Command::Bench(BenchArgs { max_moves, .. })
+            | Command::Play(PlayArgs { max_moves, .. })
+            | Command::Edit(EditArgs { max_moves, .. })
+            | Command::Repl(ReplArgs { max_moves, .. })
+            | Command::Diagnostics(DiagnosticsArgs { max_moves, .. }) => {
+                configure(matches, "max_moves", config.max_moves, max_moves)
+            }
+            Command::Fifteen(args) => {
+                configure(
+                    matches,
+                    "timeout",
+                    config.timeout.map(Some),
+                    &mut args.timeout,
+                );
+                configure(matches, "heuristic", config.heuristic, &mut args.heuristic);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn usage_error(kind: ErrorKind, message: &str) -> Status {
+    let _ = Cli::command().error(kind, message).print();
+    Status::Usage
+}
+
+fn read_game(path: &Path) -> Result<Game, String> {
+    let _span = info_span!("parse", path = %path.display()).entered();
+    let parsed = if path == Path::new("-") {
+        let text = io::read_to_string(io::stdin().lock())
+            .map_err(|error| format!("could not open file: {}", error))?;
+        parse_game(&text)
+    } else {
+        parse::load_game(path)
+    };
+
+    let game = parsed.map_err(|error| format!("could not parse input file: {}", error))?;
+    debug!(
+        blocks = game.colors().len(),
+        arrows = game.arrows().len(),
+        "parsed puzzle"
+    );
+
+    Ok(game)
+}
+
+fn load_game(path: &Path) -> Option<Game> {
+    read_game(path)
+        .inspect_err(|error| eprintln!("{}: {}", path.display(), error))
+        .ok()
+}
+
+fn write_output(output: Option<PathBuf>, contents: &str) -> Status {
+    match output {
+        Some(output) => match fs::write(&output, contents) {
+            Ok(()) => Status::Success,
+            Err(error) => {
+                eprintln!("could not write {}: {}", output.display(), error);
+                Status::OutputError
+            }
+        },
+        None => {
+            print!("{}", contents);
+            Status::Success
+        }
+    }
+}
+
+fn solve(mut args: SolveArgs) -> Status {
+    if args.paths.is_empty() && !io::stdin().is_terminal() {
+        args.paths.push(PathBuf::from("-"));
+    }
+
+    let log = match &args.log_dir {
+        Some(dir) => match session::SessionLog::create(dir) {
+            Ok(log) => Some(log),
+            Err(error) => {
+                eprintln!(
+                    "could not open a session log in {}: {}",
+                    dir.display(),
+                    error
+                );
+                return Status::OutputError;
+            }
+        },
+        None => None,
+    };
+    let log = log.as_ref();
+
+    match args.paths.as_slice() {
+        [] => usage_error(
+            ErrorKind::MissingRequiredArgument,
+            "a puzzle file is required",
+        ),
+        [path] if args.watch && path == Path::new("-") => usage_error(
+            ErrorKind::ArgumentConflict,
+            "--watch needs a puzzle file rather than stdin",
+        ),
+        [path] if args.watch => watch(path, &args, log),
+        // A CSV summary is for a batch, even a batch of one.
+        [path] if args.csv.is_none() => match load_game(path).map(|game| start_from(game, &args)) {
+            Some(Ok(game)) => solve_one(path, &game, &args, log),
+            Some(Err(error)) => {
+                eprintln!("--from: {}", error);
+                Status::InvalidPuzzle
+            }
+            None => Status::InvalidPuzzle,
+        },
+        _ if args.all_optimal
+            || args.hint.is_some()
+            || args.from.is_some()
+            || args.watch
+            || args.trace_search.is_some()
+            || args.dot.is_some()
+            || args.checkpoint.is_some()
+            || args.solution_out.is_some()
+            || args.replay_out.is_some()
+            || args.html.is_some()
+            || args.anytime.is_some()
+            || args.portfolio
+            || args.live_stats
+            || args.cache.is_some() =>
+        {
+            usage_error(
+                ErrorKind::ArgumentConflict,
+                "--all-optimal, --hint, --from, --watch, --trace-search, --dot, --checkpoint, --solution-out, --replay-out, --html, --anytime, --portfolio, --live-stats, and --cache only work with a single puzzle",
+            )
+        }
+        _ => solve_batch(&args, log),
+    }
+}
+
+// Moves the blocks that --from places, given inline or as the path of a file holding them.
+fn start_from(mut game: Game, args: &SolveArgs) -> Result<Game, String> {
+    let Some(from) = &args.from else {
+        return Ok(game);
+    };
+    let text = match Path::new(from).is_file() {
+        true => fs::read_to_string(from)
+            .map_err(|error| format!("could not read {}: {}", from, error))?,
+        false => from.clone(),
+    };
+    notation::place_blocks(&mut game, &text)?;
+    Ok(game)
+}
+
+fn solve_one(
+    path: &Path,
+    game: &Game,
+    args: &SolveArgs,
+    log: Option<&session::SessionLog>,
+) -> Status {
+    if args.all_optimal {
+        let solutions = game.solve_all_optimal(args.max_moves);
+
+        let Some(first) = solutions.first() else {
+            println!("No solution found");
+            return Status::NoSolution;
+        };
+        println!(
+            "Found {} optimal solutions with {} moves",
+            solutions.len(),
+            first.len()
+        );
+        for moves in solutions {
+            println!("Moves: {:?}", moves);
+        }
+        return Status::Success;
+    }
+
+    let start = Instant::now();
+    let timeout = args.timeout;
+    let mut expanded = vec![];
+    let (moves, stats) = match &args.trace_search {
+        _ if args.checkpoint.is_some() => match solve_checkpointed(game, args, timeout) {
+            Ok(result) => result,
+            Err(status) => return status,
+        },
+        _ if args.cache.is_some() => match solve_cached(game, args, timeout) {
+            Ok(result) => result,
+            Err(status) => return status,
+        },
+        Some(path) => {
+            let keep = args.dot.is_some();
+            let solved = solve_traced(game, args.max_moves, timeout, path, |record| {
+                if keep {
+                    expanded.push(record);
+                }
+            });
+            match solved {
+                Ok(result) => result,
+                Err(error) => {
+                    eprintln!("could not write {}: {}", path.display(), error);
+                    return Status::OutputError;
+                }
+            }
+        }
+        None if args.dot.is_some() => {
+            game.solve_observed(args.max_moves, timeout, |record| expanded.push(record))
+        }
+        None if args.live_stats => {
+            let (sender, receiver) = mpsc::channel();
+            let printer = thread::spawn(move || print_live_stats(receiver));
+            let result = game.solve_with(&solver_options(args, timeout).progress(sender));
+            let _ = printer.join();
+            result
+        }
+        None => solve_plain(game, args, timeout),
+    };
+
+    if let Some(path) = &args.dot {
+        let solution = moves
+            .as_ref()
+            .map(|moves| game.solution_path(moves))
+            .unwrap_or_default();
+        let graph = dot::search_tree(&expanded, &solution, args.dot_depth);
+        let status = write_output(Some(path.clone()), &graph);
+        if status != Status::Success {
+            return status;
+        }
+    }
+
+    if args.stats {
+        print_stats(game, &stats, moves.as_ref().map(Vec::len));
+    }
+
+    let outcome = game.outcome(moves, stats.clone());
+    let solution = match &outcome {
+        SolveOutcome::Solved(solution) => Some(match args.optimize {
+            Some(window) => game.optimize_solution(&solution.colors(), window),
+            None => solution.colors(),
+        }),
+        _ => None,
+    };
+    if let Some(log) = log {
+        let record = session::Record::new(
+            path,
+            game,
+            logged_options(args),
+            &outcome,
+            solution.as_deref(),
+            &stats,
+            start.elapsed(),
+        );
+        if let Err(error) = log.append(&record) {
+            eprintln!("could not write the session log: {}", error);
+            return Status::OutputError;
+        }
+    }
+
+    let moves = match outcome {
+        SolveOutcome::Solved(_) => solution.unwrap_or_default(),
+        SolveOutcome::Aborted(AbortReason::TimedOut) => {
+            println!("Timed out before finding a solution");
+            return Status::LimitReached;
+        }
+        SolveOutcome::Aborted(AbortReason::LimitReached) => {
+            println!("Ran out of memory budget before finding a solution; raise --max-nodes");
+            return Status::LimitReached;
+        }
+        SolveOutcome::Aborted(AbortReason::Cancelled) => {
+            println!("Cancelled before finding a solution");
+            return Status::LimitReached;
+        }
+        SolveOutcome::NoSolutionWithinBound => {
+            println!("No solution within {} moves", args.max_moves);
+            return Status::NoSolution;
+        }
+        SolveOutcome::ProvenUnsolvable => {
+            println!("No solution exists: every reachable position was searched");
+            return Status::NoSolution;
+        }
+    };
+
+    if let Some(path) = &args.solution_out {
+        let yaml = game
+            .record_solution(&moves)
+            .and_then(|record| serde_yaml::to_string(&record).ok())
+            .unwrap_or_default();
+        let status = write_output(Some(path.clone()), &yaml);
+        if status != Status::Success {
+            return status;
+        }
+    }
+
+    if let Some(path) = &args.replay_out {
+        let json = import::export_replay(game, &moves).unwrap_or_default();
+        let status = write_output(Some(path.clone()), &format!("{}\n", json));
+        if status != Status::Success {
+            return status;
+        }
+    }
+
+    if let Some(output) = &args.html {
+        let title = format!("{} in {} moves", path.display(), moves.len());
+        let page = render::html(game, &moves, &title).unwrap_or_default();
+        let status = write_output(Some(output.clone()), &page);
+        if status != Status::Success {
+            return status;
+        }
+    }
+
+    if let Some(k) = args.hint {
+        let revealed = &moves[..k.min(moves.len())];
+        println!(
+            "Solution found; the first {} of {} moves are:",
+            revealed.len(),
+            moves.len()
+        );
+        println!("Moves: {:?}", revealed);
+        print_details(game, revealed, args);
+        if args.show_board {
+            print!("{}", game.render_after(revealed));
+        }
+    } else {
         println!("Solution found with {} moves", moves.len());
         println!("Moves: {:?}", moves);
+        print_goals_reached(game, &moves);
+        if args.optimal {
+            let pushes: usize = game
+                .describe_moves(&moves)
+                .unwrap_or_default()
+                .iter()
+                .map(|step| step.pushed.len())
+                .sum();
+            println!("Pushes: {}", pushes);
+        }
+        print_details(game, &moves, args);
+    }
+
+    Status::Success
+}
+
+// Which goal each block finished on, when some block had more than one to choose from.
+fn print_goals_reached(game: &Game, moves: &[Color]) {
+    if game
+        .colors()
+        .iter()
+        .all(|color| game.goals_of(color).len() < 2)
+    {
+        return;
+    }
+    let Some(state) = game.apply_moves(&game.initial_state(), moves) else {
+        return;
+    };
+    let reached: Vec<String> = game
+        .goals_reached(&state)
+        .into_iter()
+        .map(|(color, goal)| format!("{} at ({}, {})", color, goal[0], goal[1]))
+        .collect();
+    println!("Goals reached: {}", reached.join(", "));
+}
+
+fn print_stats(game: &Game, stats: &SearchStats, length: Option<usize>) {
+    println!("Expanded: {}", stats.expanded);
+    println!("Generated: {}", stats.generated);
+    println!(
+        "Open set: {} at most, {} at the end",
+        stats.peak_open, stats.open
+    );
+    println!("Closed set: {}", stats.seen);
+    println!(
+        "Cut off: {} at the depth limit, {} at the cost limit",
+        stats.depth_cut_off, stats.cost_cut_off
+    );
+    println!(
+        "Duplicates: {} ({:.1}% of generated)",
+        stats.duplicates,
+        stats.duplicate_rate() * 100.0
+    );
+    let estimate = game.heuristic(&game.initial_state());
+    match length {
+        Some(length) => {
+            println!(
+                "Effective branching factor: {:.3}",
+                stats.branching_factor(length)
+            );
+            println!(
+                "Heuristic at the start: {} for a solution of {} moves ({:.2})",
+                estimate,
+                length,
+                estimate as f64 / length.max(1) as f64
+            );
+        }
+        None => println!("Heuristic at the start: {}", estimate),
+    }
+}
+
+fn print_details(game: &Game, moves: &[Color], args: &SolveArgs) {
+    if args.notation {
+        println!(
+            "Notation: {}",
+            notation::to_notation(game, moves).unwrap_or_default()
+        );
+    }
+    if args.detailed {
+        let steps = game.describe_moves(moves).unwrap_or_default();
+        for (i, step) in steps.iter().enumerate() {
+            println!("{:>3}. {}", i + 1, game.display_move(step));
+        }
+    }
+    if args.explain {
+        let explained = game.explain_moves(moves).unwrap_or_default();
+        for (i, explanation) in explained.iter().enumerate() {
+            println!("{:>3}. {}", i + 1, game.display_explanation(explanation));
+        }
+    }
+    if args.diff {
+        let diffs = game.diff_moves(moves).unwrap_or_default();
+        for (i, (color, changes)) in moves.iter().zip(&diffs).enumerate() {
+            println!("{:>3}. {}", i + 1, color);
+            for change in changes {
+                println!("     {}", change);
+            }
+        }
+    }
+}
+
+fn solve_checkpointed(
+    game: &Game,
+    args: &SolveArgs,
+    timeout: Option<Duration>,
+) -> Result<SolveResult, Status> {
+    let Some(path) = &args.checkpoint else {
+        return Ok(game.solve_with_timeout(args.max_moves, timeout));
+    };
+
+    let resume = if args.resume {
+        let checkpoint = File::open(path)
+            .map_err(|error| error.to_string())
+            .and_then(|file| {
+                serde_json::from_reader(io::BufReader::new(file)).map_err(|error| error.to_string())
+            });
+        match checkpoint {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(error) => {
+                eprintln!("could not read checkpoint {}: {}", path.display(), error);
+                return Err(Status::InvalidPuzzle);
+            }
+        }
+    } else {
+        None
+    };
+
+    let save = |checkpoint: &Checkpoint| {
+        if let Err(error) = write_json(path, checkpoint) {
+            eprintln!("could not write checkpoint {}: {}", path.display(), error);
+        }
+    };
+    game.solve_checkpointed(
+        args.max_moves,
+        timeout,
+        resume,
+        args.checkpoint_interval,
+        save,
+    )
+    .map_err(|error| {
+        eprintln!("could not resume from {}: {}", path.display(), error);
+        Status::InvalidPuzzle
+    })
+}
+
+// Writes to a temporary file first so a crash mid-write leaves the previous contents intact.
+fn write_json(path: &Path, value: &impl Serialize) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let mut writer = BufWriter::new(File::create(&temporary)?);
+    serde_json::to_writer(&mut writer, value)?;
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&temporary, path)
+}
+
+fn parse_timeout(text: &str) -> Result<Duration, String> {
+    let seconds = text
+        .parse::<f64>()
+        .map_err(|_| "timeout must be a positive number of seconds".to_string())?;
+    config::seconds_to_duration(seconds)
+}
+
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let (number, unit) = match text.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(index) => text.split_at(index),
+        None => (text, "s"),
+    };
+    let scale = match unit {
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        _ => return Err(format!("unknown unit `{}`; use s, m, or h", unit)),
+    };
+    match number
+        .parse::<f64>()
+        .map(|value| Duration::try_from_secs_f64(value * scale))
+    {
+        Ok(Ok(duration)) if !duration.is_zero() => Ok(duration),
+        _ => Err(format!("`{}` is not a positive duration", text)),
+    }
+}
+
+fn solve_traced(
+    game: &Game,
+    max_moves: i32,
+    timeout: Option<Duration>,
+    path: &Path,
+    mut on_expand: impl FnMut(ExpansionRecord),
+) -> io::Result<SolveResult> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let mut result = Ok(());
+    let solved = game.solve_observed(max_moves, timeout, |record| {
+        if result.is_ok() {
+            result = serde_json::to_writer(&mut writer, &record)
+                .map_err(io::Error::from)
+                .and_then(|()| writeln!(writer));
+        }
+        on_expand(record);
+    });
+    result?;
+    writer.flush()?;
+    Ok(solved)
+}
+
+fn solver_options(args: &SolveArgs, timeout: Option<Duration>) -> SolverOptions {
+    let mut options =
+        SolverOptions::new(args.max_moves).tie_break(args.tie_break.unwrap_or_default());
+    options.timeout = timeout;
+    if args.optimal {
+        options = options.algorithm(solver::Algorithm::BreadthFirst);
+    }
+    if let Some(weight) = args.anytime {
+        options = options.algorithm(solver::Algorithm::Anytime).weight(weight);
+    }
+    if let Some(max_nodes) = args.max_nodes {
+        options = options
+            .algorithm(solver::Algorithm::SmaStar)
+            .max_nodes(max_nodes);
+    }
+    if args.ida {
+        options = options.algorithm(solver::Algorithm::IdaStar);
+    }
+    if let Some(table_size) = args.table_size {
+        options = options.table_size(table_size);
+    }
+    if let Some(open_list) = args.open_list {
+        options = options.open_list(open_list);
+    }
+    options
+        .compact_paths(args.compact_paths)
+        .threads(rayon::current_num_threads())
+}
+
+// The options a session log records, with the algorithm named as `bench` names it.
+fn logged_options(args: &SolveArgs) -> session::Options {
+    let algorithm = match solver_options(args, None).algorithm {
+        _ if args.portfolio => "portfolio",
+        solver::Algorithm::AStar => "astar",
+        solver::Algorithm::BreadthFirst => "breadth-first",
+        solver::Algorithm::SmaStar => "sma",
+        solver::Algorithm::Anytime => "anytime",
+        solver::Algorithm::IdaStar => "ida",
+    };
+    session::Options {
+        algorithm: algorithm.to_string(),
+        max_moves: args.max_moves,
+        timeout: args.timeout.map(|timeout| timeout.as_secs_f64()),
+        raise_bound: args.raise_bound,
+        optimize: args.optimize,
+    }
+}
+
+fn solve_plain(game: &Game, args: &SolveArgs, timeout: Option<Duration>) -> SolveResult {
+    if let Some(limit) = args.raise_bound {
+        let (moves, stats, _) = game.solve_raising(raised_bounds(args.max_moves, limit), timeout);
+        return (moves, stats);
+    }
+
+    let options = solver_options(args, timeout);
+    if args.portfolio {
+        let (names, entries): (Vec<_>, Vec<_>) = solver::portfolio(&options).into_iter().unzip();
+        let (moves, stats, winner) = game.solve_portfolio(&entries);
+        if let Some(winner) = winner {
+            println!("Solved first by {}", names[winner]);
+        }
+        return (moves, stats);
+    }
+    if options.algorithm == solver::Algorithm::Anytime {
+        let start = Instant::now();
+        return game.solve_anytime(&options, |moves, stats| {
+            println!(
+                "Found a solution with {} moves after {:.2?} ({} nodes expanded)",
+                moves.len(),
+                start.elapsed(),
+                stats.expanded
+            );
+        });
+    }
+    game.solve_with(&options)
+}
+
+// Prints each report as it arrives until the search drops its sender, with the rate measured
+// since the report before.
+fn print_live_stats(receiver: mpsc::Receiver<Progress>) {
+    let mut last = (Duration::ZERO, 0);
+    for progress in receiver {
+        let seconds = (progress.elapsed - last.0).as_secs_f64();
+        let rate = (progress.expanded - last.1) as f64 / seconds.max(f64::EPSILON);
+        eprintln!(
+            "{:>6.1}s  {:>9.0} nodes/s  {} expanded  depth {}  {} open  ~{:.1} MB",
+            progress.elapsed.as_secs_f64(),
+            rate,
+            progress.expanded,
+            progress.depth,
+            progress.open,
+            progress.memory as f64 / 1_000_000.0
+        );
+        last = (progress.elapsed, progress.expanded);
+    }
+}
+
+fn solve_cached(
+    game: &Game,
+    args: &SolveArgs,
+    timeout: Option<Duration>,
+) -> Result<SolveResult, Status> {
+    let Some(path) = &args.cache else {
+        return Ok(solve_plain(game, args, timeout));
+    };
+
+    let mut cache = match File::open(path) {
+        Ok(file) => match serde_json::from_reader(io::BufReader::new(file)) {
+            Ok(cache) => cache,
+            Err(error) => {
+                eprintln!("could not read cache {}: {}", path.display(), error);
+                return Err(Status::InvalidPuzzle);
+            }
+        },
+        Err(error) if error.kind() == io::ErrorKind::NotFound => DeadEndCache::new(),
+        Err(error) => {
+            eprintln!("could not read cache {}: {}", path.display(), error);
+            return Err(Status::InvalidPuzzle);
+        }
+    };
+
+    let known = cache.len();
+    let result = game.solve_cached(&solver_options(args, timeout), &mut cache);
+    debug!(known, now = cache.len(), "dead-end cache");
+    if cache.len() != known {
+        if let Err(error) = write_json(path, &cache) {
+            eprintln!("could not write cache {}: {}", path.display(), error);
+            return Err(Status::OutputError);
+        }
+    }
+    Ok(result)
+}
+
+// The bounds tried by --raise-bound: the starting bound, doubled until it reaches the limit.
+fn raised_bounds(start: i32, limit: i32) -> Vec<i32> {
+    let mut bounds = vec![start];
+    let mut bound = start;
+    while bound < limit {
+        bound = bound.saturating_mul(2).max(bound + 1).min(limit);
+        bounds.push(bound);
+    }
+    bounds
+}
+
+fn watch(path: &Path, args: &SolveArgs, log: Option<&session::SessionLog>) -> Status {
+    let solve_latest = || match read_game(path)
+        .and_then(|game| start_from(game, args).map_err(|error| format!("--from: {}", error)))
+    {
+        Ok(game) => {
+            solve_one(path, &game, args, log);
+        }
+        Err(error) => println!("{}", error),
+    };
+    solve_latest();
+
+    // Editors often save by replacing the file, so watch the directory rather than the file.
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = path.file_name();
+    let (sender, receiver) = mpsc::channel();
+    let watching = notify::recommended_watcher(sender).and_then(|mut watcher| {
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        Ok(watcher)
+    });
+    let _watcher = match watching {
+        Ok(watcher) => watcher,
+        Err(error) => {
+            eprintln!("could not watch {}: {}", path.display(), error);
+            return Status::InvalidPuzzle;
+        }
+    };
+
+    while let Ok(event) = receiver.recv() {
+        let touches_puzzle = event.is_ok_and(|event| {
+            !event.kind.is_access()
+                && event
+                    .paths
+                    .iter()
+                    .any(|changed| changed.file_name() == file_name)
+        });
+        if !touches_puzzle {
+            continue;
+        }
+
+        // A single save usually produces a burst of events.
+        while receiver.recv_timeout(Duration::from_millis(100)).is_ok() {}
+
+        println!();
+        println!("{} changed", path.display());
+        solve_latest();
+    }
+
+    Status::Success
+}
+
+type SolveResult = (Option<Vec<Color>>, SearchStats);
+
+fn solve_batch(args: &SolveArgs, log: Option<&session::SessionLog>) -> Status {
+    let timeout = args.timeout;
+    let logged = Mutex::new(Ok(()));
+    let results: Vec<Result<(SolveResult, Duration), String>> = args
+        .paths
+        .par_iter()
+        .map(|path| {
+            let game = read_game(path)?;
+            let start = Instant::now();
+            let (moves, stats) = solve_plain(&game, args, timeout);
+            let moves = match (moves, args.optimize) {
+                (Some(moves), Some(window)) => Some(game.optimize_solution(&moves, window)),
+                (moves, _) => moves,
+            };
+            if let Some(log) = log {
+                let outcome = game.outcome(moves.clone(), stats.clone());
+                let record = session::Record::new(
+                    path,
+                    &game,
+                    logged_options(args),
+                    &outcome,
+                    moves.as_deref(),
+                    &stats,
+                    start.elapsed(),
+                );
+                if let Err(error) = log.append(&record) {
+                    *logged.lock().unwrap() = Err(error);
+                }
+            }
+            Ok(((moves, stats), start.elapsed()))
+        })
+        .collect();
+
+    let mut status = Status::Success;
+    if let Err(error) = logged.into_inner().unwrap() {
+        eprintln!("could not write the session log: {}", error);
+        status = Status::OutputError;
+    }
+    if let Some(csv) = &args.csv {
+        let rows: Vec<summary::Row> = args
+            .paths
+            .iter()
+            .zip(&results)
+            .map(|(path, result)| {
+                let puzzle = path.display().to_string();
+                match result {
+                    Ok(((moves, stats), elapsed)) => {
+                        summary::Row::new(puzzle, moves.as_deref(), stats, *elapsed)
+                    }
+                    Err(error) => summary::Row::invalid(puzzle, error.clone()),
+                }
+            })
+            .collect();
+        status = status.worst(write_output(Some(csv.clone()), &summary::csv(&rows)));
+    }
+    let (mut solved, mut timed_out, mut failed) = (0, 0, 0);
+    for (path, result) in args.paths.iter().zip(results) {
+        match result.map(|(result, _)| result) {
+            Ok((Some(moves), _)) => {
+                solved += 1;
+                println!(
+                    "{}: solved with {} moves: {:?}",
+                    path.display(),
+                    moves.len(),
+                    moves
+                );
+            }
+            Ok((None, stats)) if stats.timed_out => {
+                timed_out += 1;
+                status = status.worst(Status::LimitReached);
+                println!("{}: timed out", path.display());
+            }
+            Ok((None, stats)) if stats.limit_reached => {
+                status = status.worst(Status::LimitReached);
+                println!("{}: ran out of memory budget", path.display());
+            }
+            Ok((None, stats)) if stats.exhausted => {
+                status = status.worst(Status::NoSolution);
+                println!("{}: no solution exists", path.display());
+            }
+            Ok((None, _)) => {
+                status = status.worst(Status::NoSolution);
+                println!(
+                    "{}: no solution within {} moves",
+                    path.display(),
+                    args.max_moves
+                );
+            }
+            Err(error) => {
+                failed += 1;
+                status = status.worst(Status::InvalidPuzzle);
+                println!("{}: {}", path.display(), error);
+            }
+        }
+    }
+
+    println!(
+        "Solved {} of {} puzzles ({} timed out, {} could not be read)",
+        solved,
+        args.paths.len(),
+        timed_out,
+        failed
+    );
+
+    status
+}
+
+fn count(args: CountArgs) -> Status {
+    let Some(game) = load_game(&args.path) else {
+        return Status::InvalidPuzzle;
+    };
+    let total = game.count_solutions(args.max_moves);
+
+    println!(
+        "Found {} solutions with at most {} moves",
+        total, args.max_moves
+    );
+
+    if total == 0 {
+        Status::NoSolution
     } else {
+        Status::Success
+    }
+}
+
+fn read_solution(path: &Path, game: &Game) -> Result<Vec<Color>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|error| format!("could not open solution file: {}", error))?;
+    match serde_yaml::from_str(&text) {
+        Ok(SolutionFile::Moves(moves) | SolutionFile::Document { moves }) => Ok(moves),
+        Err(_) if text.contains("->") => notation::parse_notation(game, &text)
+            .map_err(|error| format!("could not parse solution notation: {}", error)),
+        Err(error) => Err(format!("could not parse solution file: {}", error)),
+    }
+}
+
+fn verify(args: VerifyArgs) -> Status {
+    let Some(game) = load_game(&args.path) else {
+        return Status::InvalidPuzzle;
+    };
+    let moves = match read_solution(&args.solution, &game) {
+        Ok(moves) => moves,
+        Err(error) => {
+            eprintln!("{}: {}", args.solution.display(), error);
+            return Status::InvalidPuzzle;
+        }
+    };
+    let verification = game.verify(&moves);
+
+    let (applied, solved) = match game.verify_solution(&moves) {
+        Ok(_) => {
+            if verification.solved {
+                println!("The {} moves solve the puzzle", moves.len());
+            } else {
+                println!("The {} moves do not solve the puzzle", moves.len());
+            }
+            (moves.len(), verification.solved)
+        }
+        Err(invalid) => {
+            println!("{}", invalid);
+            (invalid.index, false)
+        }
+    };
+
+    if let Some(after) = verification.first_solved_after {
+        if after < applied {
+            println!("The puzzle was already solved after move {}", after);
+        }
+    }
+
+    println!("Board after {} moves:", applied);
+    print!("{}", game.render_after(&moves[..applied]));
+
+    if solved {
+        Status::Success
+    } else {
+        Status::NoSolution
+    }
+}
+
+fn normalize(args: NormalizeArgs) -> Status {
+    let Some(game) = load_game(&args.path) else {
+        return Status::InvalidPuzzle;
+    };
+
+    write_output(args.output, &game.normalized().to_yaml())
+}
+
+fn generate(args: GenerateArgs) -> Status {
+    if args
+        .exact_moves
+        .is_some_and(|exact_moves| exact_moves as i64 > i64::from(args.max_moves))
+    {
+        return usage_error(
+            ErrorKind::ValueValidation,
+            "--exact-moves cannot be more than --max-moves, which bounds the solution length",
+        );
+    }
+
+    let options = GeneratorOptions {
+        blocks: args.blocks as usize,
+        arrows: args.arrows,
+        size: args.size,
+        min_moves: args.min_moves,
+        max_moves: args.max_moves,
+        exact_moves: args.exact_moves,
+        attempts: args.attempts,
+    };
+    if let Err(error) = options.validate() {
+        return usage_error(ErrorKind::ValueValidation, &error);
+    }
+    if let Some(dir) = args.output_dir {
+        return generate_into(&options, args.count.unwrap_or(1) as usize, args.seed, &dir);
+    }
+
+    let Some((game, moves)) = generator::generate(&options, &mut Rng::new(args.seed)) else {
+        eprintln!("No puzzle found in {} attempts", args.attempts);
+        return Status::NoSolution;
+    };
+    eprintln!(
+        "Generated a puzzle with an optimal solution of {} moves",
+        moves.len()
+    );
+
+    write_output(args.output, &game.to_yaml())
+}
+
+fn generate_into(options: &GeneratorOptions, count: usize, seed: u64, dir: &Path) -> Status {
+    if let Err(error) = fs::create_dir_all(dir) {
+        eprintln!("could not create {}: {}", dir.display(), error);
+        return Status::OutputError;
+    }
+
+    let width = count.to_string().len().max(3);
+    let mut written = 0;
+    let mut status = Status::Success;
+    let threads = rayon::current_num_threads();
+    let stats = generator::generate_many(options, count, threads, seed, |game, moves| {
+        written += 1;
+        let path = dir.join(format!("puzzle_{:0width$}.yaml", written));
+        if let Err(error) = fs::write(&path, game.to_yaml()) {
+            eprintln!("could not write {}: {}", path.display(), error);
+            status = Status::OutputError;
+            return false;
+        }
+        eprintln!("{}: {} moves", path.display(), moves.len());
+        true
+    });
+
+    eprintln!(
+        "Generated {} of {} puzzles from {} candidates, skipping {} duplicates",
+        stats.accepted, count, stats.tried, stats.duplicates
+    );
+    if status == Status::Success && stats.accepted < count {
+        return Status::NoSolution;
+    }
+    status
+}
+
+fn scramble(args: ScrambleArgs) -> Status {
+    let Some(solved) = load_game(&args.from) else {
+        return Status::InvalidPuzzle;
+    };
+
+    let (game, moves) = match generator::scramble(&solved, args.depth, &mut Rng::new(args.seed)) {
+        Ok(scrambled) => scrambled,
+        Err(error) => {
+            eprintln!("{}: {}", args.from.display(), error);
+            return Status::InvalidPuzzle;
+        }
+    };
+    if moves.len() < args.depth {
+        eprintln!(
+            "Every way back was used up after {} of {} moves",
+            moves.len(),
+            args.depth
+        );
+    }
+    eprintln!(
+        "Scrambled a puzzle that is solvable in at most {} moves: {:?}",
+        moves.len(),
+        moves
+    );
+
+    write_output(args.output, &game.to_yaml())
+}
+
+fn rate(args: RateArgs) -> Status {
+    let Some(game) = load_game(&args.path) else {
+        return Status::InvalidPuzzle;
+    };
+
+    let Some(rating) = rating::rate(&game, args.max_moves) else {
         println!("No solution found");
+        return Status::NoSolution;
+    };
+
+    println!("Optimal length:     {}", rating.optimal_length);
+    println!("Nodes expanded:     {}", rating.expanded);
+    println!("Branching factor:   {:.2}", rating.branching_factor);
+    println!("Deadlock traps:     {}", rating.traps);
+    println!("Optimal solutions:  {}", rating.optimal_solutions);
+    println!("Difficulty:         {:.1}", rating.score);
+
+    Status::Success
+}
+
+fn solve_fifteen(args: FifteenArgs) -> Status {
+    let text = if args.path == Path::new("-") {
+        io::read_to_string(io::stdin().lock())
+    } else {
+        fs::read_to_string(&args.path)
+    };
+    let parsed = text
+        .map_err(|error| format!("could not open file: {}", error))
+        .and_then(|text| fifteen::Fifteen::parse(&text));
+    let mut board = match parsed {
+        Ok(board) => board,
+        Err(error) => {
+            eprintln!("{}: {}", args.path.display(), error);
+            return Status::InvalidPuzzle;
+        }
+    };
+    if !board.is_solvable() {
+        println!("No solution exists for this arrangement");
+        return Status::NoSolution;
     }
+
+    board.heuristic = args.heuristic;
+    let deadline = solver::deadline(args.timeout);
+    let (moves, stats) = puzzle::solve(&board, args.max_moves, deadline);
+    let Some(moves) = moves else {
+        if stats.timed_out {
+            println!("Timed out before finding a solution");
+            return Status::LimitReached;
+        }
+        println!("No solution found");
+        return Status::NoSolution;
+    };
+
+    let moves: Vec<String> = moves.iter().map(ToString::to_string).collect();
+    println!("Solution found with {} moves", moves.len());
+    println!("Blank moves: {}", moves.join(" "));
+    println!("Nodes expanded: {}", stats.expanded);
+    Status::Success
+}
+
+fn import(args: ImportArgs) -> Status {
+    if args
+        .path
+        .extension()
+        .is_some_and(|extension| extension == "png")
+    {
+        return import_screenshot(args);
+    }
+    let text = if args.path == Path::new("-") {
+        io::read_to_string(io::stdin().lock())
+    } else {
+        fs::read_to_string(&args.path)
+    };
+    let levels = text
+        .map_err(|error| format!("could not open file: {}", error))
+        .and_then(|text| import::import_original(&text));
+    let levels = match levels {
+        Ok(levels) => levels,
+        Err(error) => {
+            eprintln!("{}: {}", args.path.display(), error);
+            return Status::InvalidPuzzle;
+        }
+    };
+
+    let yaml = match levels.as_slice() {
+        [(_, game)] => game.to_yaml(),
+        _ => import::to_pack_yaml(&levels),
+    };
+    write_output(args.output, &yaml)
+}
+
+#[cfg(feature = "screenshot")]
+fn import_screenshot(args: ImportArgs) -> Status {
+    let options = solver_of_squares::screenshot::ScreenshotOptions {
+        cell_size: args.cell_size,
+        ..Default::default()
+    };
+    let game = fs::read(&args.path)
+        .map_err(|error| format!("could not open file: {}", error))
+        .and_then(|bytes| solver_of_squares::screenshot::import_screenshot(&bytes, &options));
+    match game {
+        Ok(game) => write_output(args.output, &game.to_yaml()),
+        Err(error) => {
+            eprintln!("{}: {}", args.path.display(), error);
+            Status::InvalidPuzzle
+        }
+    }
+}
+
+#[cfg(not(feature = "screenshot"))]
+fn import_screenshot(args: ImportArgs) -> Status {
+    eprintln!(
+        "{}: reading screenshots needs the `screenshot` feature",
+        args.path.display()
+    );
+    Status::InvalidPuzzle
+}
+
+fn diff(args: DiffArgs) -> Status {
+    let (Some(first), Some(second)) = (load_game(&args.first), load_game(&args.second)) else {
+        return Status::InvalidPuzzle;
+    };
+
+    let Some(equivalence) = first.equivalence(&second) else {
+        println!("Not equivalent");
+        return Status::NoSolution;
+    };
+    println!(
+        "Equivalent: the second puzzle is the first one {}, moved and with colors renamed as:",
+        equivalence.transform
+    );
+    for (from, to) in &equivalence.colors {
+        println!("  {} -> {}", from, to);
+    }
+    Status::Success
+}
+
+fn dedupe(args: DedupeArgs) -> Status {
+    let files = match dedupe::puzzle_files(&args.dir) {
+        Ok(files) => files,
+        Err(error) => {
+            eprintln!(
+                "{}: could not read directory: {}",
+                args.dir.display(),
+                error
+            );
+            return Status::InvalidPuzzle;
+        }
+    };
+    let mut status = Status::Success;
+    let puzzles: Vec<(PathBuf, Game)> = files
+        .into_iter()
+        .filter_map(|path| match load_game(&path) {
+            Some(game) => Some((path, game)),
+            None => {
+                status = status.worst(Status::InvalidPuzzle);
+                None
+            }
+        })
+        .collect();
+
+    let groups = dedupe::find(&puzzles);
+    for group in &groups {
+        println!("{}", group.kept.display());
+        for duplicate in &group.duplicates {
+            println!("  {}: {}", duplicate.path.display(), duplicate.describe());
+            let changed = if args.delete {
+                fs::remove_file(&duplicate.path)
+            } else if args.link {
+                fs::remove_file(&duplicate.path)
+                    .and_then(|()| fs::hard_link(&group.kept, &duplicate.path))
+            } else {
+                Ok(())
+            };
+            if let Err(error) = changed {
+                eprintln!("could not replace {}: {}", duplicate.path.display(), error);
+                status = status.worst(Status::OutputError);
+            }
+        }
+    }
+
+    let duplicates: usize = groups.iter().map(|group| group.duplicates.len()).sum();
+    println!(
+        "Found {} duplicates of {} puzzles among {} files{}",
+        duplicates,
+        groups.len(),
+        puzzles.len(),
+        if duplicates == 0 {
+            ""
+        } else if args.delete {
+            ", and deleted them"
+        } else if args.link {
+            ", and linked them to the files kept"
+        } else {
+            ""
+        }
+    );
+    status
+}
+
+fn analyze(args: AnalyzeArgs) -> Status {
+    let Some(game) = load_game(&args.path) else {
+        return Status::InvalidPuzzle;
+    };
+
+    let analysis = game.analyze(args.max_depth, args.max_states);
+    println!(
+        "States:      {}{}",
+        analysis.states,
+        if analysis.complete {
+            ""
+        } else {
+            " (stopped at a limit)"
+        }
+    );
+    println!("Goal states: {}", analysis.goals);
+    println!("Dead ends:   {}", analysis.dead_ends);
+    if !analysis.complete {
+        println!("Unknown:     {}", analysis.unknown);
+    }
+    println!();
+    println!("Distance to goal  States");
+    for (distance, states) in &analysis.distance_to_goal {
+        println!("{:>16}  {}", distance, states);
+    }
+    println!();
+    println!("Depth  States");
+    for (depth, states) in &analysis.depth {
+        println!("{:>5}  {}", depth, states);
+    }
+
+    Status::Success
+}
+
+fn check_heuristic(args: CheckHeuristicArgs) -> Status {
+    let mut status = Status::Success;
+    let mut puzzles = vec![];
+    if args.paths.is_empty() {
+        let options = GeneratorOptions {
+            blocks: args.blocks as usize,
+            arrows: args.arrows,
+            size: args.size,
+            min_moves: 1,
+            max_moves: args.max_depth.min(20) as i32,
+            exact_moves: None,
+            attempts: 1,
+        };
+        if let Err(error) = options.validate() {
+            return usage_error(ErrorKind::ValueValidation, &error);
+        }
+        let mut rng = Rng::new(args.seed);
+        for sample in 1..=args.samples {
+            let game = generator::random_game(&options, &mut rng);
+            puzzles.push((format!("sample {}", sample), game));
+        }
+    }
+    for path in &args.paths {
+        match load_game(path) {
+            Some(game) => puzzles.push((path.display().to_string(), game)),
+            None => status = status.worst(Status::InvalidPuzzle),
+        }
+    }
+
+    let options = SolverOptions::default()
+        .heuristic(args.heuristic)
+        .weight(args.weight);
+    let (mut overestimated, mut inconsistent) = (0, 0);
+    for (name, game) in &puzzles {
+        let check = game.check_heuristic(&options, args.max_depth, args.max_states);
+        println!(
+            "{}: {} states{}, {} with a known distance; {} overestimated, {} of {} moves inconsistent",
+            name,
+            check.states,
+            if check.complete { "" } else { " (stopped at a limit)" },
+            check.exact,
+            check.overestimates,
+            check.inconsistencies,
+            check.moves
+        );
+        if let Some(worst) = &check.worst_overestimate {
+            overestimated += 1;
+            println!(
+                "  estimate {} where {} moves are enough, after {:?}",
+                worst.estimate, worst.actual, worst.state
+            );
+        }
+        if let Some(worst) = &check.worst_inconsistency {
+            inconsistent += 1;
+            println!(
+                "  estimate drops from {} to {} when {} moves, after {:?}",
+                worst.estimate,
+                worst.successor_estimate,
+                worst.successor.last().map_or("nothing", String::as_str),
+                worst.state
+            );
+        }
+    }
+
+    println!(
+        "Checked {} puzzles: {} overestimated, {} inconsistent",
+        puzzles.len(),
+        overestimated,
+        inconsistent
+    );
+    if overestimated + inconsistent > 0 {
+        status = status.worst(Status::NoSolution);
+    }
+    status
+}
+
+fn play(args: PlayArgs) -> Status {
+    let Some(game) = load_game(&args.path) else {
+        return Status::InvalidPuzzle;
+    };
+
+    match play::run(&game, args.max_moves) {
+        Ok(()) => Status::Success,
+        Err(error) => {
+            eprintln!("terminal error: {}", error);
+            Status::OutputError
+        }
+    }
+}
+
+fn edit(args: EditArgs) -> Status {
+    let game = if args.path.exists() {
+        match load_game(&args.path) {
+            Some(game) => game,
+            None => return Status::InvalidPuzzle,
+        }
+    } else {
+        Game::new()
+    };
+
+    match edit::run(game, &args.path, args.max_moves) {
+        Ok(()) => Status::Success,
+        Err(error) => {
+            eprintln!("terminal error: {}", error);
+            Status::OutputError
+        }
+    }
+}
+
+fn repl(args: ReplArgs) -> Status {
+    let game = match &args.path {
+        Some(path) => match load_game(path) {
+            Some(game) => Some(game),
+            None => return Status::InvalidPuzzle,
+        },
+        None => None,
+    };
+
+    let mut session = repl::Session::new(game, args.max_moves);
+    match session.run(io::stdin().lock(), &mut io::stdout()) {
+        Ok(()) => Status::Success,
+        Err(error) => {
+            eprintln!("{}", error);
+            Status::OutputError
+        }
+    }
+}
+
+fn stdio() -> Status {
+    let mut session = rpc::Session::new(50);
+    match session.run(io::stdin().lock(), io::stdout()) {
+        Ok(()) => Status::Success,
+        Err(error) => {
+            eprintln!("{}", error);
+            Status::OutputError
+        }
+    }
+}
+
+fn diagnostics(args: DiagnosticsArgs) -> Status {
+    let options = SolverOptions::new(args.max_moves).timeout(args.timeout);
+    // Editors match diagnostics to documents by absolute URI.
+    let files: Vec<(PathBuf, String)> = args
+        .paths
+        .iter()
+        .map(|path| {
+            let absolute = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+            (path.clone(), format!("file://{}", absolute.display()))
+        })
+        .collect();
+    let publish = |path: &Path, uri: &str| {
+        let found = match fs::read_to_string(path) {
+            Ok(text) => diagnostics::diagnose(&text, &options),
+            Err(error) => vec![diagnostics::Diagnostic {
+                line: 1,
+                column: 1,
+                severity: diagnostics::Severity::Error,
+                message: format!("could not read file: {}", error),
+            }],
+        };
+        print!("{}", diagnostics::publish_diagnostics(uri, &found));
+        let _ = io::stdout().flush();
+    };
+    for (path, uri) in &files {
+        publish(path, uri);
+    }
+    if args.once {
+        return Status::Success;
+    }
+
+    // Editors often save by replacing the file, so watch the directories rather than the files.
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(sender) {
+        Ok(watcher) => watcher,
+        Err(error) => {
+            eprintln!("could not watch files: {}", error);
+            return Status::InvalidPuzzle;
+        }
+    };
+    for (path, _) in &files {
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        if let Err(error) = watcher.watch(directory, RecursiveMode::NonRecursive) {
+            eprintln!("could not watch {}: {}", path.display(), error);
+            return Status::InvalidPuzzle;
+        }
+    }
+
+    while let Ok(event) = receiver.recv() {
+        // A single save usually produces a burst of events, so gather the whole burst.
+        let mut changed = vec![];
+        let mut next = Some(event);
+        while let Some(event) = next {
+            if let Ok(event) = event {
+                if !event.kind.is_access() {
+                    changed.extend(event.paths);
+                }
+            }
+            next = receiver.recv_timeout(Duration::from_millis(100)).ok();
+        }
+        for (path, uri) in &files {
+            if changed
+                .iter()
+                .any(|changed| changed.file_name() == path.file_name())
+            {
+                publish(path, uri);
+            }
+        }
+    }
+
+    Status::Success
+}
+
+fn serve(args: ServeArgs) -> Status {
+    let limits = serve::Limits {
+        max_moves: args.max_moves,
+        timeout: args.timeout,
+        max_expanded: args.max_expanded,
+        max_memory: args.max_memory,
+    };
+    let address = format!("{}:{}", args.bind, args.port);
+
+    match serve::run(&address, args.workers as usize, limits) {
+        Ok(()) => Status::Success,
+        Err(error) => {
+            eprintln!("could not listen on {}: {}", address, error);
+            Status::OutputError
+        }
+    }
+}
+
+fn minimize(args: MinimizeArgs) -> Status {
+    let Some(game) = load_game(&args.path) else {
+        return Status::InvalidPuzzle;
+    };
+
+    let Some(minimization) = minimizer::minimize(&game, args.max_moves) else {
+        println!("No solution found");
+        return Status::NoSolution;
+    };
+
+    println!("Optimal solution has {} moves", minimization.optimal_length);
+    for (position, direction) in &minimization.essential {
+        println!(
+            "Essential: {} arrow at ({}, {})",
+            direction, position[0], position[1]
+        );
+    }
+    for (position, direction) in &minimization.redundant {
+        println!(
+            "Redundant: {} arrow at ({}, {})",
+            direction, position[0], position[1]
+        );
+    }
+    match minimization.bounds_essential {
+        Some(true) => println!("Essential: bounds"),
+        Some(false) => println!("Redundant: bounds"),
+        None => {}
+    }
+
+    match args.output {
+        Some(output) => write_output(Some(output), &minimization.game.to_yaml()),
+        None => Status::Success,
+    }
+}
+
+fn solve_all(args: SolveAllArgs) -> Status {
+    let parsed = File::open(&args.path)
+        .map_err(|error| format!("could not open file: {}", error))
+        .and_then(|file| {
+            pack::parse_pack(file).map_err(|error| format!("could not parse level pack: {}", error))
+        });
+    let puzzles = match parsed {
+        Ok(puzzles) => puzzles,
+        Err(error) => {
+            eprintln!("{}: {}", args.path.display(), error);
+            return Status::InvalidPuzzle;
+        }
+    };
+    let width = puzzles
+        .iter()
+        .map(|puzzle| puzzle.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+
+    println!(
+        "{:<width$}  {:>6}  {:>10}  {:>10}",
+        "name", "moves", "time (ms)", "nodes"
+    );
+    let mut status = Status::Success;
+    let mut rows = vec![];
+    for puzzle in puzzles {
+        let game = match puzzle.game {
+            Ok(game) => game,
+            Err(error) => {
+                status = status.worst(Status::InvalidPuzzle);
+                println!("{:<width$}  invalid puzzle: {}", puzzle.name, error);
+                rows.push(summary::Row::invalid(puzzle.name, error.to_string()));
+                continue;
+            }
+        };
+
+        let start = Instant::now();
+        let (moves, stats) = game.solve_with_stats(args.max_moves);
+        rows.push(summary::Row::new(
+            puzzle.name.clone(),
+            moves.as_deref(),
+            &stats,
+            start.elapsed(),
+        ));
+        let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+
+        let moves = match moves {
+            Some(moves) => moves.len().to_string(),
+            None => {
+                status = status.worst(Status::NoSolution);
+                "-".to_string()
+            }
+        };
+        println!(
+            "{:<width$}  {:>6}  {:>10.1}  {:>10}",
+            puzzle.name, moves, elapsed, stats.expanded
+        );
+    }
+
+    if let Some(csv) = args.csv {
+        status = status.worst(write_output(Some(csv), &summary::csv(&rows)));
+    }
+    status
+}
+
+fn render(args: RenderArgs) -> Status {
+    let Some(game) = load_game(&args.path) else {
+        return Status::InvalidPuzzle;
+    };
+
+    let colored = !args.ascii && args.output.is_none() && io::stdout().is_terminal();
+    let picture = if args.svg {
+        render::svg(&game)
+    } else if colored {
+        render::ansi(&game)
+    } else {
+        game.render_after(&[])
+    };
+    write_output(args.output, &picture)
+}
+
+fn bench(args: BenchArgs) -> Status {
+    let mut status = Status::Success;
+    let mut puzzles = vec![];
+    for path in &args.paths {
+        match load_game(path) {
+            Some(game) => puzzles.push((path.display().to_string(), game)),
+            None => status = status.worst(Status::InvalidPuzzle),
+        }
+    }
+
+    let runs = bench::run(&puzzles, &args.algorithms, args.max_moves, args.timeout);
+    print!("{}", bench::table(&runs, &args.algorithms));
+    if let Some(path) = args.csv {
+        status = status.worst(write_output(Some(path), &bench::csv(&runs)));
+    }
+    status
+}
+
+fn replay_log(args: ReplayLogArgs) -> Status {
+    let records = match session::read(&args.path) {
+        Ok(records) => records,
+        Err(error) => {
+            eprintln!("{}: {}", args.path.display(), error);
+            return Status::InvalidPuzzle;
+        }
+    };
+
+    let mut status = Status::Success;
+    for record in &records {
+        let summary = match &record.moves {
+            Some(moves) => format!("solved with {} moves: {:?}", moves.len(), moves),
+            None => record.outcome.describe().to_string(),
+        };
+        println!(
+            "[{}] {}: {} ({}, at most {} moves, {} expanded in {:.3}s)",
+            record.timestamp,
+            record.path.display(),
+            summary,
+            record.options.algorithm,
+            record.options.max_moves,
+            record.stats.expanded,
+            record.seconds
+        );
+        let changed = parse::load_game(&record.path)
+            .ok()
+            .is_some_and(|game| search::stable_hash(&game.to_yaml()) != record.puzzle_hash);
+        if changed {
+            println!("  {} has changed since", record.path.display());
+        }
+
+        let Some(moves) = &record.moves else {
+            continue;
+        };
+        if !args.verify && !args.render {
+            continue;
+        }
+        let game = match parse_game(&record.puzzle) {
+            Ok(game) => game,
+            Err(error) => {
+                println!("  the logged puzzle could not be read: {}", error);
+                status = status.worst(Status::InvalidPuzzle);
+                continue;
+            }
+        };
+        if args.verify {
+            match game.verify_solution(moves) {
+                Ok(_) if game.verify(moves).solved => println!("  verified"),
+                Ok(_) => {
+                    println!("  the moves do not solve the puzzle");
+                    status = status.worst(Status::NoSolution);
+                }
+                Err(invalid) => {
+                    println!("  {}", invalid);
+                    status = status.worst(Status::NoSolution);
+                }
+            }
+        }
+        if args.render {
+            println!("Start:");
+            print!("{}", game.render_after(&[]));
+            for (i, color) in moves.iter().enumerate() {
+                println!("After move {} ({}):", i + 1, color);
+                print!("{}", game.render_after(&moves[..=i]));
+            }
+        }
+    }
+
+    println!("{} solves logged", records.len());
+    status
+}
+
+fn completions(args: CompletionsArgs) -> Status {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(args.shell, &mut command, name, &mut io::stdout());
+    Status::Success
+}
+
+fn man() -> Status {
+    match clap_mangen::Man::new(Cli::command()).render(&mut io::stdout()) {
+        Ok(()) => Status::Success,
+        Err(error) => {
+            eprintln!("could not write the manual page: {}", error);
+            Status::OutputError
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let parsed = Cli::command()
+        .try_get_matches()
+        .and_then(|matches| Ok((Cli::from_arg_matches(&matches)?, matches)));
+    let (mut cli, matches) = match parsed {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            let _ = error.print();
+            return if error.use_stderr() {
+                Status::Usage.into()
+            } else {
+                Status::Success.into()
+            };
+        }
+    };
+
+    let level = match (cli.quiet, cli.verbose) {
+        (true, _) => Level::ERROR,
+        (false, 0) => Level::WARN,
+        (false, 1) => Level::INFO,
+        (false, 2) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .init();
+
+    if cli.man {
+        return man().into();
+    }
+    if cli.stdio {
+        return stdio().into();
+    }
+
+    let config = match config::load(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{}", error);
+            return Status::InvalidPuzzle.into();
+        }
+    };
+    match (&mut cli.command, matches.subcommand()) {
+        (Some(command), Some((_, matches))) => command.configure(&config, matches),
+        _ => cli.solve.configure(&config, &matches),
+    }
+
+    let threads = cli.threads.map(usize::from).or(config.threads);
+    if let Some(threads) = threads {
+        let _ = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global();
+    }
+
+    let status = match cli.command {
+        Some(Command::Solve(args)) => solve(*args),
+        Some(Command::Count(args)) => count(args),
+        Some(Command::Verify(args)) => verify(args),
+        Some(Command::Normalize(args)) => normalize(args),
+        Some(Command::Import(args)) => import(args),
+        Some(Command::Diff(args)) => diff(args),
+        Some(Command::Dedupe(args)) => dedupe(args),
+        Some(Command::Generate(args)) => generate(args),
+        Some(Command::Scramble(args)) => scramble(args),
+        Some(Command::Rate(args)) => rate(args),
+        Some(Command::Analyze(args)) => analyze(args),
+        Some(Command::CheckHeuristic(args)) => check_heuristic(args),
+        Some(Command::Minimize(args)) => minimize(args),
+        Some(Command::Render(args)) => render(args),
+        Some(Command::SolveAll(args)) => solve_all(args),
+        Some(Command::Bench(args)) => bench(args),
+        Some(Command::Play(args)) => play(args),
+        Some(Command::Edit(args)) => edit(args),
+        Some(Command::Repl(args)) => repl(args),
+        Some(Command::Serve(args)) => serve(args),
+        Some(Command::Diagnostics(args)) => diagnostics(args),
+        Some(Command::Fifteen(args)) => solve_fifteen(args),
+        Some(Command::Completions(args)) => completions(args),
+        Some(Command::ReplayLog(args)) => replay_log(args),
+        None => solve(cli.solve),
+    };
+
+    status.into()
 }
@@ -0,0 +1,114 @@
+use crate::game::{Direction, Game, Position2D};
+use crate::rules::Ruleset;
+
+#[derive(Debug)]
+pub struct Minimization {
+    pub game: Game,
+    pub optimal_length: usize,
+    pub redundant: Vec<(Position2D, Direction)>,
+    pub essential: Vec<(Position2D, Direction)>,
+    /// Whether the rules' bounds are needed for the optimal length, or `None` if the puzzle has
+    /// none. Bounds that are not are dropped, or for a wrapping board, widened by a cell on every
+    /// side, which is the relaxation tried.
+    pub bounds_essential: Option<bool>,
+}
+
+pub fn minimize(game: &Game, max_moves: i32) -> Option<Minimization> {
+    let optimal_length = game.solve_optimal(max_moves)?.len();
+    let mut minimized = game.clone();
+    let mut redundant = vec![];
+    let mut essential = vec![];
+
+    for (position, direction) in game.arrows() {
+        let mut candidate = minimized.clone();
+        candidate.remove_arrow(&position);
+
+        let unchanged = candidate
+            .solve_optimal(optimal_length as i32)
+            .is_some_and(|moves| moves.len() == optimal_length);
+        if unchanged {
+            minimized = candidate;
+            redundant.push((position, direction));
+        } else {
+            essential.push((position, direction));
+        }
+    }
+
+    let bounds_essential = minimized.rules().bounds.map(|[min, max]| {
+        // Wrapping needs bounds, so a wrapping board can only be made larger.
+        let bounds = minimized.rules().wrap.then(|| {
+            [
+                [min[0].saturating_sub(1), min[1].saturating_sub(1)],
+                [max[0].saturating_add(1), max[1].saturating_add(1)],
+            ]
+        });
+        let mut candidate = minimized.clone();
+        candidate.set_rules(Ruleset {
+            bounds,
+            ..minimized.rules().clone()
+        });
+
+        let unchanged = candidate
+            .solve_optimal(optimal_length as i32)
+            .is_some_and(|moves| moves.len() == optimal_length);
+        if unchanged {
+            minimized = candidate;
+        }
+        !unchanged
+    });
+
+    Some(Minimization {
+        game: minimized,
+        optimal_length,
+        redundant,
+        essential,
+        bounds_essential,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimize_drops_unused_arrows() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Up, [0, 0], Some([1, 2]));
+        game.add_arrow(Direction::Right, [0, 2]);
+        game.add_arrow(Direction::Down, [5, 5]);
+
+        let minimization = minimize(&game, 10).unwrap();
+        assert_eq!(minimization.optimal_length, 3);
+        assert_eq!(minimization.redundant, vec![([5, 5], Direction::Down)]);
+        assert_eq!(minimization.essential, vec![([0, 2], Direction::Right)]);
+        assert_eq!(minimization.bounds_essential, None);
+    }
+
+    #[test]
+    fn test_minimize_reports_whether_the_bounds_are_essential() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([2, 0]));
+        game.set_rules(Ruleset {
+            bounds: Some([[0, 0], [3, 0]]),
+            ..Ruleset::default()
+        });
+
+        let minimization = minimize(&game, 10).unwrap();
+        assert_eq!(minimization.bounds_essential, Some(false));
+        assert_eq!(minimization.game.rules().bounds, None);
+
+        // Red reaches its goal by wrapping round the edge, which takes longer on a wider board.
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [1, 0], Some([0, 0]));
+        game.set_rules(Ruleset {
+            bounds: Some([[0, 0], [2, 0]]),
+            wrap: true,
+            ..Ruleset::default()
+        });
+
+        let minimization = minimize(&game, 10).unwrap();
+        assert_eq!(minimization.optimal_length, 2);
+        assert_eq!(minimization.bounds_essential, Some(true));
+        assert_eq!(minimization.game.rules(), game.rules());
+    }
+}
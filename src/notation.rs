@@ -0,0 +1,288 @@
+use crate::game::{Color, ColorId, Direction, Game, Move, Position2D};
+use std::collections::HashMap;
+
+pub fn to_notation(game: &Game, moves: &[Color]) -> Option<String> {
+    let colors = game.colors();
+    let names = abbreviations(&colors);
+    let moves = game.describe_moves(moves)?;
+    let moves: Vec<String> = moves
+        .iter()
+        .map(|step| format_move(&colors, &names, step))
+        .collect();
+    Some(moves.join("; "))
+}
+
+pub fn parse_notation(game: &Game, text: &str) -> Result<Vec<Color>, String> {
+    let colors = game.colors();
+    let names = abbreviations(&colors);
+    let written: Vec<&str> = text
+        .split([';', '\n'])
+        .map(str::trim)
+        .filter(|step| !step.is_empty())
+        .collect();
+
+    let expected = written
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            parse_move(&colors, &names, step).map_err(|error| format!("move {}: {}", i + 1, error))
+        })
+        .collect::<Result<Vec<Move>, String>>()?;
+    let moves: Vec<Color> = expected
+        .iter()
+        .map(|step| colors[step.block.0].clone())
+        .collect();
+
+    let described = game
+        .describe_moves(&moves)
+        .ok_or_else(|| String::from("a move names an unknown block"))?;
+    for (i, (step, actual)) in expected.iter().zip(&described).enumerate() {
+        if !matches(step, actual) {
+            return Err(format!(
+                "move {}: `{}` does not match the puzzle, which gives `{}`",
+                i + 1,
+                written[i],
+                format_move(&colors, &names, actual)
+            ));
+        }
+    }
+
+    Ok(moves)
+}
+
+/// Starts blocks of `game` where `text` places them, e.g. `red=(2,3,up); blue=(0,0,left)`, one
+/// block per `;` or line. Colors may be abbreviated as in move notation, and directions written
+/// as a word or its first letter. Blocks not named stay where they were. After an error, some
+/// blocks may already have moved.
+pub fn place_blocks(game: &mut Game, text: &str) -> Result<(), String> {
+    let colors = game.colors();
+    let names = abbreviations(&colors);
+    let placements = text
+        .split([';', '\n'])
+        .map(str::trim)
+        .filter(|placement| !placement.is_empty());
+    for placement in placements {
+        let invalid = || format!("expected COLOR=(x,y,direction), got `{}`", placement);
+        let (name, block) = placement.split_once('=').ok_or_else(invalid)?;
+        let color = resolve(&colors, &names, name.trim())?;
+        let inner = block
+            .trim()
+            .strip_prefix('(')
+            .and_then(|block| block.strip_suffix(')'))
+            .ok_or_else(invalid)?;
+        let [x, y, direction] = inner.split(',').map(str::trim).collect::<Vec<_>>()[..] else {
+            return Err(invalid());
+        };
+        let position = [
+            x.parse().map_err(|_| invalid())?,
+            y.parse().map_err(|_| invalid())?,
+        ];
+        let direction = parse_direction(direction).ok_or_else(invalid)?;
+        game.place_block(&color, direction, position);
+    }
+
+    let state = game.initial_state();
+    let mut occupied = HashMap::new();
+    for (color, block) in state.blocks() {
+        if let Some(other) = occupied.insert(block.position, color) {
+            return Err(format!(
+                "{} and {} would both start on ({}, {})",
+                other, color, block.position[0], block.position[1]
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn parse_direction(text: &str) -> Option<Direction> {
+    match text.to_ascii_lowercase().as_str() {
+        "up" | "u" => Some(Direction::Up),
+        "down" | "d" => Some(Direction::Down),
+        "left" | "l" => Some(Direction::Left),
+        "right" | "r" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+// The first letter of each color, or the whole name when two colors share a first letter.
+fn abbreviations(colors: &[Color]) -> HashMap<Color, String> {
+    let initial = |color: &Color| color.chars().next().map(|c| c.to_ascii_uppercase());
+    colors
+        .iter()
+        .map(|color| {
+            let shared = colors
+                .iter()
+                .filter(|other| initial(other) == initial(color))
+                .count()
+                > 1;
+            let name = match initial(color) {
+                Some(letter) if !shared => letter.to_string(),
+                _ => color.clone(),
+            };
+            (color.clone(), name)
+        })
+        .collect()
+}
+
+fn resolve(colors: &[Color], names: &HashMap<Color, String>, name: &str) -> Result<Color, String> {
+    colors
+        .iter()
+        .find(|color| color.eq_ignore_ascii_case(name) || names[*color].eq_ignore_ascii_case(name))
+        .cloned()
+        .ok_or_else(|| format!("`{}` does not name a block", name))
+}
+
+// Like `resolve`, giving the block's place in `colors`.
+fn resolve_id(
+    colors: &[Color],
+    names: &HashMap<Color, String>,
+    name: &str,
+) -> Result<ColorId, String> {
+    let color = resolve(colors, names, name)?;
+    Ok(ColorId(
+        colors.iter().position(|other| *other == color).unwrap(),
+    ))
+}
+
+fn format_move(colors: &[Color], names: &HashMap<Color, String>, step: &Move) -> String {
+    let mut text = format!(
+        "{}@({},{})->{}({},{})",
+        names[&colors[step.block.0]],
+        step.from[0],
+        step.from[1],
+        direction_letter(&step.direction),
+        step.to[0],
+        step.to[1]
+    );
+    if !step.pushed.is_empty() {
+        let pushed: Vec<&str> = step
+            .pushed
+            .iter()
+            .map(|id| names[&colors[id.0]].as_str())
+            .collect();
+        text.push_str(&format!(" push {}", pushed.join(",")));
+    }
+    text
+}
+
+fn parse_move(
+    colors: &[Color],
+    names: &HashMap<Color, String>,
+    text: &str,
+) -> Result<Move, String> {
+    let invalid = || format!("expected COLOR@(x,y)->D(x,y), got `{}`", text);
+    let (head, pushed) = match text.split_once(" push ") {
+        Some((head, pushed)) => (head, Some(pushed)),
+        None => (text, None),
+    };
+    let (name, rest) = head.split_once('@').ok_or_else(invalid)?;
+    let (from, to) = rest.split_once("->").ok_or_else(invalid)?;
+    let to = to.trim();
+    let direction = match to.chars().next().map(|c| c.to_ascii_uppercase()) {
+        Some('U') => Direction::Up,
+        Some('D') => Direction::Down,
+        Some('L') => Direction::Left,
+        Some('R') => Direction::Right,
+        _ => return Err(invalid()),
+    };
+
+    let pushed = match pushed {
+        Some(pushed) => pushed
+            .split(',')
+            .map(|name| resolve_id(colors, names, name.trim()))
+            .collect::<Result<Vec<ColorId>, String>>()?,
+        None => vec![],
+    };
+
+    Ok(Move {
+        block: resolve_id(colors, names, name.trim())?,
+        direction,
+        from: parse_position(from).ok_or_else(invalid)?,
+        to: parse_position(&to[1..]).ok_or_else(invalid)?,
+        pushed,
+        redirected_to: None,
+    })
+}
+
+fn parse_position(text: &str) -> Option<Position2D> {
+    let inner = text.trim().strip_prefix('(')?.strip_suffix(')')?;
+    let (x, y) = inner.split_once(',')?;
+    Some([x.trim().parse().ok()?, y.trim().parse().ok()?])
+}
+
+// Arrow redirects are not written down, so they are left out of the comparison.
+fn matches(expected: &Move, actual: &Move) -> bool {
+    expected.block == actual.block
+        && expected.direction == actual.direction
+        && expected.from == actual.from
+        && expected.to == actual.to
+        && expected.pushed == actual.pushed
+}
+
+fn direction_letter(direction: &Direction) -> char {
+    match direction {
+        Direction::Up => 'U',
+        Direction::Down => 'D',
+        Direction::Left => 'L',
+        Direction::Right => 'R',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notation_round_trip() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([2, 0]));
+        game.add_block("blue".into(), Direction::Up, [1, 0], None);
+        game.add_block("black".into(), Direction::Down, [5, 5], None);
+
+        let moves = vec!["red".to_string(), "black".to_string()];
+        let notation = to_notation(&game, &moves).unwrap();
+        assert_eq!(notation, "R@(0,0)->R(1,0) push blue; black@(5,5)->D(5,4)");
+        assert_eq!(parse_notation(&game, &notation), Ok(moves));
+        assert_eq!(
+            parse_notation(
+                &game,
+                "r@(0,0)->R(1,0) push blue\nBLACK @ (5, 5) -> D(5, 4)"
+            )
+            .map(|moves| moves.len()),
+            Ok(2)
+        );
+
+        assert!(parse_notation(&game, "R@(0,0)->R(1,0)")
+            .unwrap_err()
+            .contains("which gives `R@(0,0)->R(1,0) push blue`"));
+        assert!(parse_notation(&game, "B@(1,0)->U(1,1)").is_err());
+    }
+
+    #[test]
+    fn test_place_blocks_moves_the_named_blocks() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([2, 0]));
+        game.add_block("blue".into(), Direction::Up, [1, 0], Some([1, 2]));
+
+        place_blocks(&mut game, "red=(2, -1, up); B=(1,1,u)").unwrap();
+        let state = game.initial_state();
+        assert_eq!(state.block("red").unwrap().position, [2, -1]);
+        assert_eq!(state.block("red").unwrap().direction, Direction::Up);
+        assert_eq!(state.block("blue").unwrap().position, [1, 1]);
+        assert_eq!(game.goals_of("red"), [[2, 0]]);
+        assert_eq!(
+            game.solve_with_stats(10).0.map(|moves| moves.len()),
+            Some(2)
+        );
+
+        assert!(place_blocks(&mut game, "green=(0,0,up)")
+            .unwrap_err()
+            .contains("does not name a block"));
+        assert!(place_blocks(&mut game, "red=(0,0)").is_err());
+        assert!(place_blocks(&mut game, "red=(0,0,sideways)").is_err());
+        assert_eq!(
+            place_blocks(&mut game, "red=(1,1,up)"),
+            Err(String::from("blue and red would both start on (1, 1)"))
+        );
+    }
+}
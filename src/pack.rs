@@ -0,0 +1,72 @@
+use crate::game::Game;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct SerializedPuzzle {
+    name: String,
+    #[serde(flatten)]
+    game: serde_yaml::Value,
+}
+
+#[derive(Deserialize)]
+struct SerializedPack {
+    puzzles: Vec<SerializedPuzzle>,
+}
+
+pub struct Puzzle {
+    pub name: String,
+    pub game: Result<Game, serde_yaml::Error>,
+}
+
+pub fn parse_pack(reader: impl std::io::Read) -> Result<Vec<Puzzle>, serde_yaml::Error> {
+    let pack: SerializedPack = serde_yaml::from_reader(reader)?;
+
+    Ok(pack
+        .puzzles
+        .into_iter()
+        .map(|puzzle| Puzzle {
+            name: puzzle.name,
+            game: serde_yaml::from_value(puzzle.game),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pack_keeps_going_after_a_broken_puzzle() {
+        let yaml = "
+puzzles:
+  - name: first
+    blocks:
+      - color: red
+        direction: up
+        position: [0, 0]
+        goal: [0, 1]
+
+  - name: broken
+    blocks:
+      - color: red
+        diretion: up
+        position: [0, 0]
+";
+
+        let puzzles = parse_pack(yaml.as_bytes()).unwrap();
+        assert_eq!(puzzles.len(), 2);
+        assert_eq!(puzzles[0].name, "first");
+        assert_eq!(
+            puzzles[0]
+                .game
+                .as_ref()
+                .unwrap()
+                .solve(5)
+                .solution()
+                .unwrap()
+                .length,
+            1
+        );
+        assert!(puzzles[1].game.is_err());
+    }
+}
@@ -0,0 +1,502 @@
+//! Reading puzzles with errors that say where the problem is and what was probably meant.
+//!
+//! A puzzle file read with [`load_game`] may start from another one with `extends: base.yaml`,
+//! a path relative to the file. Its blocks replace the base's blocks of the same color, its
+//! arrows replace the base's arrows at the same position, and everything else is added. A base
+//! may extend a further file, but not any file that extends it.
+//!
+//! ```
+//! # #[cfg(feature = "yaml")] {
+//! use solver_of_squares::parse;
+//!
+//! let error = parse::parse_game("blocks:\n  - color: red\n    direction: upp\n    position: [0, 0]\n")
+//!     .unwrap_err();
+//! assert_eq!(error.line, Some(3));
+//! assert_eq!(error.suggestion.as_deref(), Some("up"));
+//! # }
+//! ```
+//!
+//! A [`Game`] also parses from a string with [`str::parse`], which reads JSON when the text starts
+//! with `{` and YAML otherwise.
+//!
+//! Every way of reading a puzzle holds it to [`Limits`], so a puzzle from somewhere untrusted
+//! cannot ask the solver for more than it can give. Reading from text stops at the first block or
+//! arrow too many rather than reading the rest of the list.
+
+use crate::game::Game;
+#[cfg(feature = "yaml")]
+use crate::game::PuzzleLayer;
+use std::fmt::{Display, Formatter};
+#[cfg(feature = "yaml")]
+use std::fs;
+use std::path::Path;
+#[cfg(feature = "yaml")]
+use std::path::PathBuf;
+#[cfg(any(feature = "json", feature = "yaml"))]
+use std::str::FromStr;
+
+/// How large a puzzle being read may be. The defaults are far beyond any puzzle made by hand or
+/// by the generator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    pub max_blocks: usize,
+    /// Arrows, and separately the other tiles.
+    pub max_arrows: usize,
+    /// Goals and avoided cells, counted over the whole puzzle.
+    pub max_cells: usize,
+    /// How far from 0 any coordinate may be, including those of the rules' bounds.
+    pub max_coordinate: i32,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_blocks: 256,
+            max_arrows: 10_000,
+            max_cells: 10_000,
+            max_coordinate: 100_000,
+        }
+    }
+}
+
+/// Why a puzzle could not be read.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    /// The file the problem is in, when it is a base the puzzle extends rather than the puzzle.
+    pub file: Option<Box<Path>>,
+    /// Where the problem is, counting from 1, when the parser knows.
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// The part of the puzzle the problem is in, e.g. `block 2, direction`.
+    pub field: Option<String>,
+    pub message: String,
+    /// The accepted value or key closest to a misspelled one.
+    pub suggestion: Option<String>,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "line {}, column {}: ", line, column)?,
+            (Some(line), None) => write!(f, "line {}: ", line)?,
+            _ => {}
+        }
+        if let Some(field) = &self.field {
+            write!(f, "{}: ", field)?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean `{}`?)", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(feature = "yaml")]
+impl From<serde_yaml::Error> for ParseError {
+    fn from(error: serde_yaml::Error) -> Self {
+        let location = error.location();
+        let mut message = error.to_string();
+        // The location is reported separately, so drop the parser's own ` at line L column C`.
+        if let Some(at) = message.rfind(" at line ") {
+            message.truncate(at);
+        }
+
+        let field = match message.split_once(": ") {
+            Some((path, rest)) if is_path(path) => {
+                let field = describe_path(path);
+                message = rest.to_string();
+                Some(field)
+            }
+            _ => None,
+        };
+
+        ParseError {
+            file: None,
+            line: location.as_ref().map(|location| location.line()),
+            column: location.as_ref().map(|location| location.column()),
+            field,
+            suggestion: suggest(&message),
+            message,
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for ParseError {
+    fn from(error: serde_json::Error) -> Self {
+        let mut message = error.to_string();
+        if let Some(at) = message.rfind(" at line ") {
+            message.truncate(at);
+        }
+        // serde_json counts lines from 1 and reports 0 when the error is not in the text.
+        let line = Some(error.line()).filter(|line| *line > 0);
+        ParseError {
+            file: None,
+            line,
+            column: line.map(|_| error.column()),
+            field: None,
+            suggestion: suggest(&message),
+            message,
+        }
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl ParseError {
+    fn new(message: String) -> Self {
+        ParseError {
+            file: None,
+            line: None,
+            column: None,
+            field: None,
+            message,
+            suggestion: None,
+        }
+    }
+}
+
+/// Reads a puzzle written in YAML or JSON, since YAML parsers accept JSON as well. Without a
+/// path to resolve it against, `extends` is an error.
+#[cfg(feature = "yaml")]
+pub fn parse_game(text: &str) -> Result<Game, ParseError> {
+    Ok(serde_yaml::from_str(text)?)
+}
+
+/// Reads a puzzle as [`parse_game`] does, held to `limits` rather than the default ones.
+#[cfg(feature = "yaml")]
+pub fn parse_game_with_limits(text: &str, limits: &Limits) -> Result<Game, ParseError> {
+    Ok(Game::deserialize_with_limits(
+        serde_yaml::Deserializer::from_str(text),
+        limits,
+    )?)
+}
+
+impl Game {
+    /// Reads a puzzle written in YAML. Like [`parse_game`], which this is.
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_str(text: &str) -> Result<Game, ParseError> {
+        parse_game(text)
+    }
+
+    /// Reads a puzzle written in JSON, with errors placed the way a JSON parser places them.
+    #[cfg(feature = "json")]
+    pub fn from_json_str(text: &str) -> Result<Game, ParseError> {
+        Ok(serde_json::from_str(text)?)
+    }
+}
+
+/// Without the `yaml` feature, every text is read as JSON.
+#[cfg(any(feature = "json", feature = "yaml"))]
+impl FromStr for Game {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Game, ParseError> {
+        match text.trim_start().starts_with('{') {
+            #[cfg(feature = "json")]
+            true => Game::from_json_str(text),
+            #[cfg(feature = "yaml")]
+            _ => Game::from_yaml_str(text),
+            #[cfg(not(feature = "yaml"))]
+            _ => Game::from_json_str(text),
+        }
+    }
+}
+
+#[cfg(any(feature = "json", feature = "yaml"))]
+impl TryFrom<&str> for Game {
+    type Error = ParseError;
+
+    fn try_from(text: &str) -> Result<Game, ParseError> {
+        text.parse()
+    }
+}
+
+/// Reads the puzzle file at `path`, along with the files it extends.
+#[cfg(feature = "yaml")]
+pub fn load_game(path: &Path) -> Result<Game, ParseError> {
+    load_game_with_limits(path, &Limits::default())
+}
+
+/// Reads a puzzle file as [`load_game`] does, holding the puzzle it makes with the files it
+/// extends to `limits`.
+#[cfg(feature = "yaml")]
+pub fn load_game_with_limits(path: &Path, limits: &Limits) -> Result<Game, ParseError> {
+    load(path, limits, &mut vec![])
+}
+
+// `extending` holds the files on the way here, so a file that extends one of them is a loop.
+// Each file's lists are read only up to `limits`, and the puzzle is checked as each file is
+// applied, so neither a long file nor a long chain of them is read in full before it is refused.
+#[cfg(feature = "yaml")]
+fn load(path: &Path, limits: &Limits, extending: &mut Vec<PathBuf>) -> Result<Game, ParseError> {
+    let is_base = !extending.is_empty();
+    let in_base = |mut error: ParseError| {
+        if is_base {
+            error.file = Some(path.into());
+        }
+        error
+    };
+    let text = fs::read_to_string(path)
+        .map_err(|error| in_base(ParseError::new(format!("could not read file: {}", error))))?;
+    let layer =
+        PuzzleLayer::deserialize_with_limits(serde_yaml::Deserializer::from_str(&text), limits)
+            .map_err(|error| in_base(error.into()))?;
+
+    let mut game = match &layer.extends {
+        Some(base) => {
+            let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+            extending.push(canonical);
+            let base = path.parent().unwrap_or(Path::new("")).join(base);
+            let canonical = fs::canonicalize(&base).unwrap_or_else(|_| base.clone());
+            if let Some(start) = extending.iter().position(|file| *file == canonical) {
+                let mut files: Vec<String> = extending[start..]
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect();
+                files.push(canonical.display().to_string());
+                return Err(ParseError::new(format!(
+                    "`extends` loops: {}",
+                    files.join(" -> ")
+                )));
+            }
+            let game = load(&base, limits, extending)?;
+            extending.pop();
+            game
+        }
+        None => Game::new(),
+    };
+    layer.apply_to(&mut game);
+    game.check_limits(limits)
+        .map_err(|error| in_base(ParseError::new(error)))?;
+    Ok(game)
+}
+
+// serde_yaml writes where in the document an error is as e.g. `blocks[0].direction`.
+#[cfg(feature = "yaml")]
+fn is_path(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '[' | ']'))
+}
+
+// `blocks[1].direction` becomes `block 2, direction`, counting blocks and arrows from 1 as a
+// person reading the file would.
+#[cfg(feature = "yaml")]
+fn describe_path(path: &str) -> String {
+    path.split('.')
+        .map(|segment| {
+            let Some((name, index)) = segment
+                .strip_suffix(']')
+                .and_then(|segment| segment.split_once('['))
+            else {
+                return segment.to_string();
+            };
+            let name = match name {
+                "blocks" => "block",
+                "arrows" => "arrow",
+                "tiles" => "tile",
+                name => name,
+            };
+            match index.parse::<usize>() {
+                Ok(index) => format!("{} {}", name, index + 1),
+                Err(_) => segment.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// For serde's "unknown field `x`, expected ..." and "unknown variant `x`, expected ..." messages,
+// the expected name closest to `x`, if it is close enough to be a typo.
+#[cfg(any(feature = "json", feature = "yaml"))]
+fn suggest(message: &str) -> Option<String> {
+    if !message.starts_with("unknown field") && !message.starts_with("unknown variant") {
+        return None;
+    }
+    let mut names = message.split('`').skip(1).step_by(2);
+    let given = names.next()?;
+    names
+        .map(|name| (edit_distance(given, name), name))
+        .filter(|(distance, _)| *distance <= 2 && *distance < given.chars().count())
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name.to_string())
+}
+
+#[cfg(any(feature = "json", feature = "yaml"))]
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(all(test, feature = "json", feature = "yaml"))]
+mod tests {
+    use super::*;
+    use crate::game::Direction;
+
+    #[test]
+    fn test_load_game_layers_extended_files() {
+        let dir = std::env::temp_dir().join(format!("extends-{}", std::process::id()));
+        fs::create_dir_all(dir.join("bases")).unwrap();
+        fs::write(
+            dir.join("bases/base.yaml"),
+            "blocks:\n  - color: red\n    direction: up\n    position: [0, 0]\n    goal: [0, 2]\n  \
+             - color: blue\n    direction: left\n    position: [3, 3]\narrows:\n  \
+             - direction: right\n    position: [0, 1]\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("variant.yaml"),
+            "extends: bases/base.yaml\nblocks:\n  - color: red\n    direction: down\n    \
+             position: [0, 4]\narrows:\n  - direction: left\n    position: [0, 1]\n",
+        )
+        .unwrap();
+        fs::write(dir.join("a.yaml"), "extends: b.yaml\n").unwrap();
+        fs::write(dir.join("b.yaml"), "extends: a.yaml\n").unwrap();
+
+        let game = load_game(&dir.join("variant.yaml")).unwrap();
+        let state = game.initial_state();
+        assert_eq!(state.block("red").unwrap().position, [0, 4]);
+        assert_eq!(state.block("blue").unwrap().position, [3, 3]);
+        assert_eq!(game.goal_at(&[0, 2]), None);
+        assert_eq!(game.arrows(), vec![([0, 1], Direction::Left)]);
+
+        let error = load_game(&dir.join("a.yaml")).unwrap_err();
+        assert!(error.message.starts_with("`extends` loops: "));
+        assert!(parse_game("extends: base.yaml\n").is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_errors_point_at_the_field_and_suggest_a_fix() {
+        let error = parse_game("blocks:\n  - color: red\n    diretion: up\n    position: [0, 0]\n")
+            .unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("block 1"));
+        assert_eq!(error.suggestion.as_deref(), Some("direction"));
+        assert!(error
+            .to_string()
+            .starts_with("line 3, column 5: block 1: unknown field `diretion`"));
+
+        let error = parse_game(
+            r#"{"blocks": [{"color": "red", "direction": "up", "position": [0, 0]}],
+                "arrows": [{"direction": "rigth", "position": [1, 0]}]}"#,
+        )
+        .unwrap_err();
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.field.as_deref(), Some("arrow 1, direction"));
+        assert_eq!(error.suggestion.as_deref(), Some("right"));
+
+        let error = parse_game("blocks: []\nbolcks: []\n").unwrap_err();
+        assert_eq!(error.suggestion.as_deref(), Some("blocks"));
+
+        let error =
+            parse_game("blocks:\n  - color: red\n    direction: sideways\n    position: [0, 0]\n")
+                .unwrap_err();
+        assert_eq!(error.suggestion, None);
+        assert!(
+            parse_game("blocks:\n  - color: red\n    direction: up\n    position: [0, 0]\n")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_games_parse_from_yaml_or_json_strings() {
+        let yaml = "blocks:\n  - color: red\n    direction: up\n    position: [0, 0]\n";
+        let json = r#"{"blocks": [{"color": "red", "direction": "up", "position": [0, 0]}]}"#;
+        let game: Game = yaml.parse().unwrap();
+        assert_eq!(Game::try_from(json).unwrap().to_yaml(), game.to_yaml());
+        assert_eq!(Game::from_json_str(json).unwrap().to_yaml(), game.to_yaml());
+
+        let error = "  {\"blocks\": [{\"color\": \"red\", \"direction\": \"upp\"}]}"
+            .parse::<Game>()
+            .unwrap_err();
+        assert_eq!(error.line, Some(1));
+        assert_eq!(error.suggestion.as_deref(), Some("up"));
+        assert!(Game::from_json_str(yaml).is_err());
+        assert!(Game::from_yaml_str(yaml).is_ok());
+    }
+
+    #[test]
+    fn test_puzzles_past_the_limits_are_refused() {
+        let limits = Limits {
+            max_blocks: 2,
+            max_arrows: 1,
+            max_cells: 2,
+            max_coordinate: 10,
+        };
+        let block = |color: &str, x: i32| {
+            format!(
+                "  - color: {}\n    direction: up\n    position: [{}, 0]\n",
+                color, x
+            )
+        };
+        let two = format!("blocks:\n{}{}", block("red", 0), block("blue", 1));
+        assert!(parse_game_with_limits(&two, &limits).is_ok());
+
+        let three = format!("{}{}", two, block("green", 2));
+        let error = parse_game_with_limits(&three, &limits).unwrap_err();
+        assert_eq!(error.message, "more than 2 blocks");
+        assert!(error.line.is_some());
+        assert!(parse_game(&three).is_ok());
+
+        let arrows = "arrows:\n  - direction: up\n    position: [0, 0]\n  \
+                      - direction: up\n    position: [0, 1]\n";
+        let error = parse_game_with_limits(arrows, &limits).unwrap_err();
+        assert_eq!(error.message, "more than 1 arrows");
+
+        let far = format!("blocks:\n{}", block("red", 11));
+        let error = parse_game_with_limits(&far, &limits).unwrap_err();
+        assert_eq!(error.message, "position [11, 0] is more than 10 from 0");
+        let error = parse_game_with_limits(
+            &format!("{}rules:\n  bounds: [[-20, 0], [0, 0]]\n", two),
+            &limits,
+        )
+        .unwrap_err();
+        assert_eq!(error.message, "position [-20, 0] is more than 10 from 0");
+
+        let goals = "blocks:\n  - color: red\n    direction: up\n    position: [0, 0]\n    \
+                     goal: [[0, 1], [0, 2]]\navoid: [[1, 1]]\n";
+        let error = parse_game_with_limits(goals, &limits).unwrap_err();
+        assert_eq!(error.message, "more than 2 goals and avoided cells");
+
+        let json = format!(
+            r#"{{"blocks": [{{"color": "red", "direction": "up", "position": [{}, 0]}}]}}"#,
+            i32::MAX
+        );
+        assert!(Game::from_json_str(&json).is_err());
+
+        let dir = std::env::temp_dir().join(format!("limits-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("base.yaml"), &two).unwrap();
+        fs::write(
+            dir.join("more.yaml"),
+            format!("extends: base.yaml\nblocks:\n{}", block("green", 2)),
+        )
+        .unwrap();
+        assert!(load_game(&dir.join("more.yaml")).is_ok());
+        let error = load_game_with_limits(&dir.join("more.yaml"), &limits).unwrap_err();
+        assert_eq!(error.message, "more than 2 blocks");
+        fs::write(dir.join("long.yaml"), &three).unwrap();
+        fs::write(dir.join("top.yaml"), "extends: long.yaml\n").unwrap();
+        let error = load_game_with_limits(&dir.join("top.yaml"), &limits).unwrap_err();
+        assert_eq!(error.message, "more than 2 blocks");
+        assert!(error.line.is_some());
+        assert!(error.file.is_some_and(|file| file.ends_with("long.yaml")));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
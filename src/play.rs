@@ -0,0 +1,246 @@
+use ratatui::crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    MouseButton, MouseEvent, MouseEventKind,
+};
+use ratatui::crossterm::execute;
+use ratatui::layout::{Constraint, Layout, Position, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use solver_of_squares::game::{Color, Game};
+use std::io;
+
+const HELP: &str = "click a block or pick one with tab / 1-9 and press enter to move it; \
+                    u undo, r reset, s solve from here, q quit";
+
+struct Play<'a> {
+    game: &'a Game,
+    colors: Vec<Color>,
+    max_moves: i32,
+    moves: Vec<Color>,
+    selected: usize,
+    message: String,
+    board: Rect,
+}
+
+pub fn run(game: &Game, max_moves: i32) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = execute!(io::stdout(), EnableMouseCapture).and_then(|()| {
+        let mut play = Play {
+            game,
+            colors: game.colors(),
+            max_moves,
+            moves: vec![],
+            selected: 0,
+            message: String::new(),
+            board: Rect::default(),
+        };
+        play.run(&mut terminal)
+    });
+    let _ = execute!(io::stdout(), DisableMouseCapture);
+    ratatui::restore();
+    result
+}
+
+impl Play<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let keep_going = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => self.on_key(key),
+                Event::Mouse(mouse) => {
+                    self.on_mouse(mouse);
+                    true
+                }
+                _ => true,
+            };
+            if !keep_going {
+                return Ok(());
+            }
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            // A puzzle may have no blocks, and then there is nothing to select or move.
+            KeyCode::Tab | KeyCode::Down | KeyCode::Right if !self.colors.is_empty() => {
+                self.selected = (self.selected + 1) % self.colors.len();
+            }
+            KeyCode::BackTab | KeyCode::Up | KeyCode::Left if !self.colors.is_empty() => {
+                self.selected = (self.selected + self.colors.len() - 1) % self.colors.len();
+            }
+            KeyCode::Char(digit @ '1'..='9') => {
+                let index = digit as usize - '1' as usize;
+                if index < self.colors.len() {
+                    self.selected = index;
+                }
+            }
+            KeyCode::Enter | KeyCode::Char(' ') if !self.colors.is_empty() => {
+                self.move_block(self.selected)
+            }
+            KeyCode::Char('u') => {
+                self.moves.pop();
+                self.message = String::from("Undid the last move");
+            }
+            KeyCode::Char('r') => {
+                self.moves.clear();
+                self.message = String::from("Back to the start");
+            }
+            KeyCode::Char('s') => self.solve_from_here(),
+            _ => {}
+        }
+        true
+    }
+
+    fn on_mouse(&mut self, mouse: MouseEvent) {
+        if mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+            return;
+        }
+        if !self.board.contains(Position::new(mouse.column, mouse.row)) {
+            return;
+        }
+
+        let (min, max) = self.game.bounds_after(&self.moves);
+        let position = [
+            min[0] + (mouse.column - self.board.x) as i32,
+            max[1] - (mouse.row - self.board.y) as i32,
+        ];
+        let positions = self.game.positions_after(&self.moves);
+        if let Some(index) = self
+            .colors
+            .iter()
+            .position(|color| positions[color] == position)
+        {
+            self.move_block(index);
+        }
+    }
+
+    fn move_block(&mut self, index: usize) {
+        self.selected = index;
+        self.moves.push(self.colors[index].clone());
+        let verification = self.game.verify(&self.moves);
+        self.message = if let Some(violation) = verification.violation {
+            self.moves.pop();
+            format!("That {}", violation)
+        } else if let Some(color) = verification.missed_deadline {
+            self.moves.pop();
+            format!("That would make {} miss its deadline", color)
+        } else if verification.solved {
+            format!("Solved in {} moves!", self.moves.len())
+        } else {
+            String::new()
+        };
+    }
+
+    fn solve_from_here(&mut self) {
+        let remaining = self.max_moves - self.moves.len() as i32;
+        self.message = match self.game.solve_optimal_after(&self.moves, remaining) {
+            Some(moves) if moves.is_empty() => String::from("Already solved"),
+            Some(moves) => format!("Moves from here ({}): {}", moves.len(), moves.join(", ")),
+            None => format!("No solution from here within {} moves", remaining),
+        };
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [board_area, side_area] =
+            Layout::horizontal([Constraint::Min(10), Constraint::Length(40)]).areas(frame.area());
+
+        let rendered = self.game.render_after(&self.moves);
+        let (min, max) = self.game.bounds_after(&self.moves);
+        let rows = (max[1] - min[1] + 1) as usize;
+        let board: Vec<Line> = rendered.lines().take(rows).map(Line::from).collect();
+        let block = Block::bordered().title("Board");
+        self.board = block.inner(board_area);
+        frame.render_widget(Paragraph::new(board).block(block), board_area);
+
+        let mut side = vec![
+            Line::from(format!("Moves: {}", self.moves.len())),
+            Line::from(""),
+        ];
+        for (i, color) in self.colors.iter().enumerate() {
+            let line = Line::from(format!("{} {}", i + 1, color));
+            side.push(if i == self.selected {
+                line.style(Style::new().add_modifier(Modifier::REVERSED))
+            } else {
+                line
+            });
+        }
+        side.extend([
+            Line::from(""),
+            Line::from(self.message.as_str()),
+            Line::from(""),
+            Line::from(HELP),
+        ]);
+        frame.render_widget(
+            Paragraph::new(side)
+                .wrap(Wrap { trim: true })
+                .block(Block::bordered().title("Play")),
+            side_area,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moves_undo_and_reset() {
+        let mut game = Game::new();
+        game.add_block(
+            "red".to_string(),
+            solver_of_squares::game::Direction::Right,
+            [0, 0],
+            Some([2, 0]),
+        );
+        let mut play = Play {
+            game: &game,
+            colors: game.colors(),
+            max_moves: 10,
+            moves: vec![],
+            selected: 0,
+            message: String::new(),
+            board: Rect::default(),
+        };
+
+        play.on_key(KeyEvent::from(KeyCode::Enter));
+        play.on_key(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(play.message, "Solved in 2 moves!");
+        play.on_key(KeyEvent::from(KeyCode::Char('u')));
+        assert_eq!(play.moves.len(), 1);
+        play.on_key(KeyEvent::from(KeyCode::Char('s')));
+        assert_eq!(play.message, "Moves from here (1): red");
+        play.max_moves = 1;
+        play.on_key(KeyEvent::from(KeyCode::Char('s')));
+        assert_eq!(play.message, "No solution from here within 0 moves");
+        play.on_key(KeyEvent::from(KeyCode::Char('r')));
+        assert!(play.moves.is_empty());
+        assert!(!play.on_key(KeyEvent::from(KeyCode::Char('q'))));
+    }
+
+    #[test]
+    fn test_keys_do_nothing_without_blocks() {
+        let game = Game::new();
+        let mut play = Play {
+            game: &game,
+            colors: game.colors(),
+            max_moves: 10,
+            moves: vec![],
+            selected: 0,
+            message: String::new(),
+            board: Rect::default(),
+        };
+
+        for key in [
+            KeyCode::Tab,
+            KeyCode::BackTab,
+            KeyCode::Enter,
+            KeyCode::Char('1'),
+        ] {
+            assert!(play.on_key(KeyEvent::from(key)));
+        }
+        assert!(play.moves.is_empty());
+    }
+}
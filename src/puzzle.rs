@@ -0,0 +1,106 @@
+use crate::search::{astar, SearchStats, State, TieBreak};
+use std::hash::{Hash, Hasher};
+use std::time::Instant;
+
+/// A puzzle described by its rules, so it can be solved with the generic search without writing
+/// a [`State`] implementation by hand.
+pub trait Puzzle {
+    type State: Clone + Hash;
+    type Move: Clone;
+
+    fn initial_state(&self) -> Self::State;
+    fn moves(&self, state: &Self::State) -> Vec<(Self::Move, Self::State)>;
+    fn is_solved(&self, state: &Self::State) -> bool;
+
+    /// A lower bound on the moves still needed keeps the solutions short; zero is always safe.
+    fn heuristic(&self, _state: &Self::State) -> i32 {
+        0
+    }
+}
+
+struct Node<'a, P: Puzzle> {
+    puzzle: &'a P,
+    state: P::State,
+    moves: Vec<P::Move>,
+}
+
+impl<'a, P: Puzzle> Hash for Node<'a, P> {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.state.hash(hasher);
+    }
+}
+
+impl<'a, P: Puzzle> State for Node<'a, P> {
+    type Cost = i32;
+
+    fn successors(&self) -> Vec<Self> {
+        self.puzzle
+            .moves(&self.state)
+            .into_iter()
+            .map(|(step, state)| {
+                let mut moves = self.moves.clone();
+                moves.push(step);
+                Node {
+                    puzzle: self.puzzle,
+                    state,
+                    moves,
+                }
+            })
+            .collect()
+    }
+
+    fn is_goal(&self) -> bool {
+        self.puzzle.is_solved(&self.state)
+    }
+
+    fn distance_to_goal(&self) -> i32 {
+        self.puzzle.heuristic(&self.state)
+    }
+
+    fn cost(&self) -> i32 {
+        self.moves.len() as i32
+    }
+}
+
+/// The moves that solve `puzzle` within `max_moves`, found with [`astar`].
+pub fn solve<P: Puzzle>(
+    puzzle: &P,
+    max_moves: i32,
+    deadline: Option<Instant>,
+) -> (Option<Vec<P::Move>>, SearchStats) {
+    let initial = Node {
+        puzzle,
+        state: puzzle.initial_state(),
+        moves: vec![],
+    };
+    let (node, stats) = astar(
+        initial,
+        max_moves,
+        deadline,
+        None,
+        TieBreak::default(),
+        |_, _, _| {},
+    );
+    (node.map(|node| node.moves), stats)
+}
+
+#[cfg(all(test, feature = "game"))]
+mod tests {
+    use super::*;
+    use crate::game::{Direction, Game};
+
+    #[test]
+    fn test_solves_game_through_the_puzzle_trait() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([2, 0]));
+        game.add_block("blue".into(), Direction::Up, [1, -1], Some([1, 1]));
+
+        let (moves, _) = solve(&game, 10, None);
+        let moves = moves.unwrap();
+        assert!(game.verify(&moves).solved);
+        assert_eq!(
+            Some(moves.len()),
+            game.solve(10).solution().map(|solution| solution.length)
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use crate::game::{Color, Game};
+use crate::parse::{load_game, parse_game};
+use crate::search::SearchStats;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[pyclass(name = "Game", frozen)]
+struct PyGame(Game);
+
+#[pymethods]
+impl PyGame {
+    #[staticmethod]
+    fn from_yaml(yaml: &str) -> PyResult<Self> {
+        parse_game(yaml)
+            .map(PyGame)
+            .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+
+    #[staticmethod]
+    fn from_file(path: PathBuf) -> PyResult<Self> {
+        load_game(&path)
+            .map(PyGame)
+            .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+
+    #[getter]
+    fn colors(&self) -> Vec<Color> {
+        self.0.colors()
+    }
+
+    #[pyo3(signature = (max_moves = 50, timeout = None))]
+    fn solve(&self, py: Python<'_>, max_moves: i32, timeout: Option<f64>) -> PyResult<Solution> {
+        let timeout = timeout.map(seconds).transpose()?;
+        let (moves, stats) = py.detach(|| self.0.solve_with_timeout(max_moves, timeout));
+        Ok(Solution {
+            moves,
+            stats: Stats::from(stats),
+        })
+    }
+
+    fn to_yaml(&self) -> String {
+        self.0.to_yaml()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Game(colors={:?})", self.0.colors())
+    }
+}
+
+fn seconds(timeout: f64) -> PyResult<Duration> {
+    match Duration::try_from_secs_f64(timeout) {
+        Ok(duration) if !duration.is_zero() => Ok(duration),
+        _ => Err(PyValueError::new_err(
+            "timeout must be a positive number of seconds",
+        )),
+    }
+}
+
+#[pyclass(frozen, get_all)]
+struct Solution {
+    moves: Option<Vec<Color>>,
+    stats: Stats,
+}
+
+#[pymethods]
+impl Solution {
+    #[getter]
+    fn solved(&self) -> bool {
+        self.moves.is_some()
+    }
+
+    fn __repr__(&self) -> String {
+        let moves = match &self.moves {
+            Some(moves) => format!("{:?}", moves),
+            None => String::from("None"),
+        };
+        format!("Solution(moves={}, stats={})", moves, self.stats.__repr__())
+    }
+}
+
+#[pyclass(frozen, get_all, skip_from_py_object)]
+#[derive(Clone)]
+struct Stats {
+    expanded: usize,
+    generated: usize,
+    timed_out: bool,
+}
+
+#[pymethods]
+impl Stats {
+    fn __repr__(&self) -> String {
+        format!(
+            "Stats(expanded={}, generated={}, timed_out={})",
+            self.expanded,
+            self.generated,
+            if self.timed_out { "True" } else { "False" }
+        )
+    }
+}
+
+impl From<SearchStats> for Stats {
+    fn from(stats: SearchStats) -> Self {
+        Stats {
+            expanded: stats.expanded,
+            generated: stats.generated,
+            timed_out: stats.timed_out,
+        }
+    }
+}
+
+#[pymodule]
+fn solver_of_squares(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyGame>()?;
+    module.add_class::<Solution>()?;
+    module.add_class::<Stats>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_checks_the_timeout() {
+        assert!(seconds(2.5).is_ok());
+        for timeout in [0.0, -1.0, f64::NAN, f64::INFINITY, 1e300] {
+            assert!(seconds(timeout).is_err());
+        }
+
+        let game = PyGame::from_yaml(
+            "blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n    goal: [2, 0]\n",
+        )
+        .unwrap();
+        Python::initialize();
+        Python::attach(|py| {
+            let solution = game.solve(py, 10, Some(1.0)).unwrap();
+            assert_eq!(
+                solution.moves,
+                Some(vec!["red".to_string(), "red".to_string()])
+            );
+            assert!(game.solve(py, 10, Some(-1.0)).is_err());
+        });
+    }
+}
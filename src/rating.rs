@@ -0,0 +1,71 @@
+use crate::game::Game;
+
+const TRAP_SLACK: i32 = 4;
+
+#[derive(Debug)]
+pub struct Rating {
+    pub optimal_length: usize,
+    pub expanded: usize,
+    pub branching_factor: f64,
+    pub traps: usize,
+    pub optimal_solutions: u64,
+    pub score: f64,
+}
+
+pub fn rate(game: &Game, max_moves: i32) -> Option<Rating> {
+    let (solution, stats) = game.solve_optimal_with_stats(max_moves);
+    let solution = solution?;
+    let optimal_length = solution.len();
+
+    let colors = game.colors();
+    let mut traps = 0;
+    for i in 0..optimal_length {
+        let prefix = &solution[..i];
+        let remaining = (optimal_length - i) as i32;
+
+        for color in colors.iter().filter(|color| **color != solution[i]) {
+            let mut moves = prefix.to_vec();
+            moves.push(color.clone());
+            if game
+                .solve_optimal_after(&moves, remaining + TRAP_SLACK)
+                .is_none()
+            {
+                traps += 1;
+            }
+        }
+    }
+
+    let branching_factor = stats.branching_factor(optimal_length);
+    let optimal_solutions = game.count_solutions(optimal_length);
+    let score =
+        optimal_length as f64 * branching_factor * (1.0 + traps as f64 / colors.len() as f64)
+            / (optimal_solutions as f64).sqrt();
+
+    Some(Rating {
+        optimal_length,
+        expanded: stats.expanded,
+        branching_factor,
+        traps,
+        optimal_solutions,
+        score,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Direction;
+
+    #[test]
+    fn test_rate_reports_the_optimal_solution() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([3, 0]));
+        game.add_block("blue".into(), Direction::Up, [1, -1], Some([1, 1]));
+
+        let rating = rate(&game, 10).unwrap();
+        assert_eq!(rating.optimal_length, 5);
+        assert!(rating.branching_factor > 0.0);
+        assert!(rating.score > 0.0);
+        assert!(rate(&game, 2).is_none());
+    }
+}
@@ -0,0 +1,351 @@
+use crate::game::{Color, Direction, Game, Position2D, PuzzleState};
+use std::fmt::Write;
+
+const CELL: i32 = 40;
+
+// The RGB values of the color names levels usually use, for terminals that cannot look names up.
+fn rgb(color: &str) -> Option<(u8, u8, u8)> {
+    Some(match color.to_ascii_lowercase().as_str() {
+        "red" => (220, 50, 47),
+        "orange" => (255, 140, 0),
+        "yellow" => (240, 200, 0),
+        "green" => (40, 170, 60),
+        "lime" => (120, 220, 40),
+        "teal" => (0, 128, 128),
+        "cyan" => (0, 190, 210),
+        "blue" => (40, 100, 230),
+        "navy" => (30, 50, 140),
+        "purple" => (140, 60, 180),
+        "magenta" => (220, 40, 200),
+        "pink" => (255, 120, 180),
+        "brown" => (140, 90, 40),
+        "black" => (60, 60, 60),
+        "white" => (240, 240, 240),
+        "gray" | "grey" => (150, 150, 150),
+        _ => return None,
+    })
+}
+
+fn paint(text: &str, color: &str, bold: bool) -> String {
+    match rgb(color) {
+        Some((r, g, b)) => format!(
+            "\x1b[{}38;2;{};{};{}m{}\x1b[0m",
+            if bold { "1;" } else { "" },
+            r,
+            g,
+            b,
+            text
+        ),
+        None => text.to_string(),
+    }
+}
+
+/// The same picture as [`Game::render_after`] with no moves, with each block and goal letter and
+/// each color name in the legend drawn in its color using ANSI escapes. Blocks are bold.
+pub fn ansi(game: &Game) -> String {
+    let plain = game.render_after(&[]);
+    let (min, max) = game.bounds_after(&[]);
+    let rows = (max[1] - min[1] + 1) as usize;
+
+    let mut output = String::new();
+    for (row, line) in plain.lines().enumerate() {
+        if row < rows {
+            let y = max[1] - row as i32;
+            for (column, cell) in line.chars().enumerate() {
+                let position = [min[0] + column as i32, y];
+                let text = cell.to_string();
+                match (game.block_at(&position), game.goal_at(&position)) {
+                    (Some((color, _)), _) => output.push_str(&paint(&text, color, true)),
+                    (None, Some(color)) => output.push_str(&paint(&text, color, false)),
+                    (None, None) => output.push(cell),
+                }
+            }
+        } else {
+            match line.split_once(' ') {
+                Some((color, rest)) => {
+                    let _ = write!(output, "{} {}", paint(color, color, true), rest);
+                }
+                None => output.push_str(line),
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+// Color names go into attributes as they are, so anything but a plain name or hex code is drawn
+// gray.
+fn fill(color: &str) -> &str {
+    if !color.is_empty() && color.chars().all(|c| c.is_ascii_alphanumeric() || c == '#') {
+        color
+    } else {
+        "gray"
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// A triangle pointing in `direction`, filling `scale` of the cell centered at `(x, y)`.
+fn triangle(x: i32, y: i32, direction: &Direction, scale: f64) -> String {
+    let half = CELL as f64 * scale / 2.0;
+    let (x, y) = (x as f64, y as f64);
+    // SVG's y axis points down, so up on the board is negative y.
+    let points = match direction {
+        Direction::Up => [(x, y - half), (x - half, y + half), (x + half, y + half)],
+        Direction::Down => [(x, y + half), (x - half, y - half), (x + half, y - half)],
+        Direction::Left => [(x - half, y), (x + half, y - half), (x + half, y + half)],
+        Direction::Right => [(x + half, y), (x - half, y - half), (x - half, y + half)],
+    };
+    points
+        .iter()
+        .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The starting position as an SVG image: goals as rings, arrows as gray triangles, and blocks
+/// as squares in their color with a white triangle showing the way they face.
+pub fn svg(game: &Game) -> String {
+    let (min, max) = game.bounds_after(&[]);
+    svg_of(game, &game.initial_state(), min, max)
+}
+
+// `state` as an SVG image of the board from `min` to `max`.
+fn svg_of(game: &Game, state: &PuzzleState, min: Position2D, max: Position2D) -> String {
+    let width = (max[0] - min[0] + 1) * CELL;
+    let height = (max[1] - min[1] + 1) * CELL;
+    // The center of a board position in image coordinates.
+    let center = |position: &Position2D| {
+        (
+            (position[0] - min[0]) * CELL + CELL / 2,
+            (max[1] - position[1]) * CELL + CELL / 2,
+        )
+    };
+
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">",
+        width, height
+    );
+    let _ = writeln!(
+        output,
+        "  <rect width=\"{}\" height=\"{}\" fill=\"white\"/>",
+        width, height
+    );
+
+    for (position, direction) in game.arrows() {
+        let (x, y) = center(&position);
+        let _ = writeln!(
+            output,
+            "  <polygon points=\"{}\" fill=\"lightgray\"/>",
+            triangle(x, y, &direction, 0.5)
+        );
+    }
+
+    for y in (min[1]..=max[1]).rev() {
+        for x in min[0]..=max[0] {
+            if let Some(color) = game.goal_at(&[x, y]) {
+                let (cx, cy) = center(&[x, y]);
+                let _ = writeln!(
+                    output,
+                    "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"4\"><title>{} goal</title></circle>",
+                    cx,
+                    cy,
+                    CELL * 3 / 8,
+                    fill(color),
+                    escape(color)
+                );
+            }
+        }
+    }
+
+    for (color, block) in state.blocks() {
+        let (x, y) = center(&block.position);
+        let _ = writeln!(
+            output,
+            "  <g><title>{}</title><rect x=\"{}\" y=\"{}\" width=\"{3}\" height=\"{3}\" rx=\"4\" fill=\"{4}\" fill-opacity=\"0.85\"/><polygon points=\"{5}\" fill=\"white\"/></g>",
+            escape(color),
+            x - CELL * 2 / 5,
+            y - CELL * 2 / 5,
+            CELL * 4 / 5,
+            fill(color),
+            triangle(x, y, &block.direction, 0.3)
+        );
+    }
+
+    output.push_str("</svg>\n");
+    output
+}
+
+/// A standalone HTML page that plays `moves` back on the puzzle, one SVG frame per position,
+/// with buttons to step through them or play them in turn. It needs nothing but a browser. `None`
+/// if a move is not allowed.
+pub fn html(game: &Game, moves: &[Color], title: &str) -> Option<String> {
+    let states = game.states_along(moves)?;
+    // Every frame covers the same board, so that nothing jumps as the blocks move.
+    let (mut min, mut max) = game.bounds_after(&[]);
+    for block in states
+        .iter()
+        .flat_map(|state| state.blocks())
+        .map(|(_, block)| block)
+    {
+        min = [min[0].min(block.position[0]), min[1].min(block.position[1])];
+        max = [max[0].max(block.position[0]), max[1].max(block.position[1])];
+    }
+
+    let mut frames = String::new();
+    for (index, state) in states.iter().enumerate() {
+        let caption = match index {
+            0 => format!("Start, {} moves to go", moves.len()),
+            _ => format!(
+                "Move {} of {}: {}",
+                index,
+                moves.len(),
+                escape(&moves[index - 1])
+            ),
+        };
+        let _ = write!(
+            frames,
+            "<figure class=\"frame\"{}>\n{}<figcaption>{}</figcaption>\n</figure>\n",
+            if index == 0 { "" } else { " hidden" },
+            svg_of(game, state, min, max),
+            caption
+        );
+    }
+
+    Some(format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; }}
+figure {{ margin: 0; }}
+figcaption {{ margin: 0.5em 0; }}
+button {{ font-size: 1em; margin-right: 0.25em; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+{frames}<p>
+<button id="first">First</button>
+<button id="back">Back</button>
+<button id="play">Play</button>
+<button id="next">Next</button>
+<button id="last">Last</button>
+</p>
+<script>
+const frames = document.querySelectorAll(".frame");
+const play = document.getElementById("play");
+let current = 0;
+let timer = null;
+function show(index) {{
+  frames[current].hidden = true;
+  current = Math.max(0, Math.min(frames.length - 1, index));
+  frames[current].hidden = false;
+}}
+function stop() {{
+  clearInterval(timer);
+  timer = null;
+  play.textContent = "Play";
+}}
+document.getElementById("first").onclick = () => {{ stop(); show(0); }};
+document.getElementById("back").onclick = () => {{ stop(); show(current - 1); }};
+document.getElementById("next").onclick = () => {{ stop(); show(current + 1); }};
+document.getElementById("last").onclick = () => {{ stop(); show(frames.length - 1); }};
+play.onclick = () => {{
+  if (timer !== null) {{
+    stop();
+    return;
+  }}
+  if (current === frames.length - 1) {{
+    show(0);
+  }}
+  play.textContent = "Pause";
+  timer = setInterval(() => {{
+    show(current + 1);
+    if (current === frames.length - 1) {{
+      stop();
+    }}
+  }}, 700);
+}};
+document.onkeydown = (event) => {{
+  if (event.key === "ArrowLeft") {{ stop(); show(current - 1); }}
+  if (event.key === "ArrowRight") {{ stop(); show(current + 1); }}
+  if (event.key === " ") {{ event.preventDefault(); play.click(); }}
+}};
+</script>
+</body>
+</html>
+"#,
+        title = escape(title),
+        frames = frames
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game() -> Game {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([2, 0]));
+        game.add_block("<odd>".into(), Direction::Up, [1, 1], None);
+        game.add_arrow(Direction::Down, [1, 0]);
+        game
+    }
+
+    #[test]
+    fn test_ansi_colors_the_plain_rendering() {
+        let game = game();
+        let colored = ansi(&game);
+        assert!(colored.contains("\x1b[1;38;2;220;50;47mR\x1b[0m"));
+
+        let mut stripped = String::new();
+        let mut escape = false;
+        for c in colored.chars() {
+            match c {
+                '\x1b' => escape = true,
+                'm' if escape => escape = false,
+                _ if escape => {}
+                _ => stripped.push(c),
+            }
+        }
+        assert_eq!(stripped, game.render_after(&[]));
+    }
+
+    #[test]
+    fn test_svg_draws_every_piece() {
+        let svg = svg(&game());
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<g>").count(), 2);
+        assert_eq!(svg.matches("<circle").count(), 1);
+        assert!(svg.contains("stroke=\"red\""));
+        assert!(svg.contains("<title>&lt;odd&gt;</title>"));
+        assert!(svg.contains("fill=\"gray\""));
+    }
+
+    #[test]
+    fn test_html_has_a_frame_per_position() {
+        let game = game();
+        let moves = vec!["red".to_string(), "red".to_string()];
+        let page = html(&game, &moves, "a <b> c").unwrap();
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert_eq!(page.matches("<figure").count(), 3);
+        assert_eq!(page.matches("<figure class=\"frame\" hidden>").count(), 2);
+        assert!(page.contains("<title>a &lt;b&gt; c</title>"));
+        assert!(page.contains("Move 2 of 2: red"));
+        // Red ends below the board as it starts, so every frame is drawn that much taller.
+        assert_eq!(page.matches("<svg xmlns").count(), 3);
+        assert_eq!(page.matches("width=\"120\" height=\"120\"").count(), 6);
+        assert!(html(&game, &["blue".to_string()], "").is_none());
+    }
+}
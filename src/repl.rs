@@ -0,0 +1,163 @@
+use solver_of_squares::game::{Color, Game};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::time::Instant;
+
+const HELP: &str = "\
+load PATH            load a puzzle and start from its initial position
+move COLOR...        move one or more blocks
+undo                 take back the last move
+reset                go back to the initial position
+show                 print the board and the moves so far
+solve                solve optimally from the current position
+set max-moves N      change the move bound used by solve
+help                 show this list
+quit                 leave the REPL
+";
+
+pub struct Session {
+    game: Option<Game>,
+    moves: Vec<Color>,
+    max_moves: i32,
+}
+
+impl Session {
+    pub fn new(game: Option<Game>, max_moves: i32) -> Self {
+        Session {
+            game,
+            moves: vec![],
+            max_moves,
+        }
+    }
+
+    pub fn run(&mut self, input: impl BufRead, output: &mut impl Write) -> io::Result<()> {
+        let mut lines = input.lines();
+        loop {
+            write!(output, "> ")?;
+            output.flush()?;
+            let Some(line) = lines.next().transpose()? else {
+                writeln!(output)?;
+                return Ok(());
+            };
+
+            match self.execute(&line) {
+                Ok(None) => return Ok(()),
+                Ok(Some(reply)) => write!(output, "{}", reply)?,
+                Err(error) => writeln!(output, "error: {}", error)?,
+            }
+        }
+    }
+
+    // Returns Ok(None) when the session should end.
+    fn execute(&mut self, line: &str) -> Result<Option<String>, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let reply = match words.as_slice() {
+            [] => String::new(),
+            ["quit" | "exit"] => return Ok(None),
+            ["help"] => HELP.to_string(),
+            ["load", path] => {
+                let game = crate::read_game(Path::new(path))?;
+                let reply = format!("Loaded {} with {} blocks\n", path, game.colors().len());
+                self.game = Some(game);
+                self.moves.clear();
+                reply
+            }
+            ["move", colors @ ..] if !colors.is_empty() => {
+                let game = self.game()?;
+                let known = game.colors();
+                if let Some(unknown) = colors
+                    .iter()
+                    .find(|color| !known.iter().any(|c| c == *color))
+                {
+                    return Err(format!("there is no {} block", unknown));
+                }
+                self.moves
+                    .extend(colors.iter().map(|color| color.to_string()));
+                let game = self.game()?;
+                if game.verify(&self.moves).solved {
+                    format!("Solved in {} moves\n", self.moves.len())
+                } else {
+                    String::new()
+                }
+            }
+            ["undo"] => match self.moves.pop() {
+                Some(color) => format!("Undid {}\n", color),
+                None => return Err(String::from("nothing to undo")),
+            },
+            ["reset"] => {
+                self.moves.clear();
+                String::new()
+            }
+            ["show"] => {
+                let game = self.game()?;
+                format!(
+                    "{}Moves ({}): {:?}\n",
+                    game.render_after(&self.moves),
+                    self.moves.len(),
+                    self.moves
+                )
+            }
+            ["solve"] => {
+                let game = self.game()?;
+                let remaining = self.max_moves - self.moves.len() as i32;
+                let start = Instant::now();
+                let solution = game.solve_optimal_after(&self.moves, remaining);
+                let elapsed = start.elapsed().as_secs_f64();
+                match solution {
+                    Some(moves) => format!(
+                        "Moves from here ({}, found in {:.3}s): {:?}\n",
+                        moves.len(),
+                        elapsed,
+                        moves
+                    ),
+                    None => format!(
+                        "No solution within {} moves ({:.3}s)\n",
+                        self.max_moves, elapsed
+                    ),
+                }
+            }
+            ["set", "max-moves", value] => {
+                self.max_moves = value
+                    .parse()
+                    .map_err(|_| format!("invalid move bound: {}", value))?;
+                String::new()
+            }
+            [command, ..] => return Err(format!("unknown command `{}`; try `help`", command)),
+        };
+
+        Ok(Some(reply))
+    }
+
+    fn game(&self) -> Result<&Game, String> {
+        self.game
+            .as_ref()
+            .ok_or_else(|| String::from("no puzzle loaded; use `load PATH`"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solver_of_squares::game::Direction;
+
+    #[test]
+    fn test_session_moves_undoes_and_solves() {
+        let mut game = Game::new();
+        game.add_block("red".to_string(), Direction::Right, [0, 0], Some([2, 0]));
+        let mut session = Session::new(Some(game), 10);
+
+        let input =
+            "move red\nmove blue\nsolve\nundo\nset max-moves 1\nsolve\nmove red red\nquit\nshow\n";
+        let mut output = vec![];
+        session.run(input.as_bytes(), &mut output).unwrap();
+
+        assert_eq!(session.moves, vec!["red", "red"]);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("error: there is no blue block"));
+        assert!(output.contains("Moves from here (1, found in"));
+        assert!(output.contains("Undid red"));
+        assert!(output.contains("No solution within 1 moves"));
+        assert!(output.contains("Solved in 2 moves"));
+        assert!(!output.contains("Moves (2)"));
+    }
+}
@@ -0,0 +1,333 @@
+use crate::config;
+use serde_json::{json, Value};
+use solver_of_squares::game::{Color, Game};
+use solver_of_squares::parse;
+use solver_of_squares::solver::{AbortReason, SolveOutcome, SolverOptions};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// The error codes JSON-RPC 2.0 reserves, and one for requests that are well formed but cannot be
+// carried out, such as a move before any puzzle is loaded.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const REQUEST_FAILED: i64 = -32000;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// A JSON-RPC 2.0 session over a pair of streams, one message per line, for editors that run
+/// the solver as a subprocess. Requests are answered in the order they arrive.
+pub struct Session {
+    game: Option<Game>,
+    moves: Vec<Color>,
+    max_moves: i32,
+}
+
+impl Session {
+    pub fn new(max_moves: i32) -> Self {
+        Session {
+            game: None,
+            moves: vec![],
+            max_moves,
+        }
+    }
+
+    pub fn run(&mut self, input: impl BufRead, output: impl Write + Send) -> io::Result<()> {
+        let output = Mutex::new(output);
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(reply) = self.respond(&line, &output) {
+                send(&output, &reply)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Returns nothing for a notification, a request without an id.
+    fn respond(&mut self, line: &str, output: &Mutex<impl Write + Send>) -> Option<Value> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(error) => {
+                return Some(reply(
+                    &Value::Null,
+                    Err(RpcError::new(PARSE_ERROR, error.to_string())),
+                ))
+            }
+        };
+        let id = request.get("id").cloned();
+        let result = match request.get("method").and_then(Value::as_str) {
+            Some(method) => {
+                let params = request.get("params").cloned().unwrap_or(json!({}));
+                self.execute(method, &params, id.as_ref(), output)
+            }
+            None => Err(RpcError::new(INVALID_REQUEST, "missing `method`")),
+        };
+        id.map(|id| reply(&id, result))
+    }
+
+    fn execute(
+        &mut self,
+        method: &str,
+        params: &Value,
+        id: Option<&Value>,
+        output: &Mutex<impl Write + Send>,
+    ) -> Result<Value, RpcError> {
+        match method {
+            "loadPuzzle" => {
+                let game = match (string_param(params, "text"), string_param(params, "path")) {
+                    (Some(text), None) => text.parse::<Game>(),
+                    (None, Some(path)) => parse::load_game(Path::new(path)),
+                    _ => {
+                        return Err(RpcError::new(
+                            INVALID_PARAMS,
+                            "give either `text` or `path`",
+                        ))
+                    }
+                }
+                .map_err(|error| RpcError::new(REQUEST_FAILED, error.to_string()))?;
+                let result = json!({
+                    "colors": game.colors(),
+                    "arrows": game.arrows().len(),
+                });
+                self.game = Some(game);
+                self.moves.clear();
+                Ok(result)
+            }
+            "validate" => {
+                let text = string_param(params, "text")
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, "missing `text`"))?;
+                Ok(match text.parse::<Game>() {
+                    Ok(game) => json!({
+                        "valid": true,
+                        "blocks": game.colors().len(),
+                        "arrows": game.arrows().len(),
+                    }),
+                    Err(error) => json!({
+                        "valid": false,
+                        "error": error.to_string(),
+                        "line": error.line,
+                        "column": error.column,
+                    }),
+                })
+            }
+            "applyMove" => {
+                let color = string_param(params, "color")
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, "missing `color`"))?;
+                let game = self.game()?;
+                let mut moves = self.moves.clone();
+                moves.push(color.to_string());
+                let verification = game.verify(&moves);
+                if verification.unknown_color.is_some() {
+                    return Err(RpcError::new(
+                        REQUEST_FAILED,
+                        format!("there is no {} block", color),
+                    ));
+                }
+                if let Some(violation) = verification.violation {
+                    return Err(RpcError::new(REQUEST_FAILED, format!("that {}", violation)));
+                }
+                if let Some(missed) = verification.missed_deadline {
+                    return Err(RpcError::new(
+                        REQUEST_FAILED,
+                        format!("that would make {} miss its deadline", missed),
+                    ));
+                }
+                let step = game
+                    .describe_moves(&moves)
+                    .and_then(|mut steps| steps.pop());
+                self.moves = moves;
+                Ok(json!({
+                    "color": color,
+                    "move": step,
+                    "moves": self.moves,
+                    "solved": verification.solved,
+                }))
+            }
+            "solve" => {
+                let max_moves = match params.get("maxMoves") {
+                    None => self.max_moves,
+                    Some(value) => value
+                        .as_i64()
+                        .and_then(|value| i32::try_from(value).ok())
+                        .ok_or_else(|| RpcError::new(INVALID_PARAMS, "invalid `maxMoves`"))?,
+                };
+                let timeout = match params.get("timeout") {
+                    None => None,
+                    Some(value) => Some(
+                        value
+                            .as_f64()
+                            .and_then(|seconds| config::seconds_to_duration(seconds).ok())
+                            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "invalid `timeout`"))?,
+                    ),
+                };
+                let game = self.game()?;
+                solve(game, &self.moves, max_moves, timeout, id, output)
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method `{}`", method),
+            )),
+        }
+    }
+
+    fn game(&self) -> Result<&Game, RpcError> {
+        self.game
+            .as_ref()
+            .ok_or_else(|| RpcError::new(REQUEST_FAILED, "no puzzle loaded; use `loadPuzzle`"))
+    }
+}
+
+// Solves the puzzle with A* from the position after `moves`, sending a `progress` notification
+// for the request as the search goes.
+fn solve(
+    game: &Game,
+    moves: &[Color],
+    max_moves: i32,
+    timeout: Option<Duration>,
+    id: Option<&Value>,
+    output: &Mutex<impl Write + Send>,
+) -> Result<Value, RpcError> {
+    let (sender, receiver) = mpsc::channel();
+    let mut options = SolverOptions::new(max_moves).progress(sender);
+    if let Some(timeout) = timeout {
+        options = options.timeout(timeout);
+    }
+    let (remaining, stats) = thread::scope(|scope| {
+        scope.spawn(|| {
+            for progress in receiver {
+                let notification = json!({
+                    "jsonrpc": "2.0",
+                    "method": "progress",
+                    "params": {
+                        "id": id,
+                        "elapsed": progress.elapsed.as_secs_f64(),
+                        "expanded": progress.expanded,
+                        "open": progress.open,
+                        "depth": progress.depth,
+                    },
+                });
+                let _ = send(output, &notification);
+            }
+        });
+        let result = game.solve_after(moves, &options);
+        // Dropping the options closes the channel, so the thread above ends with the search.
+        drop(options);
+        result
+    });
+
+    let expanded = stats.expanded;
+    let solved = remaining.map(|remaining| [moves, &remaining].concat());
+    let (outcome, moves) = match game.outcome(solved, stats) {
+        SolveOutcome::Solved(solution) => {
+            ("solved", Some(solution.colors()[moves.len()..].to_vec()))
+        }
+        SolveOutcome::NoSolutionWithinBound => ("noSolutionWithinBound", None),
+        SolveOutcome::ProvenUnsolvable => ("provenUnsolvable", None),
+        SolveOutcome::Aborted(AbortReason::TimedOut) => ("timedOut", None),
+        SolveOutcome::Aborted(AbortReason::Cancelled) => ("cancelled", None),
+        SolveOutcome::Aborted(AbortReason::LimitReached) => ("limitReached", None),
+    };
+    Ok(json!({
+        "outcome": outcome,
+        "moves": moves,
+        "expanded": expanded,
+    }))
+}
+
+fn string_param<'a>(params: &'a Value, name: &str) -> Option<&'a str> {
+    params.get(name).and_then(Value::as_str)
+}
+
+fn reply(id: &Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": error.code, "message": error.message },
+        }),
+    }
+}
+
+fn send(output: &Mutex<impl Write>, message: &Value) -> io::Result<()> {
+    let mut output = output
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    writeln!(output, "{}", message)?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_answers_each_request_on_its_own_line() {
+        let puzzle =
+            "blocks:\\n  - color: red\\n    direction: right\\n    position: [0, 0]\\n    \
+                      goal: [2, 0]\\n";
+        let input = [
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "applyMove", "params": {"color": "red"}}"#
+                .to_string(),
+            format!(
+                r#"{{"jsonrpc": "2.0", "id": 2, "method": "loadPuzzle", "params": {{"text": "{}"}}}}"#,
+                puzzle
+            ),
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "validate", "params": {"text": "foo: 1"}}"#
+                .to_string(),
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "applyMove", "params": {"color": "red"}}"#
+                .to_string(),
+            r#"{"jsonrpc": "2.0", "method": "applyMove", "params": {"color": "blue"}}"#
+                .to_string(),
+            r#"{"jsonrpc": "2.0", "id": 5, "method": "solve", "params": {"maxMoves": 0}}"#
+                .to_string(),
+            r#"{"jsonrpc": "2.0", "id": 6, "method": "solve"}"#.to_string(),
+            r#"{"jsonrpc": "2.0", "id": 7, "method": "undo"}"#.to_string(),
+            r#"{"jsonrpc": "2.0", "id": 8, "method": "solve", "params": {"timeout": 0}}"#
+                .to_string(),
+            "not json".to_string(),
+        ]
+        .join("\n");
+        let mut output = vec![];
+        Session::new(10).run(input.as_bytes(), &mut output).unwrap();
+
+        let replies: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(replies.len(), 9);
+        assert_eq!(replies[0]["error"]["code"], REQUEST_FAILED);
+        assert_eq!(replies[1]["result"]["colors"], json!(["red"]));
+        assert_eq!(replies[2]["result"]["valid"], false);
+        assert_eq!(replies[3]["result"]["move"]["to"], json!([1, 0]));
+        assert_eq!(replies[3]["result"]["solved"], false);
+        assert_eq!(replies[4]["result"]["outcome"], "noSolutionWithinBound");
+        assert_eq!(replies[5]["id"], 6);
+        // The block has already moved once, so one more move finishes the puzzle.
+        assert_eq!(replies[5]["result"]["outcome"], "solved");
+        assert_eq!(replies[5]["result"]["moves"], json!(["red"]));
+        assert_eq!(replies[6]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(replies[7]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(replies[8]["error"]["code"], PARSE_ERROR);
+    }
+}
@@ -0,0 +1,250 @@
+//! Rule variants, read from a puzzle's `rules:` section, so that puzzles from fan versions of
+//! the game solve the way they play there.
+//!
+//! ```
+//! # #[cfg(feature = "yaml")] {
+//! use solver_of_squares::parse::parse_game;
+//! use solver_of_squares::rules::ArrowPush;
+//!
+//! let game = parse_game(
+//!     "blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n\
+//!      rules:\n  arrow_push: follow\n  bounds: [[0, 0], [3, 3]]\n  wrap: true\n",
+//! )
+//! .unwrap();
+//! assert_eq!(game.rules().arrow_push, ArrowPush::Follow);
+//! assert_eq!(game.rules().wrapped([4, -1]), [0, 3]);
+//! # }
+//! ```
+
+use crate::game::Position2D;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// What happens to a pushed block that lands on an arrow.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArrowPush {
+    /// It turns to face the arrow, and the blocks it pushes move on the way the push started.
+    #[default]
+    Turn,
+    /// It keeps facing the way it did. Only a block moving on its own turns on an arrow.
+    Keep,
+    /// It turns to face the arrow, and the blocks it pushes move the way the arrow points. A
+    /// block moving on its own that lands on an arrow pushes that way too.
+    Follow,
+}
+
+impl Display for ArrowPush {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            ArrowPush::Turn => write!(f, "turn"),
+            ArrowPush::Keep => write!(f, "keep"),
+            ArrowPush::Follow => write!(f, "follow"),
+        }
+    }
+}
+
+/// How a puzzle's rules differ from the original game's. The default is the original game.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "SerializedRuleset")]
+pub struct Ruleset {
+    pub arrow_push: ArrowPush,
+    /// An arrow disappears once a block has landed on it.
+    pub consume_arrows: bool,
+    /// The lowest and highest corners of the board. Blocks may not leave it, unless `wrap`.
+    pub bounds: Option<[Position2D; 2]>,
+    /// A block leaving the board comes back on at the opposite edge. Needs `bounds`.
+    pub wrap: bool,
+    /// The most blocks a single move may push, or no limit.
+    pub push_strength: Option<usize>,
+    /// A block on one of its goals, including one that starts there, may not leave it. Otherwise
+    /// only where the blocks end up counts.
+    pub stay_on_goal: bool,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SerializedRuleset {
+    arrow_push: ArrowPush,
+    consume_arrows: bool,
+    bounds: Option<[Position2D; 2]>,
+    wrap: bool,
+    push_strength: Option<usize>,
+    stay_on_goal: bool,
+}
+
+impl TryFrom<SerializedRuleset> for Ruleset {
+    type Error = String;
+
+    fn try_from(rules: SerializedRuleset) -> Result<Self, String> {
+        if rules.wrap && rules.bounds.is_none() {
+            return Err(String::from(
+                "`wrap` needs `bounds` to know where the edges are",
+            ));
+        }
+        if let Some([min, max]) = rules.bounds {
+            if min[0] > max[0] || min[1] > max[1] {
+                return Err(String::from(
+                    "`bounds` must be the lowest corner followed by the highest",
+                ));
+            }
+        }
+        Ok(Ruleset {
+            arrow_push: rules.arrow_push,
+            consume_arrows: rules.consume_arrows,
+            bounds: rules.bounds,
+            wrap: rules.wrap,
+            push_strength: rules.push_strength,
+            stay_on_goal: rules.stay_on_goal,
+        })
+    }
+}
+
+impl Ruleset {
+    pub fn is_default(&self) -> bool {
+        *self == Ruleset::default()
+    }
+
+    /// Whether `position` is on the board. Every position is when the board is unbounded.
+    pub fn contains(&self, position: &Position2D) -> bool {
+        self.bounds.is_none_or(|[min, max]| {
+            (min[0]..=max[0]).contains(&position[0]) && (min[1]..=max[1]).contains(&position[1])
+        })
+    }
+
+    /// Where a block moving to `position` ends up, which is elsewhere only on a wrapping board.
+    pub fn wrapped(&self, position: Position2D) -> Position2D {
+        match self.bounds {
+            Some([min, max]) if self.wrap => [0, 1].map(|i| {
+                let size = i64::from(max[i]) - i64::from(min[i]) + 1;
+                let offset = (i64::from(position[i]) - i64::from(min[i])).rem_euclid(size);
+                (i64::from(min[i]) + offset) as i32
+            }),
+            _ => position,
+        }
+    }
+
+    /// The fewest steps between two cells, going round the edges on a wrapping board. Wider
+    /// than a coordinate, since cells far apart can be more than `i32::MAX` steps apart.
+    pub fn distance(&self, a: &Position2D, b: &Position2D) -> i64 {
+        [0, 1]
+            .map(|i| {
+                let straight = (i64::from(a[i]) - i64::from(b[i])).abs();
+                match self.bounds {
+                    Some([min, max]) if self.wrap => {
+                        straight.min(i64::from(max[i]) - i64::from(min[i]) + 1 - straight)
+                    }
+                    _ => straight,
+                }
+            })
+            .iter()
+            .sum()
+    }
+
+    /// The same rules with the board's corners moved by `transform`, e.g. a rotation.
+    pub fn transformed(&self, transform: impl Fn(&Position2D) -> Position2D) -> Ruleset {
+        let bounds = self.bounds.map(|[min, max]| {
+            let (a, b) = (transform(&min), transform(&max));
+            [
+                [a[0].min(b[0]), a[1].min(b[1])],
+                [a[0].max(b[0]), a[1].max(b[1])],
+            ]
+        });
+        Ruleset {
+            bounds,
+            ..self.clone()
+        }
+    }
+
+    /// The `rules:` section of a puzzle file with these rules, or nothing for the default.
+    pub fn to_yaml(&self) -> String {
+        if self.is_default() {
+            return String::new();
+        }
+        let mut yaml = String::from("rules:\n");
+        if self.arrow_push != ArrowPush::default() {
+            yaml.push_str(&format!("  arrow_push: {}\n", self.arrow_push));
+        }
+        if self.consume_arrows {
+            yaml.push_str("  consume_arrows: true\n");
+        }
+        if let Some([min, max]) = self.bounds {
+            yaml.push_str(&format!(
+                "  bounds: [[{}, {}], [{}, {}]]\n",
+                min[0], min[1], max[0], max[1]
+            ));
+        }
+        if self.wrap {
+            yaml.push_str("  wrap: true\n");
+        }
+        if let Some(strength) = self.push_strength {
+            yaml.push_str(&format!("  push_strength: {}\n", strength));
+        }
+        if self.stay_on_goal {
+            yaml.push_str("  stay_on_goal: true\n");
+        }
+        yaml
+    }
+}
+
+/// Why a move is not allowed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Violation {
+    AvoidedCell,
+    OffBoard,
+    TooManyPushes,
+    /// A block would have moved past `i32::MIN` or `i32::MAX`.
+    OutOfRange,
+    /// A block would have entered a tile that does not let it in, such as a wall.
+    Blocked,
+    /// A block would have left its goal under [`Ruleset::stay_on_goal`].
+    LeftGoal,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Violation::AvoidedCell => write!(f, "would put a block on a cell it must avoid"),
+            Violation::OffBoard => write!(f, "would put a block off the board"),
+            Violation::TooManyPushes => write!(f, "would push more blocks than the rules allow"),
+            Violation::OutOfRange => write!(f, "would move a block past the largest coordinate"),
+            Violation::Blocked => write!(f, "would move a block onto a tile that stops it"),
+            Violation::LeftGoal => write!(f, "would move a block off a goal it must stay on"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrapping_board_distances_go_round_the_edges() {
+        let rules: Ruleset =
+            serde_yaml::from_str("bounds: [[0, 0], [4, 2]]\nwrap: true\npush_strength: 1\n")
+                .unwrap();
+        assert_eq!(rules.wrapped([5, 1]), [0, 1]);
+        assert_eq!(rules.wrapped([-1, -1]), [4, 2]);
+        assert_eq!(rules.distance(&[0, 0], &[4, 2]), 2);
+        assert!(!rules.contains(&[5, 0]));
+        let yaml = rules.to_yaml();
+        assert_eq!(serde_yaml::from_str::<Ruleset>(&yaml[7..]).unwrap(), rules);
+
+        let error = serde_yaml::from_str::<Ruleset>("wrap: true\n").unwrap_err();
+        assert!(error.to_string().contains("`wrap` needs `bounds`"));
+        assert!(serde_yaml::from_str::<Ruleset>("bounds: [[1, 0], [0, 0]]\n").is_err());
+        assert!(Ruleset::default().to_yaml().is_empty());
+
+        let everything = Ruleset {
+            bounds: Some([[i32::MIN; 2], [i32::MAX; 2]]),
+            wrap: true,
+            ..Ruleset::default()
+        };
+        assert_eq!(everything.wrapped([i32::MAX, 0]), [i32::MAX, 0]);
+        assert_eq!(everything.distance(&[i32::MIN, 0], &[i32::MAX, 0]), 1);
+        assert_eq!(
+            Ruleset::default().distance(&[i32::MIN, i32::MIN], &[i32::MAX, i32::MAX]),
+            2 * (u32::MAX as i64)
+        );
+    }
+}
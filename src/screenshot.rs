@@ -0,0 +1,320 @@
+//! Reading a puzzle from a PNG screenshot of the game, at a known zoom and with known colors.
+//!
+//! The board is cut into square cells `cell_size` pixels wide, starting at the top left corner of
+//! everything that differs from the background, which is the color of the image's top left
+//! pixel. Each cell is then read from the pixels it holds:
+//!
+//! - mostly one palette color: a block of that color, facing the side its white marker is on;
+//! - a little of a palette color: that color's goal dot;
+//! - some of the arrow color: an arrow, pointing towards the narrow end of its triangle.
+//!
+//! Rows count down the screen, so y is flipped as [`import_original`] flips it. A block drawn
+//! over its goal hides it, as it does in the game, so such a goal is not found.
+//!
+//! [`import_original`]: crate::import::import_original
+
+use crate::game::{Color, Direction, Game, Position2D};
+use std::collections::BTreeMap;
+
+/// What a screenshot looks like.
+#[derive(Clone, Debug)]
+pub struct ScreenshotOptions {
+    /// The width of a cell, in pixels.
+    pub cell_size: u32,
+    /// The color of each block and its goal dot.
+    pub palette: Vec<(Color, [u8; 3])>,
+    pub arrow: [u8; 3],
+    /// The marker that shows which way a block faces.
+    pub marker: [u8; 3],
+    /// How far, summed over the red, green, and blue channels, a pixel may be from a color and
+    /// still count as it.
+    pub tolerance: u32,
+}
+
+impl Default for ScreenshotOptions {
+    /// Colors close to the game's own, at a cell size of 100 pixels.
+    fn default() -> Self {
+        ScreenshotOptions {
+            cell_size: 100,
+            palette: [
+                ("red", [232, 78, 78]),
+                ("blue", [66, 133, 214]),
+                ("green", [96, 186, 96]),
+                ("orange", [240, 160, 60]),
+                ("purple", [150, 95, 190]),
+                ("yellow", [236, 208, 72]),
+                ("navy", [48, 64, 120]),
+                ("black", [40, 40, 40]),
+            ]
+            .into_iter()
+            .map(|(color, rgb)| (color.to_string(), rgb))
+            .collect(),
+            arrow: [120, 120, 120],
+            marker: [255, 255, 255],
+            tolerance: 60,
+        }
+    }
+}
+
+struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    fn at(&self, x: u32, y: u32) -> [u8; 3] {
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+/// Reads the puzzle in the PNG image `bytes`.
+pub fn import_screenshot(bytes: &[u8], options: &ScreenshotOptions) -> Result<Game, String> {
+    if options.cell_size == 0 {
+        return Err(String::from("the cell size must be at least 1 pixel"));
+    }
+    let image = decode(bytes)?;
+    let background = image.at(0, 0);
+    let near = |pixel: [u8; 3], color: [u8; 3]| distance(pixel, color) <= options.tolerance;
+
+    let (mut min, mut max) = ([u32::MAX; 2], [0; 2]);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            if !near(image.at(x, y), background) {
+                min = [min[0].min(x), min[1].min(y)];
+                max = [max[0].max(x), max[1].max(y)];
+            }
+        }
+    }
+    if min[0] > max[0] {
+        return Err(String::from("the image is all background"));
+    }
+
+    let size = options.cell_size;
+    let (mut blocks, mut goals, mut arrows) = (BTreeMap::new(), BTreeMap::new(), vec![]);
+    for row in 0..=(max[1] - min[1]) / size {
+        for column in 0..=(max[0] - min[0]) / size {
+            let left = min[0] + column * size;
+            let top = min[1] + row * size;
+            let cell = Cell {
+                image: &image,
+                left,
+                top,
+                size: size.min(image.width - left).min(image.height - top),
+            };
+            let position = [column as i32, -(row as i32)];
+
+            let area = (cell.size * cell.size) as f64;
+            let counts = options.palette.iter().map(|(color, rgb)| {
+                let count = cell.pixels().filter(|(_, _, p)| near(*p, *rgb)).count();
+                (color, count as f64 / area)
+            });
+            if let Some((color, share)) = counts.max_by(|a, b| a.1.total_cmp(&b.1)) {
+                if share > 0.5 {
+                    let direction = cell
+                        .pointing(|p| near(p, options.marker), false)
+                        .ok_or_else(|| {
+                            format!(
+                                "the {} block at row {}, column {} has no marker",
+                                color, row, column
+                            )
+                        })?;
+                    if blocks
+                        .insert(color.clone(), (position, direction))
+                        .is_some()
+                    {
+                        return Err(format!("there are two {} blocks", color));
+                    }
+                    continue;
+                }
+                if share > 0.02 {
+                    goals.insert(color.clone(), position);
+                    continue;
+                }
+            }
+            if let Some(direction) = cell.pointing(|p| near(p, options.arrow), true) {
+                arrows.push((position, direction));
+            }
+        }
+    }
+
+    let mut game = Game::new();
+    for (color, (position, direction)) in &blocks {
+        game.add_block(
+            color.clone(),
+            direction.clone(),
+            *position,
+            goals.get(color).copied(),
+        );
+    }
+    if let Some(color) = goals.keys().find(|color| !blocks.contains_key(*color)) {
+        return Err(format!("the {} goal has no block", color));
+    }
+    for (position, direction) in arrows {
+        game.add_arrow(direction, position);
+    }
+    Ok(game)
+}
+
+struct Cell<'a> {
+    image: &'a Image,
+    left: u32,
+    top: u32,
+    size: u32,
+}
+
+impl Cell<'_> {
+    // Every pixel, with its offset from the cell's top left corner.
+    fn pixels(&self) -> impl Iterator<Item = (u32, u32, [u8; 3])> + '_ {
+        (0..self.size).flat_map(move |y| {
+            (0..self.size).map(move |x| (x, y, self.image.at(self.left + x, self.top + y)))
+        })
+    }
+
+    // Which way the shape made of `matches` pixels points, or `None` if there are too few of them.
+    // A marker points the way it is off the cell's center. An arrow's triangle points the way it
+    // narrows, found by comparing how wide its two ends are.
+    fn pointing(&self, matches: impl Fn([u8; 3]) -> bool, by_width: bool) -> Option<Direction> {
+        let shape: Vec<Position2D> = self
+            .pixels()
+            .filter(|(_, _, pixel)| matches(*pixel))
+            .map(|(x, y, _)| [x as i32, y as i32])
+            .collect();
+        if shape.len() < (self.size * self.size / 100).max(3) as usize {
+            return None;
+        }
+
+        let (mut min, mut max) = ([i32::MAX; 2], [i32::MIN; 2]);
+        let mut sum = [0i64; 2];
+        for point in &shape {
+            for axis in 0..2 {
+                min[axis] = min[axis].min(point[axis]);
+                max[axis] = max[axis].max(point[axis]);
+                sum[axis] += i64::from(point[axis]);
+            }
+        }
+        let lean = if by_width {
+            // How much wider the low end is than the high end, along each axis: positive when
+            // the shape narrows towards the high end.
+            let width_at = |axis: usize, at: i32| {
+                shape.iter().filter(|point| point[axis] == at).count() as f64
+            };
+            [0, 1].map(|axis| width_at(axis, min[axis]) - width_at(axis, max[axis]))
+        } else {
+            let center = f64::from(self.size - 1) / 2.0;
+            [0, 1].map(|axis| sum[axis] as f64 / shape.len() as f64 - center)
+        };
+
+        Some(if lean[0].abs() >= lean[1].abs() {
+            if lean[0] > 0.0 {
+                Direction::Right
+            } else {
+                Direction::Left
+            }
+        } else if lean[1] > 0.0 {
+            Direction::Down
+        } else {
+            Direction::Up
+        })
+    }
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    (0..3).map(|i| u32::from(a[i].abs_diff(b[i]))).sum()
+}
+
+fn decode(bytes: &[u8]) -> Result<Image, String> {
+    let error = |error: png::DecodingError| format!("not a PNG image: {}", error);
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(error)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer).map_err(error)?;
+    let channels = match frame.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => return Err(String::from("could not expand the palette")),
+    };
+    let pixels = buffer[..frame.buffer_size()]
+        .chunks(frame.line_size)
+        .flat_map(|line| line[..frame.width as usize * channels].chunks(channels))
+        .map(|pixel| match pixel {
+            [gray] | [gray, _] => [*gray; 3],
+            [r, g, b, ..] => [*r, *g, *b],
+            _ => [0; 3],
+        })
+        .collect();
+    Ok(Image {
+        width: frame.width,
+        height: frame.height,
+        pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Draws a board the way the game does, `size` pixels to a cell, with a margin of one cell.
+    fn draw(size: u32, columns: u32, rows: u32, cells: &[(u32, u32, char)]) -> Vec<u8> {
+        let options = ScreenshotOptions::default();
+        let rgb = |name: &str| options.palette.iter().find(|(c, _)| c == name).unwrap().1;
+        let (width, height) = ((columns + 2) * size, (rows + 2) * size);
+        let mut pixels = vec![[250, 246, 240]; (width * height) as usize];
+        for &(column, row, kind) in cells {
+            for y in 0..size {
+                for x in 0..size {
+                    let (fx, fy) = (x as i32 - size as i32 / 2, y as i32 - size as i32 / 2);
+                    let color = match kind {
+                        // A red block facing right, its marker near the right edge.
+                        'R' if x > size * 3 / 4 && fy.abs() < 3 => Some(options.marker),
+                        'R' => Some(rgb("red")),
+                        'r' if fx * fx + fy * fy < 16 => Some(rgb("red")),
+                        // An arrow pointing up: a triangle widening downwards.
+                        '^' if y > 4 && y < size - 4 && fx.unsigned_abs() < (y - 4) / 2 => {
+                            Some(options.arrow)
+                        }
+                        _ => None,
+                    };
+                    if let Some(color) = color {
+                        let (px, py) = ((column + 1) * size + x, (row + 1) * size + y);
+                        pixels[(py * width + px) as usize] = color;
+                    }
+                }
+            }
+        }
+
+        let mut bytes = vec![];
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&pixels.concat()).unwrap();
+        writer.finish().unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_reads_blocks_goals_and_arrows_from_a_screenshot() {
+        let options = ScreenshotOptions {
+            cell_size: 20,
+            ..ScreenshotOptions::default()
+        };
+        let bytes = draw(20, 3, 2, &[(0, 0, 'R'), (2, 0, 'r'), (1, 1, '^')]);
+        let game = import_screenshot(&bytes, &options).unwrap();
+        assert_eq!(
+            game.to_yaml(),
+            "blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n    goal: [2, 0]\n\
+             \narrows:\n  - direction: up\n    position: [1, -1]\n"
+        );
+
+        let goal_only = draw(20, 1, 1, &[(0, 0, 'r')]);
+        assert_eq!(
+            import_screenshot(&goal_only, &options).unwrap_err(),
+            "the red goal has no block"
+        );
+        assert!(import_screenshot(b"not a png", &options).is_err());
+    }
+}
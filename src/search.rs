@@ -1,31 +1,356 @@
-use num::Num;
+//! Generic best-first search over any type that implements [`State`].
+//!
+//! This module does not depend on the squares game, so it is available with
+//! `default-features = false` and the `search-only` feature, which leave out serde and the game
+//! types.
+//!
+//! ```
+//! use solver_of_squares::search::{astar, State, TieBreak};
+//!
+//! // Count from `value` up to 10, one step at a time.
+//! #[derive(Hash)]
+//! struct Counter {
+//!     value: i32,
+//!     steps: i32,
+//! }
+//!
+//! impl State for Counter {
+//!     type Cost = i32;
+//!
+//!     fn successors(&self) -> Vec<Self> {
+//!         vec![Counter { value: self.value + 1, steps: self.steps + 1 }]
+//!     }
+//!     fn is_goal(&self) -> bool {
+//!         self.value == 10
+//!     }
+//!     fn distance_to_goal(&self) -> i32 {
+//!         10 - self.value
+//!     }
+//!     fn cost(&self) -> i32 {
+//!         self.steps
+//!     }
+//! }
+//!
+//! let start = Counter { value: 7, steps: 0 };
+//! let (goal, stats) = astar(start, 20, None, None, TieBreak::default(), |_, _, _| {});
+//! assert_eq!(goal.map(|goal| goal.steps), Some(3));
+//! assert_eq!(stats.expanded, 3);
+//! ```
+
+use num::{Num, ToPrimitive, Zero};
 use std::cmp::Reverse;
 use std::collections::hash_map::DefaultHasher;
-use std::collections::{BinaryHeap, HashSet};
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+const PROGRESS_INTERVAL: usize = 10_000;
 
+/// A node in the search space. Each state carries the cost of the path that reached it.
 pub trait State: Hash + Sized {
-    type Cost: Num + PartialOrd;
+    type Cost: Num + PartialOrd + Debug;
 
+    /// The states one move away.
     fn successors(&self) -> Vec<Self>;
+
+    /// Appends the states one move away to `successors`. The searches call this with a buffer
+    /// they reuse for every expansion, so override it to save allocating a `Vec` for each state.
+    fn successors_into(&self, successors: &mut Vec<Self>) {
+        successors.extend(self.successors());
+    }
     fn is_goal(&self) -> bool;
+    /// The heuristic estimate of the remaining cost.
     fn distance_to_goal(&self) -> Self::Cost;
+    /// The cost of the path so far.
     fn cost(&self) -> Self::Cost;
+
+    /// States with the same hash are treated as the same position and only visited once.
+    /// Override this to merge positions that are equivalent, e.g. under a symmetry.
+    fn canonical_hash(&self) -> u64 {
+        hash(self)
+    }
+
+    /// Compared after the cost, so that of two states with the same cost the one with the lower
+    /// secondary cost is preferred.
+    fn secondary_cost(&self) -> usize {
+        0
+    }
+
+    /// Which of the equally promising states [`TieBreak::LowerIndex`] prefers; lower comes first.
+    fn tie_break_index(&self) -> usize {
+        0
+    }
+
+    /// The number of moves from the start, checked against [`Frontier::max_depth`]. Override it
+    /// when moves can cost more or less than one; the default of 0 never reaches a depth limit.
+    fn depth(&self) -> usize {
+        0
+    }
+
+    /// The bytes the state allocates beyond its own size, which [`SearchStats::peak_memory`]
+    /// counts for every open state. The default of 0 leaves them out of the estimate.
+    fn allocated_bytes(&self) -> usize {
+        0
+    }
+}
+
+/// How A* orders states with the same f = g + h. Every strategy falls back to generation order,
+/// so a search always expands states in the same order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TieBreak {
+    /// The state with the lower heuristic estimate, which the heuristic puts closer to a goal,
+    /// so a search whose f stays level reaches the goal without first expanding the states
+    /// nearer the start. The estimate is the one worked out when the state was queued.
+    #[default]
+    LowerEstimate,
+    HigherCost,
+    LowerIndex,
+    /// Generation order alone, for comparing against runs that do not break ties.
+    Fifo,
+}
+
+impl FromStr for TieBreak {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lower-estimate" => Ok(TieBreak::LowerEstimate),
+            "higher-cost" => Ok(TieBreak::HigherCost),
+            "lower-index" => Ok(TieBreak::LowerIndex),
+            "fifo" => Ok(TieBreak::Fifo),
+            _ => Err(format!(
+                "unknown tie-break `{}`; expected lower-estimate, higher-cost, lower-index, or fifo",
+                s
+            )),
+        }
+    }
+}
+
+/// Counters reported by every search, and why it stopped early if it did.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub expanded: usize,
+    pub generated: usize,
+    pub timed_out: bool,
+    pub cancelled: bool,
+    /// The search stopped after expanding [`Frontier::max_expanded`] states.
+    pub limit_reached: bool,
+    /// States not expanded because they reached the depth limit or the cost limit. A state at
+    /// both limits counts as cut off by cost.
+    pub depth_cut_off: usize,
+    pub cost_cut_off: usize,
+    /// Successors skipped because their state had already been seen.
+    pub duplicates: usize,
+    /// The largest the open set grew, and its size when the search stopped.
+    pub peak_open: usize,
+    pub open: usize,
+    /// The distinct states seen when the search stopped, open or expanded.
+    pub seen: usize,
+    /// The search expanded every state it reached without a limit cutting any off, so a search
+    /// that found no goal proves there is none. Only [`astar_from`] and [`shortest_path`] set it.
+    pub exhausted: bool,
+    /// The most bytes the open and seen sets were estimated to hold, measured along with the
+    /// deadline. Only [`astar_from`] and [`shortest_path_observed`] estimate it.
+    pub peak_memory: usize,
+    /// How long the search ran. [`astar_from`] adds each run to the time of the frontier it
+    /// continues, and `Game::solve_with` times every algorithm.
+    pub elapsed: Duration,
+}
+
+impl SearchStats {
+    /// The share of generated states that had already been seen.
+    pub fn duplicate_rate(&self) -> f64 {
+        if self.generated == 0 {
+            return 0.0;
+        }
+        self.duplicates as f64 / self.generated as f64
+    }
+
+    /// The branching factor of a uniform tree of the solution's depth with as many nodes as
+    /// were generated.
+    pub fn branching_factor(&self, depth: usize) -> f64 {
+        effective_branching_factor(self.generated, depth)
+    }
 }
 
-fn hash(state: &impl Hash) -> u64 {
+/// Finds b such that a uniform tree of the given depth with branching factor b has `nodes` nodes.
+pub fn effective_branching_factor(nodes: usize, depth: usize) -> f64 {
+    if depth == 0 || nodes == 0 {
+        return 0.0;
+    }
+
+    let tree_size = |b: f64| (1..=depth).map(|i| b.powi(i as i32)).sum::<f64>();
+    let (mut low, mut high) = (0.0, nodes as f64);
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if tree_size(mid) < nodes as f64 {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    (low + high) / 2.0
+}
+
+/// Lets another thread stop a running search. Clones share the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Builds the hashers of the sets and maps keyed by [`State::canonical_hash`]. The keys are
+/// hashes already, so the `fast-hash` feature swaps SipHash for FxHash, which does not resist
+/// collisions crafted on purpose but makes inserting into a set about twice as fast.
+#[cfg(feature = "fast-hash")]
+pub type StateHasher = rustc_hash::FxBuildHasher;
+#[cfg(not(feature = "fast-hash"))]
+pub type StateHasher = std::collections::hash_map::RandomState;
+
+/// States seen by a search, by their [`State::canonical_hash`].
+pub type StateSet = HashSet<u64, StateHasher>;
+/// Something about each state a search has reached, by its [`State::canonical_hash`].
+pub type StateMap<V> = HashMap<u64, V, StateHasher>;
+
+/// A hash that is stable between runs, unlike the one `HashMap` uses. Searches key the positions
+/// they have seen by it. The standard library may change its algorithm in a later release, so
+/// anything that stores these hashes in a file also records [`hash_version`].
+pub fn hash(state: &impl Hash) -> u64 {
     let mut hasher = DefaultHasher::new();
     state.hash(&mut hasher);
     hasher.finish()
 }
 
+/// Changes whenever [`hash`] does, so files holding position hashes written by a build that
+/// hashes differently can be recognized and not used.
+pub fn hash_version() -> u64 {
+    hash(&"solver-of-squares position hash")
+}
+
+/// A hash fixed by its definition rather than by the standard library, so it is the same in every
+/// build and on every platform: 64-bit FNV-1a, with integers fed as little-endian bytes and
+/// `usize` as 64 bits. It identifies puzzles in checkpoints, dead-end caches and session logs.
+pub fn stable_hash(value: &impl Hash) -> u64 {
+    let mut hasher = Fnv1a::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+
+    fn write_i16(&mut self, value: i16) {
+        self.write_u16(value as u16);
+    }
+
+    fn write_i32(&mut self, value: i32) {
+        self.write_u32(value as u32);
+    }
+
+    fn write_i64(&mut self, value: i64) {
+        self.write_u64(value as u64);
+    }
+
+    fn write_isize(&mut self, value: isize) {
+        self.write_u64(value as u64);
+    }
+}
+
 struct StateContainer<T: State> {
     state: T,
+    priority: T::Cost,
+    // The heuristic part of the priority, or zero for the searches ordered by cost alone.
+    estimate: T::Cost,
+    id: u64,
+    parent: Option<u64>,
+    tie_break: TieBreak,
+    index: usize,
+    sequence: u64,
 }
 
-impl<T: State> StateContainer<T> {
-    fn new(state: T) -> Self {
-        Self { state }
+impl<T: State> StateContainer<T>
+where
+    T::Cost: Copy,
+{
+    fn new(state: T, id: u64, parent: Option<u64>) -> Self {
+        let estimate = state.distance_to_goal();
+        let priority = state.cost() + estimate;
+        Self {
+            estimate,
+            ..Self::with_priority(state, priority, id, parent)
+        }
+    }
+
+    fn with_priority(state: T, priority: T::Cost, id: u64, parent: Option<u64>) -> Self {
+        Self {
+            state,
+            priority,
+            estimate: T::Cost::zero(),
+            id,
+            parent,
+            tie_break: TieBreak::Fifo,
+            index: 0,
+            sequence: 0,
+        }
+    }
+
+    fn ordered(mut self, tie_break: TieBreak, sequence: u64) -> Self {
+        if tie_break == TieBreak::LowerIndex {
+            self.index = self.state.tie_break_index();
+        }
+        self.tie_break = tie_break;
+        self.sequence = sequence;
+        self
     }
 }
 
@@ -45,36 +370,1490 @@ impl<T: State> PartialOrd for StateContainer<T> {
 
 impl<T: State> Ord for StateContainer<T> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        let self_cost = self.state.cost() + self.state.distance_to_goal();
-        let other_cost = other.state.cost() + other.state.distance_to_goal();
+        let ties = match self.tie_break {
+            TieBreak::LowerEstimate => self.estimate.partial_cmp(&other.estimate),
+            TieBreak::HigherCost => other.state.cost().partial_cmp(&self.state.cost()),
+            TieBreak::LowerIndex => Some(self.index.cmp(&other.index)),
+            TieBreak::Fifo => None,
+        };
+        self.priority
+            .partial_cmp(&other.priority)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(
+                self.state
+                    .secondary_cost()
+                    .cmp(&other.state.secondary_cost()),
+            )
+            .then(ties.unwrap_or(std::cmp::Ordering::Equal))
+            .then(self.sequence.cmp(&other.sequence))
+    }
+}
+
+/// Which structure holds the open states of an A* search.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OpenList {
+    /// A binary heap, which follows the secondary cost and every tie-break exactly.
+    #[default]
+    Heap,
+    /// A bucket for each integer f, split by estimate when the tie-break is
+    /// [`TieBreak::LowerEstimate`] or [`TieBreak::HigherCost`], each bucket in generation order.
+    /// Pushing and popping take constant time rather than time logarithmic in the open set, but
+    /// the secondary cost and [`TieBreak::LowerIndex`] are ignored. Meant for integer costs with
+    /// f values that span a small range; fractional values are rounded down.
+    Buckets,
+}
+
+impl FromStr for OpenList {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "heap" => Ok(OpenList::Heap),
+            "buckets" => Ok(OpenList::Buckets),
+            _ => Err(format!(
+                "unknown open list `{}`; expected heap or buckets",
+                s
+            )),
+        }
+    }
+}
+
+// The open states of a bucket queue, by f less `base` and then by estimate.
+struct Buckets<T: State> {
+    buckets: VecDeque<Vec<VecDeque<StateContainer<T>>>>,
+    base: i64,
+    // No bucket below this one holds a state.
+    lowest: usize,
+    by_estimate: bool,
+    len: usize,
+}
+
+impl<T: State> Buckets<T>
+where
+    T::Cost: Copy + ToPrimitive,
+{
+    fn push(&mut self, container: StateContainer<T>) {
+        let f = container.priority.to_i64().unwrap_or(i64::MAX);
+        let estimate = if self.by_estimate {
+            container.estimate.to_usize().unwrap_or(0)
+        } else {
+            0
+        };
+        if self.buckets.is_empty() {
+            self.base = f;
+        }
+        // A heuristic that is not consistent can give a successor a lower f than any so far.
+        while f < self.base {
+            self.buckets.push_front(vec![]);
+            self.base -= 1;
+            self.lowest += 1;
+        }
+        let index = (f - self.base) as usize;
+        if self.buckets.len() <= index {
+            self.buckets.resize_with(index + 1, Vec::new);
+        }
+        let bucket = &mut self.buckets[index];
+        if bucket.len() <= estimate {
+            bucket.resize_with(estimate + 1, VecDeque::new);
+        }
+        bucket[estimate].push_back(container);
+        self.lowest = self.lowest.min(index);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<StateContainer<T>> {
+        while let Some(bucket) = self.buckets.get_mut(self.lowest) {
+            if let Some(container) = bucket.iter_mut().find_map(VecDeque::pop_front) {
+                self.len -= 1;
+                return Some(container);
+            }
+            self.lowest += 1;
+        }
+        None
+    }
+}
+
+// The open states of an A* search, in whichever structure [`Frontier::open_list`] chose.
+enum OpenSet<T: State> {
+    Heap(BinaryHeap<Reverse<StateContainer<T>>>),
+    Buckets(Buckets<T>),
+}
+
+impl<T: State> OpenSet<T>
+where
+    T::Cost: Copy + ToPrimitive,
+{
+    fn new(open_list: OpenList, tie_break: TieBreak) -> Self {
+        match open_list {
+            OpenList::Heap => OpenSet::Heap(BinaryHeap::new()),
+            OpenList::Buckets => OpenSet::Buckets(Buckets {
+                buckets: VecDeque::new(),
+                base: 0,
+                lowest: 0,
+                by_estimate: matches!(tie_break, TieBreak::LowerEstimate | TieBreak::HigherCost),
+                len: 0,
+            }),
+        }
+    }
+
+    fn push(&mut self, container: StateContainer<T>) {
+        match self {
+            OpenSet::Heap(heap) => heap.push(Reverse(container)),
+            OpenSet::Buckets(buckets) => buckets.push(container),
+        }
+    }
+
+    fn pop(&mut self) -> Option<StateContainer<T>> {
+        match self {
+            OpenSet::Heap(heap) => heap.pop().map(|reversed| reversed.0),
+            OpenSet::Buckets(buckets) => buckets.pop(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            OpenSet::Heap(heap) => heap.len(),
+            OpenSet::Buckets(buckets) => buckets.len,
+        }
+    }
+
+    fn states(&self) -> Box<dyn Iterator<Item = &T> + '_> {
+        match self {
+            OpenSet::Heap(heap) => Box::new(heap.iter().map(|reversed| &reversed.0.state)),
+            OpenSet::Buckets(buckets) => Box::new(
+                buckets
+                    .buckets
+                    .iter()
+                    .flatten()
+                    .flatten()
+                    .map(|container| &container.state),
+            ),
+        }
+    }
+}
+
+/// A snapshot of a running A* search, sent to [`Frontier::progress`] every
+/// [`Frontier::progress_interval`].
+#[derive(Clone, Debug)]
+pub struct Progress {
+    pub elapsed: Duration,
+    pub expanded: usize,
+    pub generated: usize,
+    pub open: usize,
+    pub seen: usize,
+    /// The [`State::depth`] of the state being expanded, the most promising one left.
+    pub depth: usize,
+    /// The bytes held by the open set's states and the seen set's hashes, estimated as for
+    /// [`SearchStats::peak_memory`].
+    pub memory: usize,
+}
+
+/// The open states and seen set of an A* search, so that it can be saved and continued.
+pub struct Frontier<T> {
+    pub open: Vec<T>,
+    pub seen: StateSet,
+    pub stats: SearchStats,
+    /// Keep the states that reached `max_cost` in `open`, so the search can go on with a higher bound.
+    pub keep_cut_off: bool,
+    pub tie_break: TieBreak,
+    pub open_list: OpenList,
+    /// Stop once this many states have been expanded in total.
+    pub max_expanded: Option<usize>,
+    /// Stop once the open and seen sets are estimated to hold more than this many bytes.
+    pub max_memory: Option<usize>,
+    /// Do not expand states at this [`State::depth`], independently of their cost.
+    pub max_depth: Option<usize>,
+    /// Where to send a [`Progress`] report while the search runs. The search goes on if the
+    /// receiver is gone.
+    pub progress: Option<Sender<Progress>>,
+    /// How long to wait between reports, checked along with the deadline. Defaults to a second.
+    pub progress_interval: Duration,
+}
+
+impl<T> Frontier<T> {
+    pub fn start(initial_state: T) -> Self {
+        Frontier {
+            open: vec![initial_state],
+            seen: StateSet::default(),
+            stats: SearchStats::default(),
+            keep_cut_off: false,
+            tie_break: TieBreak::default(),
+            open_list: OpenList::default(),
+            max_expanded: None,
+            max_memory: None,
+            max_depth: None,
+            progress: None,
+            progress_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Searches for a goal state with a cost below `max_cost`, expanding states in order of
+/// cost plus heuristic. Stops early when `deadline` passes or `cancel` is cancelled.
+/// `on_expand` sees each expanded state with its hash and its parent's hash.
+pub fn astar<T: State>(
+    initial_state: T,
+    max_cost: T::Cost,
+    deadline: Option<Instant>,
+    cancel: Option<&CancellationToken>,
+    tie_break: TieBreak,
+    on_expand: impl FnMut(&T, u64, Option<u64>),
+) -> (Option<T>, SearchStats)
+where
+    T::Cost: Copy + ToPrimitive,
+{
+    let mut frontier = Frontier::start(initial_state);
+    frontier.tie_break = tie_break;
+    astar_from(
+        &mut frontier,
+        max_cost,
+        deadline,
+        cancel,
+        on_expand,
+        None,
+        |_, _, _| {},
+    )
+}
+
+/// Like [`astar`], but continues from a saved frontier and hands the whole frontier to
+/// `on_checkpoint` every `checkpoint_interval` and when the deadline is hit. If no goal is found,
+/// the frontier is left with the cut-off states when `keep_cut_off` is set.
+pub fn astar_from<T: State>(
+    frontier: &mut Frontier<T>,
+    max_cost: T::Cost,
+    deadline: Option<Instant>,
+    cancel: Option<&CancellationToken>,
+    mut on_expand: impl FnMut(&T, u64, Option<u64>),
+    checkpoint_interval: Option<Duration>,
+    mut on_checkpoint: impl FnMut(Vec<&T>, &StateSet, &SearchStats),
+) -> (Option<T>, SearchStats)
+where
+    T::Cost: Copy + ToPrimitive,
+{
+    let mut stats = frontier.stats.clone();
+    stats.timed_out = false;
+    stats.cancelled = false;
+    stats.limit_reached = false;
+    let cut_off_before = stats.cost_cut_off + stats.depth_cut_off;
+    let seen = &mut frontier.seen;
+    let mut cut_off = vec![];
+    let tie_break = frontier.tie_break;
+    let max_expanded = frontier.max_expanded;
+    let max_memory = frontier.max_memory;
+    let max_depth = frontier.max_depth;
+    let mut sequence = 0;
+    let mut next = || {
+        sequence += 1;
+        sequence
+    };
+    let mut open_set = OpenSet::new(frontier.open_list, tie_break);
+    for state in std::mem::take(&mut frontier.open) {
+        let id = state.canonical_hash();
+        open_set.push(StateContainer::new(state, id, None).ordered(tie_break, next()));
+    }
+    let mut last_checkpoint = checkpoint_interval.map(|_| Instant::now());
+    let start = Instant::now();
+    let mut last_report = start;
+    stats.peak_open = stats.peak_open.max(open_set.len());
+
+    let mut successors = Vec::new();
+    while let Some(container) = open_set.pop() {
+        let state = &container.state;
+        let id = container.id;
+
+        if state.is_goal() {
+            stats.open = open_set.len();
+            stats.seen = seen.len();
+            stats.peak_memory =
+                stats
+                    .peak_memory
+                    .max(estimated_memory(open_set.len(), seen, state));
+            stats.elapsed += start.elapsed();
+            return (Some(container.state), stats);
+        }
+
+        if cancel.is_some_and(CancellationToken::is_cancelled) {
+            stats.cancelled = true;
+            break;
+        }
+
+        if max_expanded.is_some_and(|max_expanded| stats.expanded >= max_expanded) {
+            stats.limit_reached = true;
+            break;
+        }
+
+        if stats.expanded.is_multiple_of(1024) {
+            if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                stats.timed_out = true;
+                if checkpoint_interval.is_some() {
+                    let open = std::iter::once(state).chain(open_set.states());
+                    on_checkpoint(open.collect(), seen, &stats);
+                }
+                break;
+            }
+
+            let memory = estimated_memory(open_set.len(), seen, state);
+            stats.peak_memory = stats.peak_memory.max(memory);
+            if max_memory.is_some_and(|max_memory| memory > max_memory) {
+                stats.limit_reached = true;
+                break;
+            }
+
+            if let (Some(interval), Some(last)) = (checkpoint_interval, last_checkpoint) {
+                if last.elapsed() >= interval {
+                    let open = std::iter::once(state).chain(open_set.states());
+                    on_checkpoint(open.collect(), seen, &stats);
+                    last_checkpoint = Some(Instant::now());
+                }
+            }
+
+            if let Some(progress) = &frontier.progress {
+                if last_report.elapsed() >= frontier.progress_interval {
+                    let _ = progress.send(Progress {
+                        elapsed: start.elapsed(),
+                        expanded: stats.expanded,
+                        generated: stats.generated,
+                        open: open_set.len(),
+                        seen: seen.len(),
+                        depth: state.depth(),
+                        memory,
+                    });
+                    last_report = Instant::now();
+                }
+            }
+        }
+
+        let beyond_cost = state.cost() >= max_cost;
+        if beyond_cost || max_depth.is_some_and(|max_depth| state.depth() >= max_depth) {
+            if beyond_cost {
+                stats.cost_cut_off += 1;
+            } else {
+                stats.depth_cut_off += 1;
+            }
+            if frontier.keep_cut_off {
+                cut_off.push(container.state);
+            }
+        } else {
+            stats.expanded += 1;
+            if stats.expanded.is_multiple_of(PROGRESS_INTERVAL) {
+                debug!(
+                    expanded = stats.expanded,
+                    f = ?container.priority,
+                    open = open_set.len(),
+                    "search progress"
+                );
+            }
+            on_expand(state, id, container.parent);
+
+            state.successors_into(&mut successors);
+            for successor in successors.drain(..) {
+                stats.generated += 1;
+                let fingerprint = successor.canonical_hash();
+
+                if seen.contains(&fingerprint) {
+                    stats.duplicates += 1;
+                } else {
+                    open_set.push(
+                        StateContainer::new(successor, fingerprint, Some(id))
+                            .ordered(tie_break, next()),
+                    );
+                    seen.insert(fingerprint);
+                }
+            }
+            stats.peak_open = stats.peak_open.max(open_set.len());
+        }
+    }
+
+    stats.open = open_set.len() + cut_off.len();
+    stats.seen = seen.len();
+    stats.exhausted = !(stats.timed_out || stats.cancelled || stats.limit_reached)
+        && stats.cost_cut_off + stats.depth_cut_off == cut_off_before;
+    stats.elapsed += start.elapsed();
+    frontier.open = cut_off;
+    frontier.stats = stats.clone();
+    (None, stats)
+}
+
+// The bytes the open and seen sets hold, counting every open state as allocating as much as
+// `sample`.
+fn estimated_memory<T: State>(open: usize, seen: &StateSet, sample: &T) -> usize {
+    open * (std::mem::size_of::<StateContainer<T>>() + sample.allocated_bytes())
+        + seen.capacity() * std::mem::size_of::<u64>()
+}
+
+/// Runs [`astar_from`] with each bound in turn, carrying the seen set and the cut-off states over
+/// so that raising the bound only searches the new part of the space. Returns the last bound tried.
+pub fn astar_raising<T: State>(
+    initial_state: T,
+    bounds: impl IntoIterator<Item = T::Cost>,
+    deadline: Option<Instant>,
+) -> (Option<T>, SearchStats, Option<T::Cost>)
+where
+    T::Cost: Copy + ToPrimitive,
+{
+    let mut frontier = Frontier::start(initial_state);
+    frontier.keep_cut_off = true;
+    let mut stats = SearchStats::default();
+    let mut last_bound = None;
+
+    for bound in bounds {
+        if last_bound.is_some_and(|last| bound <= last) {
+            continue;
+        }
+        last_bound = Some(bound);
+        let (state, round) = astar_from(
+            &mut frontier,
+            bound,
+            deadline,
+            None,
+            |_, _, _| {},
+            None,
+            |_, _, _| {},
+        );
+        stats = round;
+        if state.is_some() || stats.timed_out {
+            return (state, stats, last_bound);
+        }
+        if frontier.open.is_empty() {
+            break;
+        }
+        debug!(bound = ?bound, cut_off = frontier.open.len(), "raising the bound");
+    }
+
+    (None, stats, last_bound)
+}
+
+struct MemoryNode<T: State> {
+    state: T,
+    f: T::Cost,
+    depth: usize,
+    parent: Option<usize>,
+    children: usize,
+    // The lowest f among the children dropped to make room, so the node is worth expanding again.
+    forgotten: Option<T::Cost>,
+}
+
+/// Memory-bounded A* (SMA*): like [`astar`], but never holds more than `max_nodes` states. When
+/// memory is full it drops the least promising leaf, the one with the highest f and, among
+/// those, the shallowest, and its parent remembers the leaf's f so the subtree is regenerated if
+/// it becomes the most promising again. The search finds a solution whenever the path to it and
+/// the siblings along the way fit within the budget. If even the children of a single state do
+/// not fit, it stops with `stats.limit_reached` set.
+pub fn sma_star<T: State>(
+    initial_state: T,
+    max_cost: T::Cost,
+    max_nodes: usize,
+    deadline: Option<Instant>,
+    cancel: Option<&CancellationToken>,
+) -> (Option<T>, SearchStats)
+where
+    T::Cost: Copy + Ord,
+{
+    let mut stats = SearchStats::default();
+    let max_nodes = max_nodes.max(2);
+    let mut nodes: HashMap<usize, MemoryNode<T>> = HashMap::new();
+    let mut in_memory: StateMap<usize> = StateMap::default();
+    // Ordered so that the first entry is the lowest f and, among those, the deepest.
+    let mut queue = BTreeSet::new();
+    let mut next_key = 0;
+    let mut dropped = 0;
+
+    let f = initial_state.cost() + initial_state.distance_to_goal();
+    in_memory.insert(initial_state.canonical_hash(), next_key);
+    queue.insert((f, Reverse(0), next_key));
+    nodes.insert(
+        next_key,
+        MemoryNode {
+            state: initial_state,
+            f,
+            depth: 0,
+            parent: None,
+            children: 0,
+            forgotten: None,
+        },
+    );
+    next_key += 1;
+
+    // Removes a leaf and, when that leaves its parent without children, either queues the parent
+    // again or, if nothing below it is worth another look, removes it too.
+    fn remove_leaf<T: State>(
+        key: usize,
+        nodes: &mut HashMap<usize, MemoryNode<T>>,
+        in_memory: &mut StateMap<usize>,
+        queue: &mut BTreeSet<(T::Cost, Reverse<usize>, usize)>,
+        forget: bool,
+    ) where
+        T::Cost: Copy + Ord,
+    {
+        let Some(node) = nodes.remove(&key) else {
+            return;
+        };
+        let hash = node.state.canonical_hash();
+        if in_memory.get(&hash) == Some(&key) {
+            in_memory.remove(&hash);
+        }
+        let Some(parent_key) = node.parent else {
+            return;
+        };
+        let Some(parent) = nodes.get_mut(&parent_key) else {
+            return;
+        };
+        parent.children -= 1;
+        if forget && parent.forgotten.is_none_or(|forgotten| node.f < forgotten) {
+            parent.forgotten = Some(node.f);
+        }
+        if parent.children == 0 {
+            match parent.forgotten {
+                Some(forgotten) => {
+                    parent.f = forgotten;
+                    queue.insert((forgotten, Reverse(parent.depth), parent_key));
+                }
+                None => remove_leaf(parent_key, nodes, in_memory, queue, false),
+            }
+        }
+    }
+
+    let mut successors = Vec::new();
+    while let Some(entry) = queue.pop_first() {
+        let (_, _, key) = entry;
+
+        if nodes[&key].state.is_goal() {
+            stats.open = queue.len();
+            stats.seen = nodes.len();
+            let goal = nodes.remove(&key).map(|node| node.state);
+            return (goal, stats);
+        }
+
+        if cancel.is_some_and(CancellationToken::is_cancelled) {
+            stats.cancelled = true;
+            break;
+        }
+
+        if stats.expanded.is_multiple_of(1024)
+            && deadline.is_some_and(|deadline| Instant::now() > deadline)
+        {
+            stats.timed_out = true;
+            break;
+        }
+
+        let node = &nodes[&key];
+        if node.state.cost() >= max_cost {
+            remove_leaf(key, &mut nodes, &mut in_memory, &mut queue, false);
+            continue;
+        }
 
-        self_cost.partial_cmp(&other_cost).unwrap()
+        stats.expanded += 1;
+        if stats.expanded.is_multiple_of(PROGRESS_INTERVAL) {
+            debug!(
+                expanded = stats.expanded,
+                f = ?node.f,
+                open = queue.len(),
+                dropped,
+                "search progress"
+            );
+        }
+
+        let (parent_f, depth) = (node.f, node.depth + 1);
+        let mut children = vec![];
+        node.state.successors_into(&mut successors);
+        for successor in successors.drain(..) {
+            stats.generated += 1;
+            let fingerprint = successor.canonical_hash();
+            let known = in_memory
+                .get(&fingerprint)
+                .is_some_and(|other| nodes[other].state.cost() <= successor.cost());
+            if known {
+                stats.duplicates += 1;
+                continue;
+            }
+            let f = successor.cost() + successor.distance_to_goal();
+            children.push((
+                fingerprint,
+                successor,
+                if f < parent_f { parent_f } else { f },
+            ));
+        }
+
+        let node = nodes
+            .get_mut(&key)
+            .expect("the expanded state is in memory");
+        node.children = children.len();
+        node.forgotten = None;
+        if children.is_empty() {
+            remove_leaf(key, &mut nodes, &mut in_memory, &mut queue, false);
+            continue;
+        }
+        for (fingerprint, state, f) in children {
+            in_memory.insert(fingerprint, next_key);
+            queue.insert((f, Reverse(depth), next_key));
+            nodes.insert(
+                next_key,
+                MemoryNode {
+                    state,
+                    f,
+                    depth,
+                    parent: Some(key),
+                    children: 0,
+                    forgotten: None,
+                },
+            );
+            next_key += 1;
+        }
+        stats.peak_open = stats.peak_open.max(queue.len());
+
+        while nodes.len() > max_nodes {
+            // The last entry is the highest f and, among those, the shallowest.
+            let Some(&worst) = queue.last() else {
+                break;
+            };
+            if nodes[&worst.2].parent == Some(key) && nodes[&key].children == 1 {
+                stats.limit_reached = true;
+                break;
+            }
+            queue.remove(&worst);
+            remove_leaf(worst.2, &mut nodes, &mut in_memory, &mut queue, true);
+            dropped += 1;
+        }
+        if stats.limit_reached {
+            break;
+        }
     }
+
+    debug!(dropped, "memory-bounded search finished");
+    stats.open = queue.len();
+    stats.seen = nodes.len();
+    (None, stats)
 }
 
-pub fn astar<T: State>(initial_state: T, max_cost: T::Cost) -> Option<T> {
+/// Anytime A*: reports the first goal it reaches through `on_solution`, then keeps searching for
+/// cheaper ones until the open set is exhausted, the deadline passes, or `cancel` is cancelled.
+/// Each goal reported is cheaper than the last, and states that cannot beat the best goal so far
+/// are pruned. With a state whose heuristic is weighted above 1, the first goal comes quickly and
+/// the rest of the search improves on it. Returns the cheapest goal found.
+pub fn anytime<T: State>(
+    initial_state: T,
+    max_cost: T::Cost,
+    deadline: Option<Instant>,
+    cancel: Option<&CancellationToken>,
+    mut on_solution: impl FnMut(&T, &SearchStats),
+) -> (Option<T>, SearchStats)
+where
+    T::Cost: Copy,
+{
+    let mut stats = SearchStats::default();
+    let mut bound = max_cost;
+    let mut best = None;
+    // The cheapest cost each state has been reached with; a state is opened again when it is
+    // reached more cheaply, since a cheaper path can lead to a cheaper goal.
+    let mut best_costs = StateMap::default();
+    let mut sequence = 0;
     let mut open_set = BinaryHeap::new();
-    open_set.push(Reverse(StateContainer::new(initial_state)));
-    let mut seen = HashSet::new();
+    let initial_id = initial_state.canonical_hash();
+    best_costs.insert(initial_id, initial_state.cost());
+    open_set.push(Reverse(
+        StateContainer::new(initial_state, initial_id, None).ordered(TieBreak::Fifo, sequence),
+    ));
 
+    let mut successors = Vec::new();
     while let Some(reversed_state) = open_set.pop() {
         let state = reversed_state.0.state;
+        if state.cost() >= bound
+            || best_costs
+                .get(&reversed_state.0.id)
+                .is_some_and(|cost| *cost < state.cost())
+        {
+            continue;
+        }
+
+        if state.is_goal() {
+            bound = state.cost();
+            debug!(cost = ?bound, expanded = stats.expanded, "found a better solution");
+            on_solution(&state, &stats);
+            best = Some(state);
+            continue;
+        }
+
+        if cancel.is_some_and(CancellationToken::is_cancelled) {
+            stats.cancelled = true;
+            break;
+        }
+
+        if stats.expanded.is_multiple_of(1024)
+            && deadline.is_some_and(|deadline| Instant::now() > deadline)
+        {
+            stats.timed_out = true;
+            break;
+        }
+
+        stats.expanded += 1;
+        if stats.expanded.is_multiple_of(PROGRESS_INTERVAL) {
+            debug!(
+                expanded = stats.expanded,
+                f = ?reversed_state.0.priority,
+                open = open_set.len(),
+                "search progress"
+            );
+        }
+
+        state.successors_into(&mut successors);
+        for successor in successors.drain(..) {
+            stats.generated += 1;
+            let fingerprint = successor.canonical_hash();
+            let cost = successor.cost();
+            let improves = cost < bound
+                && best_costs
+                    .get(&fingerprint)
+                    .is_none_or(|best_cost| cost < *best_cost);
+            if improves {
+                best_costs.insert(fingerprint, cost);
+                sequence += 1;
+                open_set.push(Reverse(
+                    StateContainer::new(successor, fingerprint, None)
+                        .ordered(TieBreak::Fifo, sequence),
+                ));
+            } else {
+                stats.duplicates += 1;
+            }
+        }
+        stats.peak_open = stats.peak_open.max(open_set.len());
+    }
+
+    stats.open = open_set.len();
+    stats.seen = best_costs.len();
+    (best, stats)
+}
+
+enum Deepening<T: State> {
+    Found(T),
+    // The lowest f beyond the threshold below the state, or `None` if there was none. `exact` is
+    // false when part of the subtree was skipped for a reason tied to the current path or the
+    // cost limit, so the result says nothing about the state on another path.
+    CutOff { next: Option<T::Cost>, exact: bool },
+}
+
+struct Iteration<'a, T: State> {
+    threshold: T::Cost,
+    max_cost: T::Cost,
+    // The moves each position is known to still need, or `None` if it can never reach a goal.
+    table: &'a mut StateMap<Option<T::Cost>>,
+    table_size: usize,
+    path: Vec<u64>,
+    stats: &'a mut SearchStats,
+    deadline: Option<Instant>,
+    cancel: Option<&'a CancellationToken>,
+}
+
+impl<T: State> Iteration<'_, T>
+where
+    T::Cost: Copy,
+{
+    fn search(&mut self, state: T) -> Deepening<T> {
+        let id = state.canonical_hash();
+        let cost = state.cost();
+        let mut f = cost + state.distance_to_goal();
+        match self.table.get(&id) {
+            Some(None) => {
+                self.stats.duplicates += 1;
+                return Deepening::CutOff {
+                    next: None,
+                    exact: true,
+                };
+            }
+            Some(Some(needed)) if cost + *needed > f => f = cost + *needed,
+            _ => {}
+        }
+        if f > self.threshold {
+            return Deepening::CutOff {
+                next: Some(f),
+                exact: true,
+            };
+        }
 
         if state.is_goal() {
-            return Some(state);
+            return Deepening::Found(state);
+        }
+
+        if cost >= self.max_cost {
+            self.stats.cost_cut_off += 1;
+            return Deepening::CutOff {
+                next: None,
+                exact: false,
+            };
+        }
+
+        if self.cancel.is_some_and(CancellationToken::is_cancelled) {
+            self.stats.cancelled = true;
+        } else if self.stats.expanded.is_multiple_of(1024)
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() > deadline)
+        {
+            self.stats.timed_out = true;
+        }
+        if self.stats.cancelled || self.stats.timed_out {
+            return Deepening::CutOff {
+                next: None,
+                exact: false,
+            };
+        }
+
+        self.stats.expanded += 1;
+        if self.stats.expanded.is_multiple_of(PROGRESS_INTERVAL) {
+            debug!(
+                expanded = self.stats.expanded,
+                threshold = ?self.threshold,
+                depth = self.path.len(),
+                table = self.table.len(),
+                "search progress"
+            );
+        }
+
+        self.path.push(id);
+        let mut next: Option<T::Cost> = None;
+        let mut exact = true;
+        for successor in state.successors() {
+            self.stats.generated += 1;
+            if self.path.contains(&successor.canonical_hash()) {
+                self.stats.duplicates += 1;
+                exact = false;
+                continue;
+            }
+            match self.search(successor) {
+                Deepening::Found(goal) => {
+                    self.path.pop();
+                    return Deepening::Found(goal);
+                }
+                Deepening::CutOff {
+                    next: below,
+                    exact: below_exact,
+                } => {
+                    if let Some(below) = below {
+                        if next.is_none_or(|next| below < next) {
+                            next = Some(below);
+                        }
+                    }
+                    exact &= below_exact;
+                }
+            }
+            if self.stats.cancelled || self.stats.timed_out {
+                exact = false;
+                break;
+            }
+        }
+        self.stats.peak_open = self.stats.peak_open.max(self.path.len());
+        self.path.pop();
+
+        // Every path from here to a goal passes through one of the states cut off below, so the
+        // lowest f among them bounds the cost to reach a goal from here.
+        if exact && (self.table.len() < self.table_size || self.table.contains_key(&id)) {
+            let needed = next.map(|next| next - cost);
+            let entry = self.table.entry(id).or_insert(needed);
+            if let (Some(known), Some(needed)) = (*entry, needed) {
+                if needed > known {
+                    *entry = Some(needed);
+                }
+            }
+        }
+        Deepening::CutOff { next, exact }
+    }
+}
+
+/// Iterative-deepening A* (IDA*): depth-first searches that cut off states whose f exceeds a
+/// threshold, starting at the initial state's f and raising it each round to the lowest f that
+/// was cut off. Memory use is the length of the path plus a transposition table of at most
+/// `table_size` positions that is shared between rounds. Once a round has searched everything
+/// below a position, the table records the lowest f that was cut off there, relative to the
+/// position, and later rounds use it in place of the heuristic when it is higher, so they skip
+/// subtrees earlier rounds already proved too deep. With a `table_size` of 0 this is plain IDA*.
+/// The goal is optimal when the heuristic never overestimates. `stats.peak_open` is the deepest
+/// path, and `stats.seen` the positions in the table.
+pub fn ida_star<T: State + Clone>(
+    initial_state: T,
+    max_cost: T::Cost,
+    table_size: usize,
+    deadline: Option<Instant>,
+    cancel: Option<&CancellationToken>,
+) -> (Option<T>, SearchStats)
+where
+    T::Cost: Copy,
+{
+    let mut stats = SearchStats::default();
+    let mut table = StateMap::default();
+    let mut threshold = initial_state.cost() + initial_state.distance_to_goal();
+
+    loop {
+        debug!(threshold = ?threshold, table = table.len(), "deepening");
+        let mut iteration = Iteration {
+            threshold,
+            max_cost,
+            table: &mut table,
+            table_size,
+            path: vec![],
+            stats: &mut stats,
+            deadline,
+            cancel,
+        };
+        match iteration.search(initial_state.clone()) {
+            Deepening::Found(goal) => {
+                stats.seen = table.len();
+                return (Some(goal), stats);
+            }
+            Deepening::CutOff {
+                next: Some(next), ..
+            } if !stats.cancelled && !stats.timed_out => {
+                threshold = next;
+            }
+            Deepening::CutOff { .. } => break,
+        }
+    }
+
+    stats.seen = table.len();
+    (None, stats)
+}
+
+/// Every goal state of minimal cost, found by expanding in order of cost alone.
+pub fn all_optimal<T: State>(initial_state: T, max_cost: T::Cost) -> Vec<T>
+where
+    T::Cost: Copy,
+{
+    let mut open_set = BinaryHeap::new();
+    let mut best_costs = StateMap::default();
+    let initial_cost = initial_state.cost();
+    let initial_id = initial_state.canonical_hash();
+    best_costs.insert(initial_id, initial_cost);
+    open_set.push(Reverse(StateContainer::with_priority(
+        initial_state,
+        initial_cost,
+        initial_id,
+        None,
+    )));
+    let mut goals = vec![];
+
+    let mut successors = Vec::new();
+    while let Some(reversed_state) = open_set.pop() {
+        let state = reversed_state.0.state;
+
+        if let Some(goal) = goals.first() {
+            if state.cost() > T::cost(goal) {
+                break;
+            }
+        }
+
+        if state.is_goal() {
+            goals.push(state);
+            continue;
         }
 
         if state.cost() < max_cost {
-            for successor in state.successors() {
-                let fingerprint = hash(&successor);
+            state.successors_into(&mut successors);
+            for successor in successors.drain(..) {
+                let fingerprint = successor.canonical_hash();
+                let cost = successor.cost();
 
-                if !seen.contains(&fingerprint) {
-                    open_set.push(Reverse(StateContainer::new(successor)));
-                    seen.insert(fingerprint);
+                match best_costs.get(&fingerprint) {
+                    Some(best_cost) if cost > *best_cost => {}
+                    _ => {
+                        best_costs.insert(fingerprint, cost);
+                        open_set.push(Reverse(StateContainer::with_priority(
+                            successor,
+                            cost,
+                            fingerprint,
+                            None,
+                        )));
+                    }
                 }
             }
         }
     }
 
-    None
+    goals
+}
+
+/// The number of distinct move sequences of at most `max_depth` moves that reach a goal. A
+/// sequence ends at the first goal it reaches.
+pub fn count_solutions<T: State>(initial_state: T, max_depth: usize) -> u64 {
+    fn count<T: State>(
+        state: &T,
+        depth: usize,
+        memo: &mut HashMap<(u64, usize), u64, StateHasher>,
+    ) -> u64 {
+        if state.is_goal() {
+            return 1;
+        }
+
+        if depth == 0 {
+            return 0;
+        }
+
+        let key = (state.canonical_hash(), depth);
+        if let Some(total) = memo.get(&key) {
+            return *total;
+        }
+
+        let total = state
+            .successors()
+            .iter()
+            .map(|successor| count(successor, depth - 1, memo))
+            .fold(0u64, |total, n| total.saturating_add(n));
+        memo.insert(key, total);
+
+        total
+    }
+
+    count(&initial_state, max_depth, &mut HashMap::default())
+}
+
+/// When [`shortest_path_observed`] gives up early. [`Frontier`] holds the same limits for
+/// [`astar_from`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SearchLimits<'a> {
+    pub deadline: Option<Instant>,
+    pub cancel: Option<&'a CancellationToken>,
+    /// Stop once this many states have been expanded.
+    pub max_expanded: Option<usize>,
+    /// Stop once the open set and the table of reached states are estimated to hold more than
+    /// this many bytes.
+    pub max_memory: Option<usize>,
+}
+
+/// A cheapest state that satisfies `is_target`, found by expanding in order of cost alone.
+/// Among equally cheap paths it keeps the one with the lowest [`State::secondary_cost`].
+pub fn shortest_path<T: State>(
+    initial_state: T,
+    max_cost: T::Cost,
+    is_target: impl Fn(&T) -> bool,
+) -> (Option<T>, SearchStats)
+where
+    T::Cost: Copy,
+{
+    shortest_path_observed(
+        initial_state,
+        max_cost,
+        SearchLimits::default(),
+        is_target,
+        |_| {},
+    )
+}
+
+/// Like [`shortest_path`], but calls `on_expand` with each state it expands and stops early as
+/// `limits` say. Every state is expanded at the lowest cost it can be reached with.
+pub fn shortest_path_observed<T: State>(
+    initial_state: T,
+    max_cost: T::Cost,
+    limits: SearchLimits,
+    is_target: impl Fn(&T) -> bool,
+    mut on_expand: impl FnMut(&T),
+) -> (Option<T>, SearchStats)
+where
+    T::Cost: Copy,
+{
+    let mut stats = SearchStats::default();
+    let mut open_set = BinaryHeap::new();
+    // The best (cost, secondary cost) each state has been reached with; a state is queued again
+    // only when it is reached with the same cost and a lower secondary cost.
+    let mut best = StateMap::default();
+    let initial_cost = initial_state.cost();
+    let initial_id = hash(&initial_state);
+    best.insert(initial_id, (initial_cost, initial_state.secondary_cost()));
+    open_set.push(Reverse(StateContainer::with_priority(
+        initial_state,
+        initial_cost,
+        initial_id,
+        None,
+    )));
+
+    let mut successors = Vec::new();
+    while let Some(reversed_state) = open_set.pop() {
+        let id = reversed_state.0.id;
+        let state = reversed_state.0.state;
+        if best.get(&id) != Some(&(state.cost(), state.secondary_cost())) {
+            continue;
+        }
+
+        if is_target(&state) {
+            stats.open = open_set.len();
+            stats.seen = best.len();
+            return (Some(state), stats);
+        }
+
+        if limits.cancel.is_some_and(CancellationToken::is_cancelled) {
+            stats.cancelled = true;
+            break;
+        }
+
+        if limits
+            .max_expanded
+            .is_some_and(|max_expanded| stats.expanded >= max_expanded)
+        {
+            stats.limit_reached = true;
+            break;
+        }
+
+        if stats.expanded.is_multiple_of(1024) {
+            if limits
+                .deadline
+                .is_some_and(|deadline| Instant::now() > deadline)
+            {
+                stats.timed_out = true;
+                break;
+            }
+
+            let memory = open_set.len()
+                * (std::mem::size_of::<Reverse<StateContainer<T>>>() + state.allocated_bytes())
+                + best.capacity() * std::mem::size_of::<(u64, (T::Cost, usize))>();
+            stats.peak_memory = stats.peak_memory.max(memory);
+            if limits
+                .max_memory
+                .is_some_and(|max_memory| memory > max_memory)
+            {
+                stats.limit_reached = true;
+                break;
+            }
+        }
+
+        if state.cost() < max_cost {
+            stats.expanded += 1;
+            if stats.expanded.is_multiple_of(PROGRESS_INTERVAL) {
+                debug!(
+                    expanded = stats.expanded,
+                    g = ?state.cost(),
+                    open = open_set.len(),
+                    "search progress"
+                );
+            }
+            on_expand(&state);
+
+            state.successors_into(&mut successors);
+            for successor in successors.drain(..) {
+                stats.generated += 1;
+                let fingerprint = hash(&successor);
+                let cost = successor.cost();
+                let secondary = successor.secondary_cost();
+                let improves = match best.get(&fingerprint) {
+                    Some(&(best_cost, best_secondary)) => {
+                        cost == best_cost && secondary < best_secondary
+                    }
+                    None => true,
+                };
+                if improves {
+                    best.insert(fingerprint, (cost, secondary));
+                    open_set.push(Reverse(StateContainer::with_priority(
+                        successor,
+                        cost,
+                        fingerprint,
+                        None,
+                    )));
+                } else {
+                    stats.duplicates += 1;
+                }
+            }
+            stats.peak_open = stats.peak_open.max(open_set.len());
+        } else {
+            stats.cost_cut_off += 1;
+        }
+    }
+
+    stats.open = open_set.len();
+    stats.seen = best.len();
+    stats.exhausted =
+        stats.cost_cut_off == 0 && !stats.timed_out && !stats.cancelled && !stats.limit_reached;
+    (None, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Walks along the integers from 0 to 10 in steps of +2, +1, or -1.
+    #[derive(Clone, Hash)]
+    struct Walk {
+        value: i32,
+        steps: i32,
+    }
+
+    impl State for Walk {
+        type Cost = i32;
+
+        fn successors(&self) -> Vec<Self> {
+            [2, 1, -1]
+                .map(|step| Walk {
+                    value: self.value + step,
+                    steps: self.steps + 1,
+                })
+                .into()
+        }
+        fn is_goal(&self) -> bool {
+            self.value == 10
+        }
+        fn distance_to_goal(&self) -> i32 {
+            (10 - self.value + 1).max(0) / 2
+        }
+        fn cost(&self) -> i32 {
+            self.steps
+        }
+        fn canonical_hash(&self) -> u64 {
+            hash(&self.value)
+        }
+        fn depth(&self) -> usize {
+            self.steps as usize
+        }
+    }
+
+    #[test]
+    fn test_astar_reports_depth_and_cost_limits_separately() {
+        let search = |max_cost, max_depth| {
+            let mut frontier = Frontier::start(Walk { value: 0, steps: 0 });
+            frontier.max_depth = max_depth;
+            astar_from(
+                &mut frontier,
+                max_cost,
+                None,
+                None,
+                |_, _, _| {},
+                None,
+                |_, _, _| {},
+            )
+        };
+
+        let (goal, stats) = search(20, Some(4));
+        assert!(goal.is_none());
+        assert!(stats.depth_cut_off > 0);
+        assert_eq!(stats.cost_cut_off, 0);
+
+        let (goal, stats) = search(4, None);
+        assert!(goal.is_none());
+        assert!(stats.cost_cut_off > 0);
+        assert_eq!(stats.depth_cut_off, 0);
+
+        let (goal, _) = search(20, Some(5));
+        assert_eq!(goal.map(|goal| goal.steps), Some(5));
+    }
+
+    #[test]
+    fn test_astar_sends_progress_reports() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut frontier = Frontier::start(Walk { value: 0, steps: 0 });
+        frontier.progress = Some(sender);
+        frontier.progress_interval = Duration::ZERO;
+        let (goal, _) = astar_from(
+            &mut frontier,
+            20,
+            None,
+            None,
+            |_, _, _| {},
+            None,
+            |_, _, _| {},
+        );
+        assert!(goal.is_some());
+        drop(frontier);
+
+        let reports: Vec<Progress> = receiver.iter().collect();
+        assert!(!reports.is_empty());
+        assert_eq!(reports[0].expanded, 0);
+        assert_eq!(reports[0].depth, 0);
+    }
+
+    // Steps right or up from the origin to (3, 3), so every state on the way has the same f.
+    #[derive(Hash)]
+    struct Grid(i32, i32);
+
+    impl State for Grid {
+        type Cost = i32;
+
+        fn successors(&self) -> Vec<Self> {
+            vec![Grid(self.0 + 1, self.1), Grid(self.0, self.1 + 1)]
+        }
+        fn is_goal(&self) -> bool {
+            (self.0, self.1) == (3, 3)
+        }
+        fn distance_to_goal(&self) -> i32 {
+            (3 - self.0).abs() + (3 - self.1).abs()
+        }
+        fn cost(&self) -> i32 {
+            self.0 + self.1
+        }
+    }
+
+    #[test]
+    fn test_lower_estimate_reaches_the_goal_first_among_equal_f() {
+        let expanded = |tie_break| {
+            let (goal, stats) = astar(Grid(0, 0), 20, None, None, tie_break, |_, _, _| {});
+            assert_eq!(goal.map(|goal| goal.cost()), Some(6));
+            stats.expanded
+        };
+
+        assert_eq!(TieBreak::default(), TieBreak::LowerEstimate);
+        assert_eq!(expanded(TieBreak::LowerEstimate), 6);
+        assert!(expanded(TieBreak::Fifo) > 6);
+    }
+
+    #[test]
+    fn test_bucket_queue_orders_by_f_and_then_estimate() {
+        let search = |start, tie_break| {
+            let mut frontier = Frontier::start(start);
+            frontier.open_list = OpenList::Buckets;
+            frontier.tie_break = tie_break;
+            astar_from(
+                &mut frontier,
+                20,
+                None,
+                None,
+                |_, _, _| {},
+                None,
+                |_, _, _| {},
+            )
+        };
+
+        let (goal, stats) = search(Grid(0, 0), TieBreak::LowerEstimate);
+        assert_eq!(goal.map(|goal| goal.cost()), Some(6));
+        assert_eq!(stats.expanded, 6);
+        let (_, fifo) = search(Grid(0, 0), TieBreak::Fifo);
+        assert!(fifo.expanded > 6);
+
+        let mut frontier = Frontier::start(Walk { value: 0, steps: 0 });
+        frontier.open_list = OpenList::Buckets;
+        let (goal, _) = astar_from(
+            &mut frontier,
+            20,
+            None,
+            None,
+            |_, _, _| {},
+            None,
+            |_, _, _| {},
+        );
+        assert_eq!(goal.map(|goal| goal.steps), Some(5));
+    }
+
+    #[test]
+    fn test_sma_star_finds_the_solution_within_a_memory_budget() {
+        let start = || Walk { value: 0, steps: 0 };
+
+        let (goal, stats) = sma_star(start(), 20, 1000, None, None);
+        assert_eq!(goal.map(|goal| goal.steps), Some(5));
+        assert!(stats.seen <= 1000);
+
+        let (goal, stats) = sma_star(start(), 20, 6, None, None);
+        assert_eq!(goal.map(|goal| goal.steps), Some(5));
+        assert!(stats.seen <= 6);
+
+        let (goal, stats) = sma_star(start(), 20, 3, None, None);
+        assert!(goal.is_none());
+        assert!(stats.limit_reached);
+
+        let (goal, stats) = sma_star(start(), 4, 1000, None, None);
+        assert!(goal.is_none());
+        assert!(!stats.limit_reached);
+    }
+
+    // A graph whose heuristic leads to the long way round from 0 to 9 before the short one.
+    #[derive(Hash)]
+    struct Detour {
+        node: usize,
+        steps: i32,
+    }
+
+    impl State for Detour {
+        type Cost = i32;
+
+        fn successors(&self) -> Vec<Self> {
+            let next: &[usize] = match self.node {
+                0 => &[1, 5],
+                1 => &[2],
+                2 => &[3],
+                3 | 5 => &[9],
+                _ => &[],
+            };
+            next.iter()
+                .map(|&node| Detour {
+                    node,
+                    steps: self.steps + 1,
+                })
+                .collect()
+        }
+        fn is_goal(&self) -> bool {
+            self.node == 9
+        }
+        fn distance_to_goal(&self) -> i32 {
+            match self.node {
+                9 => 0,
+                5 => 5,
+                _ => 1,
+            }
+        }
+        fn cost(&self) -> i32 {
+            self.steps
+        }
+        fn canonical_hash(&self) -> u64 {
+            hash(&self.node)
+        }
+    }
+
+    #[test]
+    fn test_anytime_reports_improving_solutions() {
+        let start = Detour { node: 0, steps: 0 };
+        let mut found = vec![];
+        let (goal, stats) = anytime(start, 10, None, None, |goal, _| found.push(goal.cost()));
+
+        assert_eq!(found, vec![4, 2]);
+        assert_eq!(goal.map(|goal| goal.cost()), Some(2));
+        assert!(!stats.timed_out);
+    }
+
+    // A walk with no heuristic, so IDA* needs a round for every step.
+    #[derive(Clone, Hash)]
+    struct Blind(Walk);
+
+    impl State for Blind {
+        type Cost = i32;
+
+        fn successors(&self) -> Vec<Self> {
+            self.0.successors().into_iter().map(Blind).collect()
+        }
+        fn is_goal(&self) -> bool {
+            self.0.is_goal()
+        }
+        fn distance_to_goal(&self) -> i32 {
+            0
+        }
+        fn cost(&self) -> i32 {
+            self.0.cost()
+        }
+        fn canonical_hash(&self) -> u64 {
+            self.0.canonical_hash()
+        }
+    }
+
+    #[test]
+    fn test_ida_star_shares_its_table_between_rounds() {
+        let start = Blind(Walk { value: 0, steps: 0 });
+        let (plain, plain_stats) = ida_star(start.clone(), 20, 0, None, None);
+        let (shared, shared_stats) = ida_star(start.clone(), 20, 1000, None, None);
+        assert_eq!(plain.map(|goal| goal.0.steps), Some(5));
+        assert_eq!(shared.map(|goal| goal.0.steps), Some(5));
+        assert_eq!(plain_stats.seen, 0);
+        assert!(shared_stats.seen > 0);
+        assert!(shared_stats.expanded < plain_stats.expanded);
+
+        let (limited, limited_stats) = ida_star(start.clone(), 20, 3, None, None);
+        assert_eq!(limited.map(|goal| goal.0.steps), Some(5));
+        assert!(limited_stats.seen <= 3);
+
+        let (goal, stats) = ida_star(start, 4, 1000, None, None);
+        assert!(goal.is_none());
+        assert!(stats.cost_cut_off > 0);
+    }
+
+    #[test]
+    fn test_effective_branching_factor() {
+        assert!((effective_branching_factor(14, 3) - 2.0).abs() < 1e-9);
+        assert!((effective_branching_factor(5, 1) - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stable_hash_is_fnv_1a() {
+        // FNV-1a of the bytes of "a" and then the 0xff `str` adds to mark its end.
+        assert_eq!(stable_hash(&"a"), 0x089bc907b544c769);
+        assert_eq!(stable_hash(&1u32), stable_hash(&1i32));
+        assert_ne!(stable_hash(&1u32), stable_hash(&1u64));
+    }
 }
@@ -0,0 +1,365 @@
+use serde::Serialize;
+use serde_json::json;
+use solver_of_squares::game::Color;
+use solver_of_squares::parse::parse_game;
+use solver_of_squares::solver::SolverOptions;
+use std::fmt::Write;
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{info, warn};
+
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+// Upper bounds, in seconds, of the solve latency histogram's buckets.
+const LATENCY_BUCKETS: [f64; 9] = [0.001, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0];
+
+#[derive(Clone, Copy)]
+pub struct Limits {
+    pub max_moves: i32,
+    pub timeout: Duration,
+    pub max_expanded: Option<usize>,
+    pub max_memory: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct Solution {
+    solved: bool,
+    timed_out: bool,
+    /// The search hit `max_expanded` or `max_memory`.
+    limit_reached: bool,
+    moves: Option<Vec<Color>>,
+    expanded: usize,
+    peak_memory: usize,
+    seconds: f64,
+}
+
+// Counters for `GET /metrics`, shared by every worker. A solve counts as failed when it ended
+// without a solution for any reason other than its timeout.
+#[derive(Default)]
+struct Metrics {
+    solves: AtomicU64,
+    failures: AtomicU64,
+    timeouts: AtomicU64,
+    expanded: AtomicU64,
+    // How many solves took at most each bucket's bound, not counting earlier buckets, then the
+    // rest. The sum is in microseconds.
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    latency_micros: AtomicU64,
+}
+
+impl Metrics {
+    fn record_solve(&self, solved: bool, timed_out: bool, expanded: usize, latency: Duration) {
+        self.solves.fetch_add(1, Ordering::Relaxed);
+        if timed_out {
+            self.timeouts.fetch_add(1, Ordering::Relaxed);
+        } else if !solved {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        self.expanded.fetch_add(expanded as u64, Ordering::Relaxed);
+        let seconds = latency.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.latency_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.latency_micros
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    // The Prometheus text exposition format.
+    fn render(&self) -> String {
+        let mut text = String::new();
+        for (name, help, counter) in [
+            (
+                "solver_solves_total",
+                "Solve requests that ran a search.",
+                &self.solves,
+            ),
+            (
+                "solver_solve_failures_total",
+                "Searches that ended without a solution before their timeout.",
+                &self.failures,
+            ),
+            (
+                "solver_solve_timeouts_total",
+                "Searches stopped by their timeout.",
+                &self.timeouts,
+            ),
+            (
+                "solver_nodes_expanded_total",
+                "Positions expanded by every search.",
+                &self.expanded,
+            ),
+        ] {
+            let _ = write!(
+                text,
+                "# HELP {name} {help}\n# TYPE {name} counter\n{name} {}\n",
+                counter.load(Ordering::Relaxed)
+            );
+        }
+
+        let name = "solver_solve_duration_seconds";
+        let _ = write!(
+            text,
+            "# HELP {name} Time spent solving, from parsing the puzzle to the result.\n\
+             # TYPE {name} histogram\n"
+        );
+        let mut count = 0;
+        for (i, bucket) in self.latency_buckets.iter().enumerate() {
+            count += bucket.load(Ordering::Relaxed);
+            let bound = LATENCY_BUCKETS
+                .get(i)
+                .map_or(String::from("+Inf"), |bound| bound.to_string());
+            let _ = writeln!(text, "{name}_bucket{{le=\"{bound}\"}} {count}");
+        }
+        let sum = self.latency_micros.load(Ordering::Relaxed) as f64 / 1e6;
+        let _ = write!(text, "{name}_sum {sum}\n{name}_count {count}\n");
+        text
+    }
+}
+
+pub fn run(address: &str, workers: usize, limits: Limits) -> io::Result<()> {
+    let server = Arc::new(Server::http(address).map_err(io::Error::other)?);
+    let metrics = Arc::new(Metrics::default());
+    info!(address, workers, "listening");
+
+    let handles: Vec<_> = (0..workers)
+        .map(|_| {
+            let server = Arc::clone(&server);
+            let metrics = Arc::clone(&metrics);
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    respond(request, &limits, &metrics);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        let _ = handle.join();
+    }
+
+    Ok(())
+}
+
+fn respond(mut request: Request, limits: &Limits, metrics: &Metrics) {
+    let (status, body) = if request.body_length().unwrap_or(0) as u64 > MAX_BODY_BYTES {
+        (413, json!({ "error": "puzzle too large" }).to_string())
+    } else {
+        let mut body = String::new();
+        match request
+            .as_reader()
+            .take(MAX_BODY_BYTES + 1)
+            .read_to_string(&mut body)
+        {
+            Ok(_) if body.len() as u64 > MAX_BODY_BYTES => {
+                (413, json!({ "error": "puzzle too large" }).to_string())
+            }
+            Ok(_) => handle(request.method(), request.url(), &body, limits, metrics),
+            Err(error) => (400, json!({ "error": error.to_string() }).to_string()),
+        }
+    };
+
+    info!(method = %request.method(), url = request.url(), status, "request");
+    let content_type = match request.url().split('?').next() {
+        Some("/metrics") if status == 200 => "text/plain; version=0.0.4",
+        _ => "application/json",
+    };
+    let content_type = Header::from_bytes("Content-Type", content_type).unwrap();
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+    if let Err(error) = request.respond(response) {
+        warn!(%error, "could not send response");
+    }
+}
+
+fn handle(
+    method: &Method,
+    url: &str,
+    body: &str,
+    limits: &Limits,
+    metrics: &Metrics,
+) -> (u16, String) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    match (method, path) {
+        (Method::Post, "/solve") => solve(body, query, limits, metrics),
+        (Method::Post, "/validate") => validate(body),
+        (Method::Get, "/metrics") => (200, metrics.render()),
+        (_, "/solve" | "/validate") => (405, json!({ "error": "use POST" }).to_string()),
+        (_, "/metrics") => (405, json!({ "error": "use GET" }).to_string()),
+        _ => (404, json!({ "error": "not found" }).to_string()),
+    }
+}
+
+fn solve(body: &str, query: &str, limits: &Limits, metrics: &Metrics) -> (u16, String) {
+    let mut max_moves = limits.max_moves;
+    let mut timeout = limits.timeout;
+    let (mut max_expanded, mut max_memory) = (limits.max_expanded, limits.max_memory);
+    // A request may only lower a quota, or set one the server leaves open.
+    let lower = |limit: Option<usize>, value: usize| Some(limit.map_or(value, |l| l.min(value)));
+    for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        let (Some(key), Some(value)) = (percent_decode(key), percent_decode(value)) else {
+            return bad_option(key, value);
+        };
+        let (key, value) = (key.as_str(), value.as_str());
+        match key {
+            "max_moves" => match value.parse::<i32>() {
+                Ok(value) => max_moves = value.min(limits.max_moves),
+                Err(_) => return bad_option(key, value),
+            },
+            "max_expanded" => match value.parse::<usize>() {
+                Ok(value) => max_expanded = lower(max_expanded, value),
+                Err(_) => return bad_option(key, value),
+            },
+            "max_memory" => match value.parse::<usize>() {
+                Ok(value) => max_memory = lower(max_memory, value),
+                Err(_) => return bad_option(key, value),
+            },
+            "timeout" => match value.parse::<f64>().map(Duration::try_from_secs_f64) {
+                Ok(Ok(value)) if !value.is_zero() => timeout = value.min(limits.timeout),
+                _ => return bad_option(key, value),
+            },
+            _ => return bad_option(key, value),
+        }
+    }
+
+    let started = Instant::now();
+    let game = match parse_game(body) {
+        Ok(game) => game,
+        Err(error) => return (400, json!({ "error": error.to_string() }).to_string()),
+    };
+    let mut options = SolverOptions::new(max_moves).timeout(timeout);
+    options.max_expanded = max_expanded;
+    options.max_memory = max_memory;
+    let (moves, stats) = game.solve_with(&options);
+    metrics.record_solve(
+        moves.is_some(),
+        stats.timed_out,
+        stats.expanded,
+        started.elapsed(),
+    );
+    let solution = Solution {
+        solved: moves.is_some(),
+        timed_out: stats.timed_out,
+        limit_reached: stats.limit_reached,
+        moves,
+        expanded: stats.expanded,
+        peak_memory: stats.peak_memory,
+        seconds: stats.elapsed.as_secs_f64(),
+    };
+    (200, serde_json::to_string(&solution).unwrap_or_default())
+}
+
+fn validate(body: &str) -> (u16, String) {
+    match parse_game(body) {
+        Ok(game) => (
+            200,
+            json!({
+                "valid": true,
+                "blocks": game.colors().len(),
+                "arrows": game.arrows().len(),
+            })
+            .to_string(),
+        ),
+        Err(error) => (
+            400,
+            json!({ "valid": false, "error": error.to_string() }).to_string(),
+        ),
+    }
+}
+
+// A query string key or value with `+` and `%XX` escapes undone, or `None` if an escape is not
+// two hex digits or the bytes are not UTF-8.
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = vec![];
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = std::str::from_utf8(rest.get(..2)?).ok()?;
+                if !hex.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+                    return None;
+                }
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+                rest = &rest[2..];
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn bad_option(key: &str, value: &str) -> (u16, String) {
+    (
+        400,
+        json!({ "error": format!("invalid option {}={}", key, value) }).to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handle_routes() {
+        let limits = Limits {
+            max_moves: 10,
+            timeout: Duration::from_secs(1),
+            max_expanded: None,
+            max_memory: Some(1 << 20),
+        };
+        let puzzle = "blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n    goal: [2, 0]\n";
+        let metrics = Metrics::default();
+        let handle = |method, url, body| handle(method, url, body, &limits, &metrics);
+
+        let (status, body) = handle(&Method::Post, "/solve", puzzle);
+        assert_eq!(status, 200);
+        assert!(body.starts_with(
+            r#"{"solved":true,"timed_out":false,"limit_reached":false,"moves":["red","red"],"expanded":2,"peak_memory":"#
+        ));
+
+        let (status, body) = handle(&Method::Post, "/solve?max_expanded=1", puzzle);
+        assert_eq!(status, 200);
+        assert!(body.contains(r#""limit_reached":true"#));
+
+        let (status, body) = handle(&Method::Post, "/solve?max_moves=1", puzzle);
+        assert_eq!(status, 200);
+        assert!(body.contains(r#""solved":false"#));
+
+        assert_eq!(handle(&Method::Post, "/solve?depth=3", puzzle).0, 400);
+        assert_eq!(handle(&Method::Post, "/solve?timeout=0", puzzle).0, 400);
+        assert_eq!(handle(&Method::Post, "/solve?timeout=-1", puzzle).0, 400);
+        assert_eq!(handle(&Method::Post, "/solve?timeout=1e300", puzzle).0, 400);
+        assert_eq!(handle(&Method::Post, "/solve?timeout=inf", puzzle).0, 400);
+        assert_eq!(handle(&Method::Post, "/validate", puzzle).0, 200);
+        assert_eq!(handle(&Method::Post, "/validate", "foo: 1").0, 400);
+        assert_eq!(handle(&Method::Get, "/solve", "").0, 405);
+        assert_eq!(handle(&Method::Post, "/metrics", "").0, 405);
+        assert_eq!(handle(&Method::Post, "/", "").0, 404);
+
+        let (status, body) = handle(&Method::Get, "/metrics", "");
+        assert_eq!(status, 200);
+        assert!(body.contains("# TYPE solver_solves_total counter\nsolver_solves_total 3\n"));
+        assert!(body.contains("solver_solve_failures_total 2\n"));
+        assert!(body.contains("solver_solve_timeouts_total 0\n"));
+        assert!(body.contains("solver_nodes_expanded_total 4\n"));
+        assert!(body.contains("solver_solve_duration_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(body.contains("solver_solve_duration_seconds_count 3\n"));
+
+        let (status, body) = handle(&Method::Post, "/solve?timeout=1%2E5", puzzle);
+        assert_eq!(status, 200);
+        assert!(body.contains(r#""solved":true"#));
+        let (status, body) = handle(&Method::Post, "/solve?max%5Fmoves=1", puzzle);
+        assert_eq!(status, 200);
+        assert!(body.contains(r#""solved":false"#));
+        assert_eq!(handle(&Method::Post, "/solve?timeout=1%2", puzzle).0, 400);
+        assert_eq!(handle(&Method::Post, "/solve?timeout=%+1", puzzle).0, 400);
+        assert_eq!(handle(&Method::Post, "/solve?timeout=%FF", puzzle).0, 400);
+    }
+}
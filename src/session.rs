@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize};
+use solver_of_squares::game::{Color, Game};
+use solver_of_squares::search::{self, SearchStats};
+use solver_of_squares::solver::{AbortReason, SolveOutcome};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// One solve as written to a session log, with the puzzle itself so the solution can be checked
+/// again after the file has changed or gone.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// Seconds since the Unix epoch when the solve finished.
+    pub timestamp: u64,
+    pub path: PathBuf,
+    /// The hash of `puzzle`, to tell whether the file at `path` still holds the same puzzle.
+    pub puzzle_hash: u64,
+    pub puzzle: String,
+    pub options: Options,
+    pub outcome: Outcome,
+    pub moves: Option<Vec<Color>>,
+    pub stats: Stats,
+    pub seconds: f64,
+}
+
+/// The options that decide what a solve finds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Options {
+    pub algorithm: String,
+    pub max_moves: i32,
+    pub timeout: Option<f64>,
+    pub raise_bound: Option<i32>,
+    pub optimize: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Solved,
+    NoSolutionWithinBound,
+    ProvenUnsolvable,
+    TimedOut,
+    Cancelled,
+    LimitReached,
+}
+
+impl From<&SolveOutcome> for Outcome {
+    fn from(outcome: &SolveOutcome) -> Self {
+        match outcome {
+            SolveOutcome::Solved(_) => Outcome::Solved,
+            SolveOutcome::NoSolutionWithinBound => Outcome::NoSolutionWithinBound,
+            SolveOutcome::ProvenUnsolvable => Outcome::ProvenUnsolvable,
+            SolveOutcome::Aborted(AbortReason::TimedOut) => Outcome::TimedOut,
+            SolveOutcome::Aborted(AbortReason::Cancelled) => Outcome::Cancelled,
+            SolveOutcome::Aborted(AbortReason::LimitReached) => Outcome::LimitReached,
+        }
+    }
+}
+
+impl Outcome {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Outcome::Solved => "solved",
+            Outcome::NoSolutionWithinBound => "no solution within the bound",
+            Outcome::ProvenUnsolvable => "no solution exists",
+            Outcome::TimedOut => "timed out",
+            Outcome::Cancelled => "cancelled",
+            Outcome::LimitReached => "ran out of memory budget",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    pub expanded: usize,
+    pub generated: usize,
+    pub duplicates: usize,
+    pub peak_open: usize,
+    pub seen: usize,
+}
+
+impl From<&SearchStats> for Stats {
+    fn from(stats: &SearchStats) -> Self {
+        Stats {
+            expanded: stats.expanded,
+            generated: stats.generated,
+            duplicates: stats.duplicates,
+            peak_open: stats.peak_open,
+            seen: stats.seen,
+        }
+    }
+}
+
+impl Record {
+    pub fn new(
+        path: &Path,
+        game: &Game,
+        options: Options,
+        outcome: &SolveOutcome,
+        moves: Option<&[Color]>,
+        stats: &SearchStats,
+        elapsed: Duration,
+    ) -> Self {
+        let puzzle = game.to_yaml();
+        Record {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            path: path.to_path_buf(),
+            puzzle_hash: search::stable_hash(&puzzle),
+            puzzle,
+            options,
+            outcome: outcome.into(),
+            moves: moves.map(<[Color]>::to_vec),
+            stats: stats.into(),
+            seconds: elapsed.as_secs_f64(),
+        }
+    }
+}
+
+/// The file this run appends its records to, named for when the run started so that every run
+/// in the directory keeps its own log.
+pub struct SessionLog {
+    file: Mutex<File>,
+}
+
+impl SessionLog {
+    pub fn create(dir: &Path) -> io::Result<SessionLog> {
+        fs::create_dir_all(dir)?;
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let name = format!("session-{}-{}.jsonl", started, std::process::id());
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(name))?;
+        Ok(SessionLog {
+            file: Mutex::new(file),
+        })
+    }
+
+    // Each record is written whole, so lines from solves on different threads do not interleave.
+    pub fn append(&self, record: &Record) -> io::Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        let mut file = self
+            .file
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        file.write_all(line.as_bytes())
+    }
+}
+
+/// Reads the records of a session log, or of every `.jsonl` log in a directory, in the order the
+/// solves finished.
+pub fn read(path: &Path) -> Result<Vec<Record>, String> {
+    let files = if path.is_dir() {
+        let mut files: Vec<PathBuf> = fs::read_dir(path)
+            .map_err(|error| format!("could not read directory: {}", error))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| {
+                file.extension()
+                    .is_some_and(|extension| extension == "jsonl")
+            })
+            .collect();
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut records: Vec<Record> = vec![];
+    for file in files {
+        let reader = File::open(&file)
+            .map(BufReader::new)
+            .map_err(|error| format!("{}: could not open file: {}", file.display(), error))?;
+        for (number, line) in reader.lines().enumerate() {
+            let line =
+                line.map_err(|error| format!("{}: could not read: {}", file.display(), error))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str(&line)
+                .map_err(|error| format!("{}: line {}: {}", file.display(), number + 1, error))?;
+            records.push(record);
+        }
+    }
+    // Sessions that ran at the same time wrote to different files.
+    records.sort_by_key(|record| record.timestamp);
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solver_of_squares::game::Direction;
+
+    #[test]
+    fn test_session_logs_read_back_in_order() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Up, [0, 0], Some([0, 2]));
+        let (moves, stats) = game.solve_with_stats(10);
+        let outcome = game.outcome(moves.clone(), stats.clone());
+        let options = Options {
+            algorithm: String::from("astar"),
+            max_moves: 10,
+            timeout: None,
+            raise_bound: None,
+            optimize: None,
+        };
+        let record = Record::new(
+            Path::new("red.yaml"),
+            &game,
+            options,
+            &outcome,
+            moves.as_deref(),
+            &stats,
+            Duration::from_millis(5),
+        );
+        assert_eq!(record.outcome, Outcome::Solved);
+        assert_eq!(record.puzzle_hash, search::stable_hash(&game.to_yaml()));
+
+        let dir = std::env::temp_dir().join(format!("session-log-{}", std::process::id()));
+        let log = SessionLog::create(&dir).unwrap();
+        log.append(&record).unwrap();
+        let mut unsolved = record.clone();
+        unsolved.outcome = Outcome::TimedOut;
+        unsolved.moves = None;
+        log.append(&unsolved).unwrap();
+
+        assert_eq!(read(&dir).unwrap(), vec![record, unsolved]);
+        fs::write(dir.join("broken.jsonl"), "{}\n").unwrap();
+        assert!(read(&dir).unwrap_err().contains("line 1"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
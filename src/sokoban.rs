@@ -0,0 +1,190 @@
+use crate::game::{Direction, Position2D};
+use crate::heuristics::manhattan_distance;
+use crate::puzzle::Puzzle;
+use std::collections::HashSet;
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+// A Sokoban level in the usual text format: `#` wall, `@` player, `$` box, `.` goal,
+// `*` box on a goal, `+` player on a goal. Rows go down the screen, so `Up` decreases y.
+#[derive(Clone, Debug)]
+pub struct Sokoban {
+    walls: HashSet<Position2D>,
+    goals: Vec<Position2D>,
+    start: SokobanState,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SokobanState {
+    pub player: Position2D,
+    // Kept sorted so that equal positions hash the same.
+    pub boxes: Vec<Position2D>,
+}
+
+impl Sokoban {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut walls = HashSet::new();
+        let mut goals = vec![];
+        let mut boxes = vec![];
+        let mut player = None;
+
+        for (y, line) in text.lines().enumerate() {
+            for (x, cell) in line.chars().enumerate() {
+                let position = [x as i32, y as i32];
+                match cell {
+                    '#' => {
+                        walls.insert(position);
+                    }
+                    '.' => goals.push(position),
+                    '$' => boxes.push(position),
+                    '*' => {
+                        goals.push(position);
+                        boxes.push(position);
+                    }
+                    '@' | '+' => {
+                        if player.replace(position).is_some() {
+                            return Err(String::from("the level has more than one player"));
+                        }
+                        if cell == '+' {
+                            goals.push(position);
+                        }
+                    }
+                    ' ' | '-' | '_' => {}
+                    _ => return Err(format!("unexpected `{}` at ({}, {})", cell, x, y)),
+                }
+            }
+        }
+
+        let player = player.ok_or_else(|| String::from("the level has no player"))?;
+        if boxes.len() != goals.len() {
+            return Err(format!(
+                "the level has {} boxes but {} goals",
+                boxes.len(),
+                goals.len()
+            ));
+        }
+        boxes.sort();
+
+        Ok(Sokoban {
+            walls,
+            goals,
+            start: SokobanState { player, boxes },
+        })
+    }
+
+    fn is_wall(&self, position: &Position2D) -> bool {
+        self.walls.contains(position)
+    }
+
+    // A box pushed into a corner that is not a goal can never be moved out again.
+    fn is_dead(&self, position: &Position2D) -> bool {
+        if self.goals.contains(position) {
+            return false;
+        }
+        let blocked = |direction: &Direction| self.is_wall(&step(position, direction));
+        (blocked(&Direction::Up) || blocked(&Direction::Down))
+            && (blocked(&Direction::Left) || blocked(&Direction::Right))
+    }
+}
+
+fn step(position: &Position2D, direction: &Direction) -> Position2D {
+    let [dx, dy] = direction.offset();
+    [position[0] + dx, position[1] - dy]
+}
+
+impl Puzzle for Sokoban {
+    type State = SokobanState;
+    type Move = Direction;
+
+    fn initial_state(&self) -> SokobanState {
+        self.start.clone()
+    }
+
+    fn moves(&self, state: &SokobanState) -> Vec<(Direction, SokobanState)> {
+        let mut moves = vec![];
+        for direction in DIRECTIONS {
+            let target = step(&state.player, &direction);
+            if self.is_wall(&target) {
+                continue;
+            }
+
+            let mut boxes = state.boxes.clone();
+            if let Some(index) = boxes.iter().position(|position| *position == target) {
+                let beyond = step(&target, &direction);
+                if self.is_wall(&beyond) || boxes.contains(&beyond) || self.is_dead(&beyond) {
+                    continue;
+                }
+                boxes[index] = beyond;
+                boxes.sort();
+            }
+
+            let next = SokobanState {
+                player: target,
+                boxes,
+            };
+            moves.push((direction, next));
+        }
+        moves
+    }
+
+    fn is_solved(&self, state: &SokobanState) -> bool {
+        state
+            .boxes
+            .iter()
+            .all(|position| self.goals.contains(position))
+    }
+
+    fn heuristic(&self, state: &SokobanState) -> i32 {
+        state
+            .boxes
+            .iter()
+            .map(|position| {
+                self.goals
+                    .iter()
+                    .map(|goal| manhattan_distance(position, goal))
+                    .min()
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::solve;
+
+    #[test]
+    fn test_solves_a_small_level() {
+        let level = Sokoban::parse(
+            "\
+#######
+#     #
+# $ . #
+#@$ . #
+#######",
+        )
+        .unwrap();
+
+        let (moves, _) = solve(&level, 30, None);
+        let moves = moves.unwrap();
+        let mut state = level.initial_state();
+        for direction in &moves {
+            state = level
+                .moves(&state)
+                .into_iter()
+                .find(|(step, _)| step == direction)
+                .unwrap()
+                .1;
+        }
+        assert!(level.is_solved(&state));
+        assert_eq!(moves.len(), 7);
+
+        assert!(Sokoban::parse("#@$#").is_err());
+    }
+}
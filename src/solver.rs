@@ -0,0 +1,382 @@
+//! Options for [`Game::solve_with`](crate::game::Game::solve_with).
+//!
+//! ```
+//! use solver_of_squares::game::Game;
+//! use solver_of_squares::solver::{Algorithm, SolverOptions};
+//! use std::time::Duration;
+//!
+//! let game: Game = serde_yaml::from_str(
+//!     "blocks:\n  - color: red\n    direction: right\n    position: [0, 0]\n    goal: [2, 0]\n",
+//! )
+//! .unwrap();
+//! let options = SolverOptions::new(20)
+//!     .algorithm(Algorithm::AStar)
+//!     .weight(1.5)
+//!     .timeout(Duration::from_secs(5));
+//! let (moves, _) = game.solve_with(&options);
+//! assert_eq!(moves.map(|moves| moves.len()), Some(2));
+//! ```
+
+use crate::game::{Color, Move};
+use crate::search::{CancellationToken, OpenList, Progress, SearchLimits, SearchStats, TieBreak};
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Which search [`Game::solve_with`](crate::game::Game::solve_with) runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Best-first on moves so far plus the weighted heuristic. Fast, but not always optimal.
+    #[default]
+    AStar,
+    /// Expands in order of moves alone, so the solution is as short as possible. Among equally
+    /// short solutions it finds the one with the fewest pushes. Ignores the heuristic and the
+    /// tie-break.
+    BreadthFirst,
+    /// A* that keeps at most [`SolverOptions::max_nodes`] states in memory, dropping the least
+    /// promising ones and regenerating them if needed. Ignores the tie-break and `max_expanded`.
+    SmaStar,
+    /// Weighted A* that reports its first solution and then keeps looking for shorter ones until
+    /// the search space under the bound is exhausted or the timeout is hit. Set the weight above 1
+    /// so the first solution comes quickly. Ignores the tie-break and `max_expanded`.
+    Anytime,
+    /// Iterative-deepening A*: repeated depth-first searches under a rising bound on moves plus
+    /// the heuristic. Uses little memory beyond a table of at most [`SolverOptions::table_size`]
+    /// positions shared between the rounds. Ignores the tie-break and `max_expanded`.
+    IdaStar,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "astar" => Ok(Algorithm::AStar),
+            "breadth-first" => Ok(Algorithm::BreadthFirst),
+            "sma" => Ok(Algorithm::SmaStar),
+            "anytime" => Ok(Algorithm::Anytime),
+            "ida" => Ok(Algorithm::IdaStar),
+            _ => Err(format!(
+                "unknown algorithm `{}`; expected astar, breadth-first, sma, anytime, or ida",
+                s
+            )),
+        }
+    }
+}
+
+/// The estimate of the remaining moves that A* adds to the moves so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Heuristic {
+    /// The sum of each block's Manhattan distance to its goal.
+    #[default]
+    Manhattan,
+    /// No estimate, which turns A* into a uniform-cost search.
+    Zero,
+}
+
+impl FromStr for Heuristic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "manhattan" => Ok(Heuristic::Manhattan),
+            "zero" => Ok(Heuristic::Zero),
+            _ => Err(format!(
+                "unknown heuristic `{}`; expected manhattan or zero",
+                s
+            )),
+        }
+    }
+}
+
+/// Everything that controls a search, built up from [`SolverOptions::new`] with the setters.
+#[derive(Clone, Debug)]
+pub struct SolverOptions {
+    /// Do not expand positions this many moves from the start.
+    pub max_depth: usize,
+    /// Do not expand positions whose path has cost this much. Every move costs one, so this is
+    /// the same as `max_depth` unless the two are set apart.
+    pub max_cost: i32,
+    pub algorithm: Algorithm,
+    pub heuristic: Heuristic,
+    /// The heuristic is multiplied by this before it is added to the moves so far. Above 1 the
+    /// search is greedier: usually faster, with longer solutions.
+    pub weight: f64,
+    pub timeout: Option<Duration>,
+    /// Stop after expanding this many states, with `stats.limit_reached` set.
+    pub max_expanded: Option<usize>,
+    /// Stop [`Algorithm::AStar`] once its open and seen sets are estimated to hold more than this
+    /// many bytes, with `stats.limit_reached` set. `stats.peak_memory` reports the estimate.
+    pub max_memory: Option<usize>,
+    pub tie_break: TieBreak,
+    /// How [`Algorithm::AStar`] holds its open states; [`OpenList::Buckets`] is faster on big
+    /// searches but ignores part of the tie-break.
+    pub open_list: OpenList,
+    pub cancel: Option<CancellationToken>,
+    /// The most states [`Algorithm::SmaStar`] keeps in memory at once.
+    pub max_nodes: usize,
+    /// The most positions [`Algorithm::IdaStar`] remembers between rounds. 0 turns the table off.
+    pub table_size: usize,
+    /// Store with each position only its last move and a link to the position it was made from,
+    /// instead of every move that led to it, and rebuild the solution from the links at the end.
+    /// Saves memory on searches that keep many long paths open, at the cost of a little time.
+    pub compact_paths: bool,
+    /// Where [`Algorithm::AStar`] sends a [`Progress`] report about once a second.
+    pub progress: Option<Sender<Progress>>,
+    /// The most searches [`Game::solve_portfolio`](crate::game::Game::solve_portfolio) runs at
+    /// once. Every other search runs on the calling thread alone. One per core by default.
+    pub threads: usize,
+    // Moves still needed from positions of the normalized puzzle, set by `Game::solve_cached`.
+    pub(crate) known: Option<Arc<HashMap<u64, i32>>>,
+}
+
+impl SolverOptions {
+    pub fn new(max_moves: i32) -> Self {
+        SolverOptions {
+            max_depth: max_moves.max(0) as usize,
+            max_cost: max_moves,
+            algorithm: Algorithm::default(),
+            heuristic: Heuristic::default(),
+            weight: 1.0,
+            timeout: None,
+            max_expanded: None,
+            max_memory: None,
+            tie_break: TieBreak::default(),
+            open_list: OpenList::default(),
+            cancel: None,
+            max_nodes: 1_000_000,
+            table_size: 1_000_000,
+            compact_paths: false,
+            progress: None,
+            threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            known: None,
+        }
+    }
+
+    /// When a search started now has to stop, or `None` if it has no timeout.
+    pub fn deadline(&self) -> Option<Instant> {
+        deadline(self.timeout)
+    }
+
+    /// The timeout, cancellation token, and limits on expansions and memory, for a search
+    /// started now.
+    pub fn search_limits(&self) -> SearchLimits<'_> {
+        SearchLimits {
+            deadline: self.deadline(),
+            cancel: self.cancel.as_ref(),
+            max_expanded: self.max_expanded,
+            max_memory: self.max_memory,
+        }
+    }
+
+    /// Sets both the depth and the cost limit.
+    pub fn max_moves(mut self, max_moves: i32) -> Self {
+        self.max_depth = max_moves.max(0) as usize;
+        self.max_cost = max_moves;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn max_cost(mut self, max_cost: i32) -> Self {
+        self.max_cost = max_cost;
+        self
+    }
+
+    // The searches other than A* take a single bound. Moves cost one each, so the tighter of the
+    // two limits is the one that applies.
+    pub(crate) fn move_bound(&self) -> i32 {
+        self.max_cost
+            .min(i32::try_from(self.max_depth).unwrap_or(i32::MAX))
+    }
+
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    pub fn heuristic(mut self, heuristic: Heuristic) -> Self {
+        self.heuristic = heuristic;
+        self
+    }
+
+    pub fn weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn max_expanded(mut self, max_expanded: usize) -> Self {
+        self.max_expanded = Some(max_expanded);
+        self
+    }
+
+    pub fn max_memory(mut self, max_memory: usize) -> Self {
+        self.max_memory = Some(max_memory);
+        self
+    }
+
+    pub fn tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    pub fn open_list(mut self, open_list: OpenList) -> Self {
+        self.open_list = open_list;
+        self
+    }
+
+    pub fn cancel(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    pub fn table_size(mut self, table_size: usize) -> Self {
+        self.table_size = table_size;
+        self
+    }
+
+    pub fn compact_paths(mut self, compact_paths: bool) -> Self {
+        self.compact_paths = compact_paths;
+        self
+    }
+
+    pub fn progress(mut self, progress: Sender<Progress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+}
+
+/// The instant `timeout` from now, or `None` for no timeout. A timeout too long for the clock to
+/// reach is no timeout either.
+pub fn deadline(timeout: Option<Duration>) -> Option<Instant> {
+    timeout.and_then(|timeout| Instant::now().checked_add(timeout))
+}
+
+/// The configurations `--portfolio` races, with their names: A*, weighted A*, IDA*, and greedy
+/// best-first search. Greedy is A* with the heuristic weighted so heavily that the moves so far
+/// only break ties. Each starts from `base`, so they share its limits, heuristic, timeout, and
+/// threads.
+pub fn portfolio(base: &SolverOptions) -> Vec<(&'static str, SolverOptions)> {
+    let greedy = f64::from(base.max_cost.max(0)) + 1.0;
+    vec![
+        ("A*", base.clone().algorithm(Algorithm::AStar).weight(1.0)),
+        (
+            "weighted A*",
+            base.clone().algorithm(Algorithm::AStar).weight(2.0),
+        ),
+        (
+            "IDA*",
+            base.clone().algorithm(Algorithm::IdaStar).weight(1.0),
+        ),
+        (
+            "greedy",
+            base.clone().algorithm(Algorithm::AStar).weight(greedy),
+        ),
+    ]
+}
+
+/// The moves a search found, each with where its block went and what it pushed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Solution {
+    pub moves: Vec<Move>,
+    /// The puzzle's colors in [`Game::colors`] order, so `block_colors[id.0]` names the block of a
+    /// move's [`ColorId`].
+    ///
+    /// [`Game::colors`]: crate::game::Game::colors
+    /// [`ColorId`]: crate::game::ColorId
+    pub block_colors: Vec<Color>,
+    /// The number of moves.
+    pub length: usize,
+    pub stats: SearchStats,
+}
+
+impl Solution {
+    /// The color of each block moved, in order, as the rest of the API takes moves.
+    pub fn colors(&self) -> Vec<Color> {
+        self.moves
+            .iter()
+            .map(|m| self.block_colors[m.block.0].clone())
+            .collect()
+    }
+}
+
+/// How a search for a solution ended.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SolveOutcome {
+    Solved(Solution),
+    /// Every position within the move bound was searched and none is solved. A higher bound
+    /// may still find a solution.
+    NoSolutionWithinBound,
+    /// Every reachable position was searched and none is solved, so no bound will help.
+    ProvenUnsolvable,
+    /// The search stopped before it could tell.
+    Aborted(AbortReason),
+}
+
+/// Why a search stopped before finding a solution or running out of positions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AbortReason {
+    TimedOut,
+    Cancelled,
+    /// It expanded as many states, or kept as many in memory, as it was allowed.
+    LimitReached,
+}
+
+impl SolveOutcome {
+    /// Classifies how a search ended, given the solution it found if any. See [`Game::outcome`]
+    /// for what the `solve_*` methods return.
+    ///
+    /// [`Game::outcome`]: crate::game::Game::outcome
+    pub fn from_search(solution: Option<Solution>, stats: &SearchStats) -> Self {
+        match solution {
+            Some(solution) => SolveOutcome::Solved(solution),
+            None if stats.timed_out => SolveOutcome::Aborted(AbortReason::TimedOut),
+            None if stats.cancelled => SolveOutcome::Aborted(AbortReason::Cancelled),
+            None if stats.limit_reached => SolveOutcome::Aborted(AbortReason::LimitReached),
+            None if stats.exhausted => SolveOutcome::ProvenUnsolvable,
+            None => SolveOutcome::NoSolutionWithinBound,
+        }
+    }
+
+    pub fn solution(&self) -> Option<&Solution> {
+        match self {
+            SolveOutcome::Solved(solution) => Some(solution),
+            _ => None,
+        }
+    }
+
+    pub fn into_solution(self) -> Option<Solution> {
+        match self {
+            SolveOutcome::Solved(solution) => Some(solution),
+            _ => None,
+        }
+    }
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        Self::new(50)
+    }
+}
@@ -0,0 +1,111 @@
+use crate::bench::csv_field;
+use solver_of_squares::game::Color;
+use solver_of_squares::search::SearchStats;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// How one puzzle of a batch or level pack went, as a row of `--csv`.
+#[derive(Debug, PartialEq)]
+pub struct Row {
+    pub puzzle: String,
+    pub moves: Option<usize>,
+    pub millis: f64,
+    pub expanded: usize,
+    pub generated: usize,
+    /// Which limit stopped the search without a solution: `timeout`, `memory`, or `max_moves`.
+    /// Empty when the puzzle was solved or proven to have no solution.
+    pub limit: &'static str,
+    /// Why the puzzle could not be read, for a puzzle that was not solved at all.
+    pub error: Option<String>,
+}
+
+impl Row {
+    pub fn new(
+        puzzle: String,
+        moves: Option<&[Color]>,
+        stats: &SearchStats,
+        elapsed: Duration,
+    ) -> Self {
+        let limit = match moves {
+            Some(_) => "",
+            None if stats.timed_out => "timeout",
+            None if stats.limit_reached => "memory",
+            None if stats.exhausted => "",
+            None => "max_moves",
+        };
+        Row {
+            puzzle,
+            moves: moves.map(<[Color]>::len),
+            millis: elapsed.as_secs_f64() * 1000.0,
+            expanded: stats.expanded,
+            generated: stats.generated,
+            limit,
+            error: None,
+        }
+    }
+
+    pub fn invalid(puzzle: String, error: String) -> Self {
+        Row {
+            puzzle,
+            moves: None,
+            millis: 0.0,
+            expanded: 0,
+            generated: 0,
+            limit: "",
+            error: Some(error),
+        }
+    }
+}
+
+/// The rows as CSV, with an empty `moves` field when there was no solution.
+pub fn csv(rows: &[Row]) -> String {
+    let mut output = "puzzle,solved,moves,time_ms,expanded,generated,limit,error\n".to_string();
+    for row in rows {
+        let _ = writeln!(
+            output,
+            "{},{},{},{:.3},{},{},{},{}",
+            csv_field(&row.puzzle),
+            row.moves.is_some(),
+            row.moves.map(|moves| moves.to_string()).unwrap_or_default(),
+            row.millis,
+            row.expanded,
+            row.generated,
+            row.limit,
+            csv_field(row.error.as_deref().unwrap_or_default())
+        );
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solver_of_squares::game::{Direction, Game};
+
+    #[test]
+    fn test_rows_say_which_limit_stopped_the_search() {
+        let mut game = Game::new();
+        game.add_block("red".into(), Direction::Right, [0, 0], Some([2, 0]));
+        let (moves, stats) = game.solve_with_stats(5);
+        let solved = Row::new(
+            "red.yaml".into(),
+            moves.as_deref(),
+            &stats,
+            Duration::from_millis(2),
+        );
+        let (moves, stats) = game.solve_with_stats(1);
+        let bounded = Row::new("short".into(), moves.as_deref(), &stats, Duration::ZERO);
+        assert_eq!(bounded.limit, "max_moves");
+        let invalid = Row::invalid("bad.yaml".into(), "line 1, column 2: oops".into());
+
+        let csv = csv(&[solved, bounded, invalid]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[1].starts_with("red.yaml,true,2,2.000,"));
+        assert!(lines[2].starts_with("short,false,,"));
+        assert!(lines[2].ends_with(",max_moves,"));
+        assert_eq!(
+            lines[3],
+            "bad.yaml,false,,0.000,0,0,,\"line 1, column 2: oops\""
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use crate::game::{Direction, Position2D};
+use std::collections::HashMap;
+
+const LINEAR_PARTS: [[[i32; 2]; 2]; 8] = [
+    [[1, 0], [0, 1]],
+    [[0, -1], [1, 0]],
+    [[-1, 0], [0, -1]],
+    [[0, 1], [-1, 0]],
+    [[-1, 0], [0, 1]],
+    [[1, 0], [0, -1]],
+    [[0, 1], [1, 0]],
+    [[0, -1], [-1, 0]],
+];
+
+const LINEAR_NAMES: [&str; 8] = [
+    "as is",
+    "rotated 90° counterclockwise",
+    "rotated 180°",
+    "rotated 90° clockwise",
+    "mirrored left to right",
+    "mirrored top to bottom",
+    "reflected across y = x",
+    "reflected across y = -x",
+];
+
+#[derive(Clone, Debug)]
+pub struct Symmetry {
+    linear: [[i32; 2]; 2],
+    translation: Position2D,
+}
+
+impl Symmetry {
+    pub fn identity() -> Self {
+        Symmetry {
+            linear: LINEAR_PARTS[0],
+            translation: [0, 0],
+        }
+    }
+
+    // The eight rotations and reflections of the grid, without translation, with a description.
+    pub fn rotations_and_reflections() -> impl Iterator<Item = (&'static str, Symmetry)> {
+        LINEAR_NAMES
+            .into_iter()
+            .zip(LINEAR_PARTS)
+            .map(|(name, linear)| {
+                (
+                    name,
+                    Symmetry {
+                        linear,
+                        translation: [0, 0],
+                    },
+                )
+            })
+    }
+
+    // Wrapping, so that cells at the extremes still map one to one for hashing rather than
+    // overflowing.
+    pub fn apply(&self, position: &Position2D) -> Position2D {
+        let [x, y] = self.rotate(position);
+        [
+            x.wrapping_add(self.translation[0]),
+            y.wrapping_add(self.translation[1]),
+        ]
+    }
+
+    // Like `apply`, or `None` where the image is past the largest or smallest coordinate.
+    fn checked_apply(&self, position: &Position2D) -> Option<Position2D> {
+        let row = |row: [i32; 2], translation: i32| {
+            row[0]
+                .checked_mul(position[0])?
+                .checked_add(row[1].checked_mul(position[1])?)?
+                .checked_add(translation)
+        };
+        Some([
+            row(self.linear[0], self.translation[0])?,
+            row(self.linear[1], self.translation[1])?,
+        ])
+    }
+
+    pub fn apply_direction(&self, direction: &Direction) -> Direction {
+        Direction::from_offset(self.rotate(&direction.offset())).unwrap()
+    }
+
+    fn rotate(&self, position: &Position2D) -> Position2D {
+        let m = self.linear;
+        [
+            m[0][0]
+                .wrapping_mul(position[0])
+                .wrapping_add(m[0][1].wrapping_mul(position[1])),
+            m[1][0]
+                .wrapping_mul(position[0])
+                .wrapping_add(m[1][1].wrapping_mul(position[1])),
+        ]
+    }
+}
+
+// `cell_sets` are sets of cells that a symmetry must map onto themselves, such as the goals of a
+// block, any one of which will do.
+pub fn find_symmetries(
+    cell_sets: &[Vec<Position2D>],
+    arrows: &HashMap<Position2D, Direction>,
+) -> Vec<Symmetry> {
+    let static_points: Vec<&Position2D> = cell_sets.iter().flatten().chain(arrows.keys()).collect();
+    let Some(anchor) = static_points.iter().min() else {
+        return vec![Symmetry::identity()];
+    };
+
+    let mut symmetries = vec![];
+
+    for linear in LINEAR_PARTS {
+        let rotation = Symmetry {
+            linear,
+            translation: [0, 0],
+        };
+        // Cells near the largest or smallest coordinates may have no image, and then this is no
+        // symmetry of the board.
+        let Some(rotated_anchor) = static_points
+            .iter()
+            .map(|position| rotation.checked_apply(position))
+            .collect::<Option<Vec<_>>>()
+            .and_then(|rotated| rotated.into_iter().min())
+        else {
+            continue;
+        };
+        let (Some(dx), Some(dy)) = (
+            anchor[0].checked_sub(rotated_anchor[0]),
+            anchor[1].checked_sub(rotated_anchor[1]),
+        ) else {
+            continue;
+        };
+        let symmetry = Symmetry {
+            linear,
+            translation: [dx, dy],
+        };
+
+        let fixes_goals = cell_sets.iter().all(|positions| {
+            positions.iter().all(|position| {
+                symmetry
+                    .checked_apply(position)
+                    .is_some_and(|image| positions.contains(&image))
+            })
+        });
+        let maps_arrows = arrows.iter().all(|(position, direction)| {
+            symmetry.checked_apply(position).is_some_and(|image| {
+                arrows.get(&image) == Some(&symmetry.apply_direction(direction))
+            })
+        });
+
+        if fixes_goals && maps_arrows {
+            symmetries.push(symmetry);
+        }
+    }
+
+    symmetries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_symmetries_of_a_mirrored_board() {
+        let goals = [vec![[0, 0]]];
+        let arrows = HashMap::from([([-1, 2], Direction::Left), ([1, 2], Direction::Right)]);
+
+        let symmetries = find_symmetries(&goals, &arrows);
+        assert_eq!(symmetries.len(), 2);
+
+        let mirror = &symmetries[1];
+        assert_eq!(mirror.apply(&[3, 5]), [-3, 5]);
+        assert_eq!(mirror.apply_direction(&Direction::Left), Direction::Right);
+        assert_eq!(mirror.apply_direction(&Direction::Up), Direction::Up);
+    }
+}
@@ -0,0 +1,234 @@
+//! What happens to a block that moves onto a tile.
+//!
+//! Every special cell of the board is a [`TileEffect`]: the arrows, and the walls, pits, and
+//! teleporters placed with [`Game::add_tile`](crate::game::Game::add_tile) or under `tiles` in a
+//! puzzle file. A push asks the tile on each cell a block enters what happens, so a new kind of
+//! tile only needs its own implementation of the trait.
+//!
+//! ```
+//! use solver_of_squares::game::{Direction, Game};
+//! use solver_of_squares::tiles::Wall;
+//! use std::sync::Arc;
+//!
+//! let mut game = Game::new();
+//! game.add_block("red".into(), Direction::Right, [0, 0], Some([2, 0]));
+//! assert!(game.solve_optimal(5).is_some());
+//!
+//! game.add_tile([1, 0], Arc::new(Wall));
+//! assert_eq!(game.solve_optimal(5), None);
+//! ```
+//!
+//! The heuristic measures straight distances, so on a board with teleporters it can overestimate
+//! and A* may return a longer solution than needed. Breadth-first search still finds the shortest.
+
+use crate::game::{Direction, Position2D};
+use crate::rules::Ruleset;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// A block entering a tile, as the tile sees it.
+pub struct Entering<'a> {
+    pub color: &'a str,
+    /// The way the block is moving.
+    pub direction: &'a Direction,
+    /// Whether another block pushed this one here, rather than it being the block moved.
+    pub pushed: bool,
+    /// Whether an earlier move used the tile up.
+    pub spent: bool,
+    pub rules: &'a Ruleset,
+}
+
+/// What a tile does to a block entering it. The default lets the block pass.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Entry {
+    /// The block may not enter, so the move is not allowed.
+    pub blocked: bool,
+    /// The block turns to face this way, as on an arrow. Whether a pushed block turns, and
+    /// whether the push follows it, is up to [`ArrowPush`](crate::rules::ArrowPush).
+    pub turn: Option<Direction>,
+    /// The block lands here instead, pushing whatever it finds there. The tile there, if any,
+    /// does nothing to it.
+    pub teleport: Option<Position2D>,
+    /// The tile is used up, which it will see as [`Entering::spent`] from then on.
+    pub consume: bool,
+}
+
+/// A kind of tile. Implementations are shared by every search state of a puzzle, and so across
+/// threads.
+pub trait TileEffect: Debug + Send + Sync {
+    fn on_enter(&self, entering: &Entering) -> Entry;
+
+    /// How the tile is written under `tiles` in a puzzle file.
+    fn spec(&self) -> TileSpec;
+
+    /// How the tile is drawn on a text board.
+    fn symbol(&self, spent: bool) -> char;
+
+    /// The same tile on a board whose cells and directions were moved by `position` and
+    /// `direction`, for a puzzle that has been translated, rotated, or reflected.
+    fn mapped(
+        &self,
+        position: &dyn Fn(&Position2D) -> Position2D,
+        direction: &dyn Fn(&Direction) -> Direction,
+    ) -> Arc<dyn TileEffect>;
+}
+
+/// A tile as written in a puzzle file. Only the kinds in this module can be read back. Arrows
+/// are usually written under `arrows` instead.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase", deny_unknown_fields)]
+pub enum TileSpec {
+    Arrow { direction: Direction },
+    Wall,
+    Pit,
+    Teleporter { to: Position2D },
+}
+
+impl TileSpec {
+    pub fn build(&self) -> Arc<dyn TileEffect> {
+        match self {
+            TileSpec::Arrow { direction } => Arc::new(Arrow(direction.clone())),
+            TileSpec::Wall => Arc::new(Wall),
+            TileSpec::Pit => Arc::new(Pit),
+            TileSpec::Teleporter { to } => Arc::new(Teleporter { to: *to }),
+        }
+    }
+}
+
+/// Turns a block the way it points, until a block uses it up under
+/// [`Ruleset::consume_arrows`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Arrow(pub Direction);
+
+impl TileEffect for Arrow {
+    fn on_enter(&self, entering: &Entering) -> Entry {
+        if entering.spent {
+            return Entry::default();
+        }
+        Entry {
+            turn: Some(self.0.clone()),
+            consume: entering.rules.consume_arrows,
+            ..Entry::default()
+        }
+    }
+
+    fn spec(&self) -> TileSpec {
+        TileSpec::Arrow {
+            direction: self.0.clone(),
+        }
+    }
+
+    fn symbol(&self, spent: bool) -> char {
+        if spent {
+            '.'
+        } else {
+            self.0.symbol()
+        }
+    }
+
+    fn mapped(
+        &self,
+        _: &dyn Fn(&Position2D) -> Position2D,
+        direction: &dyn Fn(&Direction) -> Direction,
+    ) -> Arc<dyn TileEffect> {
+        Arc::new(Arrow(direction(&self.0)))
+    }
+}
+
+/// No block may enter, pushed or not.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Wall;
+
+impl TileEffect for Wall {
+    fn on_enter(&self, _: &Entering) -> Entry {
+        Entry {
+            blocked: true,
+            ..Entry::default()
+        }
+    }
+
+    fn spec(&self) -> TileSpec {
+        TileSpec::Wall
+    }
+
+    fn symbol(&self, _: bool) -> char {
+        '%'
+    }
+
+    fn mapped(
+        &self,
+        _: &dyn Fn(&Position2D) -> Position2D,
+        _: &dyn Fn(&Direction) -> Direction,
+    ) -> Arc<dyn TileEffect> {
+        Arc::new(Wall)
+    }
+}
+
+/// Covered until the first block enters it. The cover breaks under that block, and from then on
+/// no block may enter, though the one on it can still leave.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pit;
+
+impl TileEffect for Pit {
+    fn on_enter(&self, entering: &Entering) -> Entry {
+        Entry {
+            blocked: entering.spent,
+            consume: true,
+            ..Entry::default()
+        }
+    }
+
+    fn spec(&self) -> TileSpec {
+        TileSpec::Pit
+    }
+
+    fn symbol(&self, spent: bool) -> char {
+        if spent {
+            '*'
+        } else {
+            '_'
+        }
+    }
+
+    fn mapped(
+        &self,
+        _: &dyn Fn(&Position2D) -> Position2D,
+        _: &dyn Fn(&Direction) -> Direction,
+    ) -> Arc<dyn TileEffect> {
+        Arc::new(Pit)
+    }
+}
+
+/// Sends a block entering it on to `to`, facing the same way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Teleporter {
+    pub to: Position2D,
+}
+
+impl TileEffect for Teleporter {
+    fn on_enter(&self, _: &Entering) -> Entry {
+        Entry {
+            teleport: Some(self.to),
+            ..Entry::default()
+        }
+    }
+
+    fn spec(&self) -> TileSpec {
+        TileSpec::Teleporter { to: self.to }
+    }
+
+    fn symbol(&self, _: bool) -> char {
+        '@'
+    }
+
+    fn mapped(
+        &self,
+        position: &dyn Fn(&Position2D) -> Position2D,
+        _: &dyn Fn(&Direction) -> Direction,
+    ) -> Arc<dyn TileEffect> {
+        Arc::new(Teleporter {
+            to: position(&self.to),
+        })
+    }
+}
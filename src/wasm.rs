@@ -0,0 +1,49 @@
+use crate::game::{Color, Game};
+use crate::parse::ParseError;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+const DEFAULT_MAX_MOVES: i32 = 50;
+
+#[derive(Serialize)]
+struct Solution {
+    solved: bool,
+    moves: Vec<Color>,
+    expanded: usize,
+}
+
+#[wasm_bindgen]
+pub fn solve(puzzle_json: &str) -> Result<String, JsError> {
+    solve_within(puzzle_json, DEFAULT_MAX_MOVES)
+}
+
+#[wasm_bindgen(js_name = solveWithin)]
+pub fn solve_within(puzzle_json: &str, max_moves: i32) -> Result<String, JsError> {
+    Ok(solve_json(puzzle_json, max_moves)?)
+}
+
+fn solve_json(puzzle_json: &str, max_moves: i32) -> Result<String, ParseError> {
+    let game = Game::from_json_str(puzzle_json)?;
+    let (moves, stats) = game.solve_with_timeout(max_moves, None);
+    Ok(serde_json::to_string(&Solution {
+        solved: moves.is_some(),
+        moves: moves.unwrap_or_default(),
+        expanded: stats.expanded,
+    })
+    .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_json() {
+        let puzzle = r#"{"blocks": [{"color": "red", "direction": "right", "position": [0, 0], "goal": [2, 0]}]}"#;
+        assert_eq!(
+            solve_json(puzzle, 10).unwrap(),
+            r#"{"solved":true,"moves":["red","red"],"expanded":2}"#
+        );
+        assert!(solve_json(r#"{"walls": []}"#, 10).is_err());
+    }
+}